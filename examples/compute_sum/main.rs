@@ -0,0 +1,40 @@
+use std::mem::size_of;
+
+use cogrrs::{anyhow::Result, main_loop_run, CoGr, Game, Input};
+
+const VALUE_COUNT: u32 = 1 << 16;
+
+// fills a buffer on the gpu, reads it back and checks its sum on the cpu. there is nothing to show, so it exits
+// before the first frame
+struct ComputeSum;
+
+impl Game for ComputeSum {
+    fn on_init(gpu: &mut CoGr) -> Result<Self> {
+        let values = gpu.buffer("values", VALUE_COUNT as usize, size_of::<u32>());
+        let mut fill = gpu.pipeline("examples/compute_sum/fill.wgsl", "main", &[&values])?;
+
+        let mut encoder = gpu.get_encoder()?;
+        encoder.dispatch_1d(&mut fill, VALUE_COUNT, &[&values])?;
+        let values = encoder.read_buffer::<u32>(&values)?;
+        drop(encoder);
+
+        let sum = values.iter().map(|&value| value as u64).sum::<u64>();
+        let expected = VALUE_COUNT as u64 * (VALUE_COUNT as u64 - 1) / 2;
+        assert_eq!(sum, expected, "the gpu filled the buffer wrong");
+        println!("sum of {} values is {}", values.len(), sum);
+        std::process::exit(0);
+    }
+
+    fn on_render(&mut self, _gpu: &mut CoGr, _input: &Input, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_tick(&mut self, _gpu: &mut CoGr, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    main_loop_run::<ComputeSum>(10f32)?;
+    Ok(())
+}
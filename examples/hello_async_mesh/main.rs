@@ -0,0 +1,177 @@
+use std::mem::size_of;
+
+use cogrrs::{
+    anyhow::Result,
+    bytemuck::{Pod, Zeroable},
+    egui,
+    glam::{vec2, vec3, Vec3},
+    main_loop_run, BackgroundTask, CoGr, Game, Input, Pipeline, ResourceHandle, TextureFormat,
+    TextureRes,
+};
+
+// the mesh code of the ray tracer example
+#[path = "../ray_tracer/bvh.rs"]
+#[allow(dead_code)]
+mod bvh;
+
+use bvh::{BVHNode, Bvh, Ray, Triangle, MISS};
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct CameraConstants {
+    position: [f32; 4],
+    direction: [f32; 4],
+    side: [f32; 4],
+    up: [f32; 4],
+    screen_dimensions: [u32; 2],
+    highlight: u32,
+    _padding: u32,
+}
+
+// the camera ray through a pixel, has to match the one in trace.wgsl
+fn camera_ray(camera: &CameraConstants, pixel: [f32; 2]) -> Ray {
+    let dimensions = vec2(
+        camera.screen_dimensions[0] as f32,
+        camera.screen_dimensions[1] as f32,
+    );
+    let uv = (vec2(pixel[0], pixel[1]) - dimensions * 0.5) / dimensions.y;
+    let [direction, side, up] =
+        [camera.direction, camera.side, camera.up].map(|v| Vec3::from_slice(&v[..3]));
+    let direction = (direction * 1.5 + side * uv.x - up * uv.y).normalize();
+    Ray::new(Vec3::from_slice(&camera.position[..3]), direction)
+}
+
+// the gpu side of the mesh, made once the background thread is done with it
+struct LoadedMesh {
+    output: ResourceHandle,
+    triangles: ResourceHandle,
+    nodes: ResourceHandle,
+    pipeline: Pipeline,
+    // kept on the cpu to pick the triangle under the mouse
+    bvh: Bvh,
+}
+
+struct HelloAsyncMesh {
+    loading: BackgroundTask<Bvh>,
+    mesh: Option<LoadedMesh>,
+    time: f32,
+}
+
+impl LoadedMesh {
+    fn new(gpu: &mut CoGr, bvh: Bvh) -> Result<Self> {
+        let output = gpu.texture("output", TextureRes::FullRes, TextureFormat::Rgba8Unorm);
+        let triangles = gpu.buffer("triangles", bvh.triangles.len(), size_of::<Triangle>());
+        let nodes = gpu.buffer("bvh_nodes", bvh.bvh_nodes.len(), size_of::<BVHNode>());
+        let pipeline = gpu.pipeline(
+            "examples/hello_async_mesh/trace.wgsl",
+            "main",
+            &[&output, &triangles, &nodes],
+        )?;
+        {
+            let mut encoder = gpu.get_encoder()?;
+            encoder.set_buffer_data(&triangles, &bvh.triangles)?;
+            encoder.set_buffer_data(&nodes, &bvh.bvh_nodes)?;
+        }
+        Ok(LoadedMesh {
+            output,
+            triangles,
+            nodes,
+            pipeline,
+            bvh,
+        })
+    }
+}
+
+impl Game for HelloAsyncMesh {
+    fn on_init(_gpu: &mut CoGr) -> Result<Self> {
+        // the window opens right away while the dragon loads on its own thread
+        let loading = BackgroundTask::spawn("load_dragon", |progress| {
+            let mut bvh = Bvh::new("examples/ray_tracer/dragon.obj");
+            bvh.normalize_to_unit_cube();
+            bvh.build_bvh_with_progress(|fraction| {
+                progress.set(fraction);
+                true
+            });
+            bvh
+        })?;
+        Ok(HelloAsyncMesh {
+            loading,
+            mesh: None,
+            time: 0.0,
+        })
+    }
+
+    fn on_render(&mut self, gpu: &mut CoGr, input: &Input, dt: f32) -> Result<()> {
+        self.time += dt;
+        if let Some(bvh) = self.loading.poll()? {
+            self.mesh = Some(LoadedMesh::new(gpu, bvh)?);
+        }
+        let (width, height) = gpu.render_size();
+
+        let mut hovered = None;
+        let mut encoder = gpu.get_encoder_for_draw()?;
+        if let Some(mesh) = &mut self.mesh {
+            let position = vec3(self.time.sin(), 0.3, self.time.cos()) * 1.5;
+            let direction = -position.normalize();
+            let side = direction.cross(vec3(0.0, 1.0, 0.0)).normalize();
+            let up = side.cross(direction);
+            let mut camera = CameraConstants {
+                position: position.extend(0.0).into(),
+                direction: direction.extend(0.0).into(),
+                side: side.extend(0.0).into(),
+                up: up.extend(0.0).into(),
+                screen_dimensions: [width, height],
+                highlight: u32::MAX,
+                _padding: 0,
+            };
+            let mut ray = camera_ray(&camera, input.mouse_state.mouse_location);
+            mesh.bvh.intersect(&mut ray);
+            if ray.t < MISS {
+                camera.highlight = ray.prim;
+                hovered = Some(ray.prim);
+            }
+            let work_groups = encoder.work_groups_for_texture(&mut mesh.pipeline, &mesh.output)?;
+            encoder.dispatch_pipeline_with_constants(
+                &mut mesh.pipeline,
+                work_groups,
+                &[&mesh.output, &mesh.triangles, &mesh.nodes],
+                &camera,
+            )?;
+            encoder.to_screen(&mesh.output)?;
+        }
+
+        let progress = self.loading.progress();
+        let triangle_count = self.mesh.as_ref().map(|mesh| mesh.bvh.triangles.len());
+        encoder.draw_ui(|ctx| {
+            egui::Window::new("mesh").show(ctx, |ui| {
+                ui.label(format!("fps: {}", 1f32 / dt));
+                match triangle_count {
+                    Some(count) => {
+                        ui.label(format!("{} triangles", count));
+                        if let Some(triangle) = hovered {
+                            ui.label(format!("hovering triangle {}", triangle));
+                        }
+                    }
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("building bvh");
+                        });
+                        ui.add(egui::ProgressBar::new(progress).show_percentage());
+                    }
+                }
+            });
+        })?;
+
+        Ok(())
+    }
+
+    fn on_tick(&mut self, _gpu: &mut CoGr, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    main_loop_run::<HelloAsyncMesh>(10f32)?;
+    Ok(())
+}
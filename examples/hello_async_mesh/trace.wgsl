@@ -0,0 +1,126 @@
+struct Triangle {
+    p0: vec4<f32>,
+    p1: vec4<f32>,
+    p2: vec4<f32>,
+}
+
+// same layout as BVHNode in bvh.rs, leaves have a count and start at triangle left_first, other nodes have their
+// children at left_first and left_first + 1
+struct BvhNode {
+    minx: f32,
+    miny: f32,
+    minz: f32,
+    maxx: f32,
+    maxy: f32,
+    maxz: f32,
+    left_first: i32,
+    count: i32,
+}
+
+struct Camera {
+    position: vec4<f32>,
+    direction: vec4<f32>,
+    side: vec4<f32>,
+    up: vec4<f32>,
+    screen_dimensions: vec2<u32>,
+    // the triangle under the mouse, picked on the cpu with Bvh::intersect
+    highlight: u32,
+}
+
+@group(0) @binding(0)
+var output: texture_storage_2d<rgba8unorm, write>;
+@group(0) @binding(1)
+var<storage, read> triangles: array<Triangle>;
+@group(0) @binding(2)
+var<storage, read> nodes: array<BvhNode>;
+
+var<push_constant> camera: Camera;
+
+// distance along the ray to the box, or a miss when it's behind the ray or further than closest
+fn intersect_node(node: BvhNode, origin: vec3<f32>, inverse_direction: vec3<f32>, closest: f32) -> f32 {
+    let t0 = (vec3<f32>(node.minx, node.miny, node.minz) - origin) * inverse_direction;
+    let t1 = (vec3<f32>(node.maxx, node.maxy, node.maxz) - origin) * inverse_direction;
+    let t_enter = max(max(min(t0.x, t1.x), min(t0.y, t1.y)), min(t0.z, t1.z));
+    let t_exit = min(min(max(t0.x, t1.x), max(t0.y, t1.y)), max(t0.z, t1.z));
+    if (t_enter > t_exit || t_exit < 0.0 || t_enter > closest) {
+        return 1e30;
+    }
+    return t_enter;
+}
+
+// moller trumbore, returns the distance to the triangle or a miss
+fn intersect_triangle(triangle: Triangle, origin: vec3<f32>, direction: vec3<f32>) -> f32 {
+    let edge1 = triangle.p1.xyz - triangle.p0.xyz;
+    let edge2 = triangle.p2.xyz - triangle.p0.xyz;
+    let h = cross(direction, edge2);
+    let determinant = dot(edge1, h);
+    if (abs(determinant) < 1e-8) {
+        return 1e30;
+    }
+    let f = 1.0 / determinant;
+    let s = origin - triangle.p0.xyz;
+    let u = f * dot(s, h);
+    let q = cross(s, edge1);
+    let v = f * dot(direction, q);
+    if (u < 0.0 || v < 0.0 || u + v > 1.0) {
+        return 1e30;
+    }
+    let t = f * dot(edge2, q);
+    if (t < 1e-5) {
+        return 1e30;
+    }
+    return t;
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= camera.screen_dimensions)) {
+        return;
+    }
+    let dimensions = vec2<f32>(camera.screen_dimensions);
+    let uv = (vec2<f32>(id.xy) - dimensions * 0.5) / dimensions.y;
+    let origin = camera.position.xyz;
+    let direction = normalize(camera.direction.xyz * 1.5 + camera.side.xyz * uv.x - camera.up.xyz * uv.y);
+    let inverse_direction = 1.0 / direction;
+
+    var closest = 1e30;
+    var hit = 0u;
+    var stack: array<u32, 64>;
+    var stack_size = 1u;
+    stack[0] = 0u;
+    while (stack_size > 0u) {
+        stack_size -= 1u;
+        let node = nodes[stack[stack_size]];
+        if (intersect_node(node, origin, inverse_direction, closest) >= 1e30) {
+            continue;
+        }
+        if (node.count > 0) {
+            for (var i = node.left_first; i < node.left_first + node.count; i++) {
+                let t = intersect_triangle(triangles[i], origin, direction);
+                if (t < closest) {
+                    closest = t;
+                    hit = u32(i);
+                }
+            }
+        } else if (stack_size < 63u) {
+            stack[stack_size] = u32(node.left_first);
+            stack[stack_size + 1u] = u32(node.left_first + 1);
+            stack_size += 2u;
+        }
+    }
+
+    var color = mix(vec3<f32>(0.1, 0.1, 0.15), vec3<f32>(0.4, 0.5, 0.7), uv.y * -0.5 + 0.5);
+    if (closest < 1e30) {
+        let triangle = triangles[hit];
+        var normal = normalize(cross(triangle.p1.xyz - triangle.p0.xyz, triangle.p2.xyz - triangle.p0.xyz));
+        if (dot(normal, direction) > 0.0) {
+            normal = -normal;
+        }
+        let light = max(dot(normal, normalize(vec3<f32>(0.5, 1.0, 0.3))), 0.0) * 0.8 + 0.2;
+        color = vec3<f32>(0.8, 0.75, 0.7) * light;
+        if (hit == camera.highlight) {
+            color = vec3<f32>(1.0, 0.3, 0.1) * light;
+        }
+    }
+    textureStore(output, id.xy, vec4<f32>(color, 1.0));
+}
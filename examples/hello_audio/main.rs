@@ -0,0 +1,78 @@
+use cogrrs::{
+    anyhow::Result,
+    audio::AudioSpectrum,
+    bytemuck::{Pod, Zeroable},
+    div_ceil, egui, main_loop_run, CoGr, Game, Input, Pipeline, ResourceHandle, TextureFormat,
+    TextureRes,
+};
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct AudioConstants {
+    bands: [f32; 16],
+    gain: f32,
+    time: f32,
+    _padding: [f32; 2],
+}
+
+// draws the spectrum of the default microphone or line in, run with --features audio
+struct HelloAudio {
+    audio: AudioSpectrum,
+    output: ResourceHandle,
+    pipeline: Pipeline,
+    gain: f32,
+}
+
+impl Game for HelloAudio {
+    fn on_init(gpu: &mut CoGr) -> Result<Self> {
+        let audio = AudioSpectrum::new()?;
+        let output = gpu.texture("output", TextureRes::FullRes, TextureFormat::Rgba8Unorm);
+        let pipeline = gpu.pipeline("examples/hello_audio/spectrum.wgsl", "main", &[&output])?;
+        Ok(HelloAudio {
+            audio,
+            output,
+            pipeline,
+            gain: 20.0,
+        })
+    }
+
+    fn on_render(&mut self, gpu: &mut CoGr, _input: &Input, _dt: f32) -> Result<()> {
+        self.audio.update();
+        let mut bands = [0.0; 16];
+        bands.copy_from_slice(&self.audio.bands(16));
+        let constants = AudioConstants {
+            bands,
+            gain: self.gain,
+            time: gpu.time(),
+            _padding: [0.0; 2],
+        };
+
+        let (width, height) = gpu.render_size();
+        let mut encoder = gpu.get_encoder_for_draw()?;
+        encoder.dispatch_pipeline_with_constants(
+            &mut self.pipeline,
+            (div_ceil(width, 16), div_ceil(height, 16), 1),
+            &[&self.output],
+            &constants,
+        )?;
+        encoder.to_screen(&self.output)?;
+        encoder.draw_ui(|ctx| {
+            egui::Window::new("audio").show(ctx, |ui| {
+                ui.label(format!("sample rate: {}hz", self.audio.sample_rate()));
+                ui.add(egui::Slider::new(&mut self.gain, 1.0..=100.0).text("gain"));
+                ui.add(egui::Slider::new(&mut self.audio.smoothing, 0.0..=0.99).text("smoothing"));
+            });
+        })?;
+
+        Ok(())
+    }
+
+    fn on_tick(&mut self, _gpu: &mut CoGr, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    main_loop_run::<HelloAudio>(10f32)?;
+    Ok(())
+}
@@ -0,0 +1,35 @@
+struct AudioConstants {
+    // 16 band magnitudes, packed in vec4s because arrays of scalars get a 16 byte stride
+    bands: array<vec4<f32>, 4>,
+    gain: f32,
+    time: f32,
+}
+
+@group(0) @binding(0)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+var<push_constant> constants: AudioConstants;
+
+fn band(index: u32) -> f32 {
+    return constants.bands[index / 4u][index % 4u];
+}
+
+// a bar per band over a background that pulses with the bass
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let uv = vec2<f32>(id.xy) / vec2<f32>(size);
+    let index = min(u32(uv.x * 16.0), 15u);
+    let level = clamp(band(index) * constants.gain, 0.0, 1.0);
+    let bass = clamp(band(0u) * constants.gain, 0.0, 1.0);
+
+    let hue = f32(index) / 16.0 + constants.time * 0.1;
+    let bar_color = 0.5 + 0.5 * cos(6.2831 * (hue + vec3<f32>(0.0, 0.33, 0.67)));
+    let background = vec3<f32>(0.05, 0.02, 0.1) * (1.0 + 4.0 * bass);
+    let in_bar = 1.0 - uv.y < level && fract(uv.x * 16.0) > 0.1;
+    let color = select(background, bar_color, in_bar);
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(color, 1.0));
+}
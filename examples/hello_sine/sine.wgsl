@@ -1,23 +1,19 @@
 @group(0) @binding(0)
-var to_draw_texture: texture_storage_2d<f32, write>;
-@group(0) @binding(1)
-var<uniform> gpu_data: GpuData;
-
-struct GpuData{
-    f32 time;
-    u32 width;
-    u32 height;
-}
+var to_draw_texture: texture_storage_2d<rgba8unorm, write>;
 
 @compute @workgroup_size(16, 16)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    u32 x = global_invocation_id.x;
-    u32 y = global_invocation_id.y;
-    Vec3<i32> pos = Vec3<i32>(x,y);
+    if (any(global_id.xy >= sketch.resolution)) {
+        return;
+    }
+    let x = f32(global_id.x);
+    let y = f32(global_id.y);
+    let width = f32(sketch.resolution.x);
+    let height = f32(sketch.resolution.y);
 
-    f32 val = sin(float(x * 5) / width + time) / 2 + 0.5; // calulate sin value at certain x
-    bool color = val * height < y + 1 && val * height > y - 1;   // the pixel has to be colored if it is at most 1 pixel away from the sin value
-    
-    textureStore(to_draw_texture, pos, vec4(color, 0, 0, 1));
-    return;
-}
\ No newline at end of file
+    // calculate the sin value at x, the pixel is colored if it is at most 1 pixel away from it
+    let val = sin(x * 5.0 / width + sketch.time) / 2.0 + 0.5;
+    let color = f32(abs(val * height - y) < 1.0);
+
+    textureStore(to_draw_texture, global_id.xy, vec4<f32>(color, 0.0, 0.0, 1.0));
+}
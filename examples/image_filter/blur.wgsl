@@ -0,0 +1,35 @@
+struct BlurConstants {
+    radius: u32,
+    horizontal: u32,
+}
+
+@group(0) @binding(0)
+var input: texture_storage_2d<rgba8unorm, read>;
+@group(0) @binding(1)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+var<push_constant> constants: BlurConstants;
+
+// one pass of a separable gaussian blur, the input gets resampled when it has a different size than the output
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let output_size = textureDimensions(output);
+    if (id.x >= output_size.x || id.y >= output_size.y) {
+        return;
+    }
+    let input_size = vec2<i32>(textureDimensions(input));
+    let center = vec2<i32>(vec2<f32>(id.xy) * vec2<f32>(input_size) / vec2<f32>(output_size));
+    let step = select(vec2<i32>(0, 1), vec2<i32>(1, 0), constants.horizontal == 1u);
+    let radius = i32(constants.radius);
+    let sigma = max(f32(radius) / 2.0, 0.5);
+
+    var sum = vec4<f32>(0.0);
+    var weight_sum = 0.0;
+    for (var i = -radius; i <= radius; i += 1) {
+        let weight = exp(-f32(i * i) / (2.0 * sigma * sigma));
+        let position = clamp(center + step * i, vec2<i32>(0), input_size - 1);
+        sum += textureLoad(input, position) * weight;
+        weight_sum += weight;
+    }
+    textureStore(output, vec2<i32>(id.xy), sum / weight_sum);
+}
@@ -0,0 +1,132 @@
+use cogrrs::{
+    anyhow::Result,
+    bytemuck::{Pod, Zeroable},
+    egui, main_loop_run, CoGr, Game, Input, Pipeline, ResourceHandle, TextureFormat, TextureRes,
+};
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct BlurConstants {
+    radius: u32,
+    horizontal: u32,
+}
+
+// texels per side of the color grading lut
+const LUT_SIZE: u32 = 16;
+
+// a warm film look: lifted blacks, teal shadows and orange highlights, as rgba8 texels of a 3d lut
+fn film_lut() -> Vec<[u8; 4]> {
+    (0..LUT_SIZE * LUT_SIZE * LUT_SIZE)
+        .map(|i| {
+            let [r, g, b] = [
+                i % LUT_SIZE,
+                i / LUT_SIZE % LUT_SIZE,
+                i / (LUT_SIZE * LUT_SIZE),
+            ]
+            .map(|c| c as f32 / (LUT_SIZE - 1) as f32);
+            let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+            let tint = [
+                1.0 + 0.15 * (luminance - 0.5),
+                1.0,
+                1.0 - 0.2 * (luminance - 0.5),
+            ];
+            let [r, g, b] = [r, g, b]
+                .map(|c| 0.05 + 0.9 * c)
+                .map(|c| c * c * (3.0 - 2.0 * c));
+            let [r, g, b] =
+                [r * tint[0], g * tint[1], b * tint[2]].map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8);
+            [r, g, b, 255]
+        })
+        .collect()
+}
+
+// blurs an image at half resolution with a horizontal and a vertical pass, ping-ponging between two textures
+struct ImageFilter {
+    image: ResourceHandle,
+    ping: ResourceHandle,
+    pong: ResourceHandle,
+    horizontal_pipeline: Pipeline,
+    vertical_pipeline: Pipeline,
+    radius: u32,
+    lut: ResourceHandle,
+    grade: bool,
+}
+
+impl Game for ImageFilter {
+    fn on_init(gpu: &mut CoGr) -> Result<Self> {
+        let image = gpu.texture_from_image("image", "examples/image_filter/image.png")?;
+        let ping = gpu.texture("ping", TextureRes::HalfRes, TextureFormat::Rgba8Unorm);
+        let pong = gpu.texture("pong", TextureRes::HalfRes, TextureFormat::Rgba8Unorm);
+        let horizontal_pipeline =
+            gpu.pipeline("examples/image_filter/blur.wgsl", "main", &[&image, &ping])?;
+        let vertical_pipeline =
+            gpu.pipeline("examples/image_filter/blur.wgsl", "main", &[&ping, &pong])?;
+        let lut = gpu.texture_sampled(
+            "lut",
+            TextureRes::Custom(LUT_SIZE, LUT_SIZE, LUT_SIZE),
+            TextureFormat::Rgba8Unorm,
+        );
+        {
+            let mut encoder = gpu.get_encoder()?;
+            encoder.set_texture_data(&lut, film_lut())?;
+        }
+        gpu.validate()?;
+        Ok(ImageFilter {
+            image,
+            ping,
+            pong,
+            horizontal_pipeline,
+            vertical_pipeline,
+            radius: 8,
+            lut,
+            grade: true,
+        })
+    }
+
+    fn on_render(&mut self, gpu: &mut CoGr, _input: &Input, dt: f32) -> Result<()> {
+        let mut encoder = gpu.get_encoder_for_draw()?;
+        // the passes write half res textures, so they are dispatched over those instead of the screen
+        let work_groups =
+            encoder.work_groups_for_texture(&mut self.horizontal_pipeline, &self.ping)?;
+        encoder.dispatch_pipeline_with_constants(
+            &mut self.horizontal_pipeline,
+            work_groups,
+            &[&self.image, &self.ping],
+            &BlurConstants {
+                radius: self.radius,
+                horizontal: 1,
+            },
+        )?;
+        encoder.dispatch_pipeline_with_constants(
+            &mut self.vertical_pipeline,
+            work_groups,
+            &[&self.ping, &self.pong],
+            &BlurConstants {
+                radius: self.radius,
+                horizontal: 0,
+            },
+        )?;
+        match self.grade {
+            true => encoder.to_screen_with_lut(&self.pong, &self.lut)?,
+            false => encoder.to_screen(&self.pong)?,
+        }
+        encoder.draw_ui(|ctx| {
+            egui::Window::new("blur").show(ctx, |ui| {
+                ui.label(format!("fps: {}", 1f32 / dt));
+                ui.add(egui::Slider::new(&mut self.radius, 0..=32).text("radius"));
+                ui.checkbox(&mut self.grade, "color grading");
+            });
+        })?;
+
+        Ok(())
+    }
+
+    fn on_tick(&mut self, _gpu: &mut CoGr, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    main_loop_run::<ImageFilter>(10f32)?;
+    Ok(())
+}
@@ -0,0 +1,51 @@
+struct Volume {
+    size: vec3<u32>,
+}
+
+@group(0) @binding(0)
+var<storage, read_write> cells: array<u32>;
+@group(0) @binding(1)
+var<storage, read_write> next: array<u32>;
+
+var<push_constant> volume: Volume;
+
+fn index(cell: vec3<u32>) -> u32 {
+    return cell.x + (cell.y + cell.z * volume.size.y) * volume.size.x;
+}
+
+// the volume wraps around at the edges
+fn alive(cell: vec3<i32>) -> u32 {
+    let size = vec3<i32>(volume.size);
+    return cells[index(vec3<u32>((cell + size) % size))];
+}
+
+// 5766 rule: a live cell survives with 5 to 7 live neighbours, a dead one is born with exactly 6
+@compute @workgroup_size(4, 4, 4)
+fn update(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= volume.size)) {
+        return;
+    }
+    let cell = vec3<i32>(id);
+    var neighbours = 0u;
+    for (var z = -1; z <= 1; z++) {
+        for (var y = -1; y <= 1; y++) {
+            for (var x = -1; x <= 1; x++) {
+                if (x != 0 || y != 0 || z != 0) {
+                    neighbours += alive(cell + vec3<i32>(x, y, z));
+                }
+            }
+        }
+    }
+    let was_alive = cells[index(id)] == 1u;
+    let survives = was_alive && neighbours >= 5u && neighbours <= 7u;
+    let born = !was_alive && neighbours == 6u;
+    next[index(id)] = u32(survives || born);
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn copy_back(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= volume.size)) {
+        return;
+    }
+    cells[index(id)] = next[index(id)];
+}
@@ -0,0 +1,153 @@
+use std::mem::size_of;
+
+use cogrrs::{
+    anyhow::Result,
+    bytemuck::{Pod, Zeroable},
+    div_ceil, egui, main_loop_run, CoGr, Game, Input, Pipeline, ResourceHandle, TextureFormat,
+    TextureRes,
+};
+
+// cells per side of the volume
+const SIZE: u32 = 64;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct RenderConstants {
+    size: [u32; 3],
+    angle: f32,
+    screen_dimensions: [u32; 2],
+    _padding: [u32; 2],
+}
+
+struct Life3d {
+    cells: ResourceHandle,
+    next: ResourceHandle,
+    output: ResourceHandle,
+    update_pipeline: Pipeline,
+    copy_back_pipeline: Pipeline,
+    render_pipeline: Pipeline,
+    angle: f32,
+    paused: bool,
+    generation: u32,
+    seed: u32,
+}
+
+// cheap deterministic noise so the example doesn't need a rng dependency
+fn hash(mut x: u32) -> f32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    x as f32 / u32::MAX as f32
+}
+
+// a random blob of live cells in the middle of the volume
+fn random_cells(seed: u32) -> Vec<u32> {
+    let center = SIZE as f32 * 0.5;
+    (0..SIZE * SIZE * SIZE)
+        .map(|i| {
+            let (x, y, z) = (i % SIZE, i / SIZE % SIZE, i / (SIZE * SIZE));
+            let distance = ((x as f32 - center).powi(2)
+                + (y as f32 - center).powi(2)
+                + (z as f32 - center).powi(2))
+            .sqrt();
+            (distance < SIZE as f32 * 0.3 && hash(i ^ seed.wrapping_mul(0x9e3779b9)) < 0.35) as u32
+        })
+        .collect()
+}
+
+impl Game for Life3d {
+    fn on_init(gpu: &mut CoGr) -> Result<Self> {
+        let cell_count = (SIZE * SIZE * SIZE) as usize;
+        let cells = gpu.buffer("cells", cell_count, size_of::<u32>());
+        let next = gpu.buffer("next", cell_count, size_of::<u32>());
+        let output = gpu.texture("output", TextureRes::FullRes, TextureFormat::Rgba8Unorm);
+        let update_pipeline =
+            gpu.pipeline("examples/life_3d/life.wgsl", "update", &[&cells, &next])?;
+        let copy_back_pipeline =
+            gpu.pipeline("examples/life_3d/life.wgsl", "copy_back", &[&cells, &next])?;
+        let render_pipeline = gpu
+            .pipeline_builder("examples/life_3d/render.wgsl")
+            .bindings(&[&output, &cells])
+            .define("MAX_STEPS", format!("{}u", SIZE * 3))
+            .read_only(&cells)
+            .build()?;
+        {
+            let mut encoder = gpu.get_encoder()?;
+            encoder.set_buffer_data(&cells, random_cells(0))?;
+        }
+        Ok(Life3d {
+            cells,
+            next,
+            output,
+            update_pipeline,
+            copy_back_pipeline,
+            render_pipeline,
+            angle: 0.0,
+            paused: false,
+            generation: 0,
+            seed: 0,
+        })
+    }
+
+    fn on_render(&mut self, gpu: &mut CoGr, _input: &Input, dt: f32) -> Result<()> {
+        self.angle += dt * 0.3;
+        let (width, height) = gpu.render_size();
+
+        let mut encoder = gpu.get_encoder_for_draw()?;
+        if !self.paused {
+            // the new generation is written to next first, every cell reads its neighbours from the old one
+            let volume = (SIZE, SIZE, SIZE);
+            encoder.dispatch_3d(
+                &mut self.update_pipeline,
+                volume,
+                &[&self.cells, &self.next],
+            )?;
+            encoder.dispatch_3d(
+                &mut self.copy_back_pipeline,
+                volume,
+                &[&self.cells, &self.next],
+            )?;
+            self.generation += 1;
+        }
+        encoder.dispatch_pipeline_with_constants(
+            &mut self.render_pipeline,
+            (div_ceil(width, 16), div_ceil(height, 16), 1),
+            &[&self.output, &self.cells],
+            &RenderConstants {
+                size: [SIZE; 3],
+                angle: self.angle,
+                screen_dimensions: [width, height],
+                _padding: [0; 2],
+            },
+        )?;
+        encoder.to_screen(&self.output)?;
+
+        let mut reset = false;
+        encoder.draw_ui(|ctx| {
+            egui::Window::new("life_3d").show(ctx, |ui| {
+                ui.label(format!("fps: {}", 1f32 / dt));
+                ui.label(format!("generation: {}", self.generation));
+                ui.checkbox(&mut self.paused, "paused");
+                reset = ui.button("reset").clicked();
+            });
+        })?;
+        if reset {
+            self.seed += 1;
+            self.generation = 0;
+            encoder.set_buffer_data(&self.cells, random_cells(self.seed))?;
+        }
+
+        Ok(())
+    }
+
+    fn on_tick(&mut self, _gpu: &mut CoGr, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    main_loop_run::<Life3d>(10f32)?;
+    Ok(())
+}
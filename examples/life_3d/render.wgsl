@@ -0,0 +1,70 @@
+struct RenderConstants {
+    size: vec3<u32>,
+    angle: f32,
+    screen_dimensions: vec2<u32>,
+}
+
+@group(0) @binding(0)
+var output: texture_storage_2d<rgba8unorm, write>;
+@group(0) @binding(1)
+var<storage, read> cells: array<u32>;
+
+var<push_constant> constants: RenderConstants;
+
+// MAX_STEPS is defined by the pipeline builder, a ray crosses at most three times the cells per side
+
+fn is_alive(cell: vec3<i32>) -> bool {
+    let size = vec3<i32>(constants.size);
+    if (any(cell < vec3<i32>(0)) || any(cell >= size)) {
+        return false;
+    }
+    return cells[cell.x + (cell.y + cell.z * size.y) * size.x] == 1u;
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= constants.screen_dimensions)) {
+        return;
+    }
+    // orbit around the volume, which spans 0..size
+    let size = vec3<f32>(constants.size);
+    let center = size * 0.5;
+    let origin = center + vec3<f32>(cos(constants.angle), 0.6, sin(constants.angle)) * size.x * 1.6;
+    let forward = normalize(center - origin);
+    let side = normalize(cross(forward, vec3<f32>(0.0, 1.0, 0.0)));
+    let up = cross(side, forward);
+    let dimensions = vec2<f32>(constants.screen_dimensions);
+    let uv = (vec2<f32>(id.xy) - dimensions * 0.5) / dimensions.y;
+    let direction = normalize(forward * 1.5 + side * uv.x - up * uv.y);
+
+    var color = vec3<f32>(0.05, 0.05, 0.08);
+    // start the traversal where the ray enters the bounding box of the volume
+    let inverse = 1.0 / direction;
+    let t0 = (vec3<f32>(0.0) - origin) * inverse;
+    let t1 = (size - origin) * inverse;
+    let t_enter = max(max(min(t0.x, t1.x), min(t0.y, t1.y)), min(t0.z, t1.z));
+    let t_exit = min(min(max(t0.x, t1.x), max(t0.y, t1.y)), max(t0.z, t1.z));
+    if (t_enter < t_exit && t_exit > 0.0) {
+        let start = origin + direction * max(t_enter, 0.0);
+        var cell = vec3<i32>(clamp(floor(start), vec3<f32>(0.0), size - 1.0));
+        let cell_step = vec3<i32>(sign(direction));
+        let delta = abs(inverse);
+        var side_distance = (sign(direction) * (vec3<f32>(cell) - start) + sign(direction) * 0.5 + 0.5) * delta;
+        var normal = vec3<f32>(0.0);
+        for (var i = 0u; i < MAX_STEPS; i++) {
+            if (is_alive(cell)) {
+                let light = max(dot(normal, normalize(vec3<f32>(0.4, 1.0, 0.3))), 0.0) * 0.7 + 0.3;
+                color = (vec3<f32>(cell) / size * 0.8 + 0.2) * light;
+                break;
+            }
+            let mask = step(side_distance.xyz, side_distance.yzx) * step(side_distance.xyz, side_distance.zxy);
+            side_distance += mask * delta;
+            cell += vec3<i32>(mask) * cell_step;
+            normal = -mask * sign(direction);
+            if (any(cell < vec3<i32>(0)) || any(cell >= vec3<i32>(constants.size))) {
+                break;
+            }
+        }
+    }
+    textureStore(output, id.xy, vec4<f32>(color, 1.0));
+}
@@ -0,0 +1,22 @@
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec4<f32>,
+    @location(1) velocity: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position.xy, 0.0, 1.0);
+    // faster particles are drawn brighter
+    let speed = clamp(length(velocity.xy), 0.0, 1.0);
+    out.color = mix(vec3<f32>(0.1, 0.2, 0.8), vec3<f32>(1.0, 0.8, 0.3), speed);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
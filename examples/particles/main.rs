@@ -0,0 +1,139 @@
+use std::mem::size_of;
+
+use cogrrs::{
+    anyhow::Result, bytemuck::Pod, bytemuck::Zeroable, div_ceil, egui, glam::vec4, glam::Vec4,
+    main_loop_run, CoGr, Game, Input, MeshPipeline, Pipeline, PrimitiveTopology, ResourceHandle,
+    VertexFormat,
+};
+
+const PARTICLE_COUNT: u32 = 1 << 16;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct Particle {
+    position: Vec4,
+    velocity: Vec4,
+}
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct SimulationData {
+    dt: f32,
+    time: f32,
+    particle_count: u32,
+    _padding: u32,
+}
+
+// same layout as wgpu's DrawIndirect arguments
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct DrawArguments {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+}
+
+struct Particles {
+    particles: ResourceHandle,
+    draw_arguments: ResourceHandle,
+    simulation_data: ResourceHandle,
+    update_pipeline: Pipeline,
+    draw_pipeline: MeshPipeline,
+    time: f32,
+}
+
+// cheap deterministic noise so the example doesn't need a rng dependency
+fn hash(mut x: u32) -> f32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    x as f32 / u32::MAX as f32
+}
+
+impl Game for Particles {
+    fn on_init(gpu: &mut CoGr) -> Result<Self> {
+        let particles = gpu.buffer("particles", PARTICLE_COUNT as usize, size_of::<Particle>());
+        let draw_arguments = gpu.buffer("draw_arguments", 1, size_of::<DrawArguments>());
+        let simulation_data = gpu.buffer("simulation_data", 1, size_of::<SimulationData>());
+        let update_pipeline = gpu.pipeline(
+            "examples/particles/update.wgsl",
+            "main",
+            &[&particles, &draw_arguments, &simulation_data],
+        )?;
+        let draw_pipeline = gpu.mesh_pipeline(
+            "examples/particles/draw.wgsl",
+            &[VertexFormat::Float32x4, VertexFormat::Float32x4],
+            PrimitiveTopology::PointList,
+            &[],
+        )?;
+
+        let initial_particles = (0..PARTICLE_COUNT)
+            .map(|i| Particle {
+                position: vec4(
+                    hash(i * 2) * 2.0 - 1.0,
+                    hash(i * 2 + 1) * 2.0 - 1.0,
+                    0.0,
+                    1.0,
+                ),
+                velocity: Vec4::ZERO,
+            })
+            .collect::<Vec<_>>();
+        {
+            let mut encoder = gpu.get_encoder()?;
+            encoder.set_buffer_data(&particles, initial_particles)?;
+        }
+
+        Ok(Particles {
+            particles,
+            draw_arguments,
+            simulation_data,
+            update_pipeline,
+            draw_pipeline,
+            time: 0f32,
+        })
+    }
+
+    fn on_render(&mut self, gpu: &mut CoGr, _input: &Input, dt: f32) -> Result<()> {
+        self.time += dt;
+        let simulation_data = SimulationData {
+            dt,
+            time: self.time,
+            particle_count: PARTICLE_COUNT,
+            _padding: 0,
+        };
+
+        let mut encoder = gpu.get_encoder_for_draw()?;
+        encoder.set_buffer_data(&self.simulation_data, [simulation_data])?;
+        encoder.dispatch_pipeline(
+            &mut self.update_pipeline,
+            (div_ceil(PARTICLE_COUNT, 64), 1, 1),
+            &[&self.particles, &self.draw_arguments, &self.simulation_data],
+        )?;
+        encoder.draw_mesh_indirect(
+            &mut self.draw_pipeline,
+            &self.particles,
+            &self.draw_arguments,
+            &[],
+        )?;
+        encoder.draw_ui(|ctx| {
+            egui::Window::new("debug").show(ctx, |ui| {
+                ui.label(format!("fps: {}", 1f32 / dt));
+                ui.label(format!("particles: {}", PARTICLE_COUNT));
+            });
+        })?;
+
+        Ok(())
+    }
+
+    fn on_tick(&mut self, _gpu: &mut CoGr, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    main_loop_run::<Particles>(10f32)?;
+    Ok(())
+}
@@ -0,0 +1,50 @@
+struct Particle {
+    position: vec4<f32>,
+    velocity: vec4<f32>,
+}
+
+struct DrawArguments {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+}
+
+struct SimulationData {
+    dt: f32,
+    time: f32,
+    particle_count: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0)
+var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1)
+var<storage, read_write> draw_arguments: DrawArguments;
+@group(0) @binding(2)
+var<storage, read_write> simulation_data: SimulationData;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    // the draw call reads its vertex count from the arguments written here
+    if index == 0u {
+        draw_arguments.vertex_count = simulation_data.particle_count;
+        draw_arguments.instance_count = 1u;
+        draw_arguments.first_vertex = 0u;
+        draw_arguments.first_instance = 0u;
+    }
+    if index >= simulation_data.particle_count {
+        return;
+    }
+
+    var particle = particles[index];
+    // every particle is pulled towards a point which circles around the center of the screen
+    let attractor = vec2<f32>(cos(simulation_data.time), sin(simulation_data.time)) * 0.5;
+    let to_attractor = attractor - particle.position.xy;
+    let direction = to_attractor / max(length(to_attractor), 0.05);
+    let velocity = (particle.velocity.xy + direction * simulation_data.dt) * 0.995;
+    particle.velocity = vec4<f32>(velocity, 0.0, 0.0);
+    particle.position = vec4<f32>(particle.position.xy + velocity * simulation_data.dt, 0.0, 1.0);
+    particles[index] = particle;
+}
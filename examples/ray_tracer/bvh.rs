@@ -1,13 +1,16 @@
 use cogrrs::{
     bytemuck::{Pod, Zeroable},
+    f32_to_f16,
     glam::vec3,
     glam::Vec3,
 };
-use glam::{Vec4, Vec4Swizzles};
+use glam::{vec4, Mat4, Vec4, Vec4Swizzles};
 use std::fmt::Debug;
+use std::time::{Duration, Instant};
 use std::{
     fs::File,
     io::{BufRead, BufReader},
+    path::Path,
 };
 
 #[repr(C, align(32))]
@@ -45,6 +48,44 @@ pub struct Ray {
     pub _padding1: u32,
 }
 
+// distance the intersection functions return for a miss, the shaders use the same value
+pub const MISS: f32 = 1e30;
+
+// 1 / v per component, like `1.0 / direction` in the shaders. zero components become infinity, which the slab test
+// in BVHNode::intersect handles
+pub fn reciprocal(v: Vec3) -> Vec3 {
+    Vec3::ONE / v
+}
+
+impl Ray {
+    pub fn new(o: Vec3, d: Vec3) -> Ray {
+        Ray {
+            o,
+            t: MISS,
+            d,
+            prim: u32::MAX,
+            d_r: reciprocal(d),
+            _padding1: 0,
+        }
+    }
+}
+
+impl BVHNode {
+    // distance along the ray to the box, or MISS when it's behind the ray or further than the closest hit so far.
+    // same as intersect_node in hello_async_mesh/trace.wgsl
+    pub fn intersect(&self, ray: &Ray) -> f32 {
+        let t0 = (vec3(self.minx, self.miny, self.minz) - ray.o) * ray.d_r;
+        let t1 = (vec3(self.maxx, self.maxy, self.maxz) - ray.o) * ray.d_r;
+        let t_enter = t0.min(t1).max_element();
+        let t_exit = t0.max(t1).min_element();
+        if t_enter > t_exit || t_exit < 0.0 || t_enter > ray.t {
+            return MISS;
+        }
+        t_enter
+    }
+}
+
+// the w of p0 holds the bits of the material index, see Triangle::material
 #[repr(C)]
 #[derive(Pod, Zeroable, Clone, Copy)]
 pub struct Triangle {
@@ -53,12 +94,112 @@ pub struct Triangle {
     pub p2: Vec4,
 }
 
+impl Triangle {
+    pub fn material(&self) -> u32 {
+        self.p0.w.to_bits()
+    }
+    // moller trumbore, returns the distance to the triangle or MISS. same as intersect_triangle in
+    // hello_async_mesh/trace.wgsl
+    pub fn intersect(&self, ray: &Ray) -> f32 {
+        let edge1 = self.p1.xyz() - self.p0.xyz();
+        let edge2 = self.p2.xyz() - self.p0.xyz();
+        let h = ray.d.cross(edge2);
+        let determinant = edge1.dot(h);
+        if determinant.abs() < 1e-8 {
+            return MISS;
+        }
+        let f = 1.0 / determinant;
+        let s = ray.o - self.p0.xyz();
+        let u = f * s.dot(h);
+        let q = s.cross(edge1);
+        let v = f * ray.d.dot(q);
+        if u < 0.0 || v < 0.0 || u + v > 1.0 {
+            return MISS;
+        }
+        let t = f * edge2.dot(q);
+        if t < 1e-5 {
+            return MISS;
+        }
+        t
+    }
+}
+
+// the diffuse color of a material from an .mtl file, has to match Material in trace.glsl
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug)]
+pub struct Material {
+    pub diffuse: Vec4,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            diffuse: vec4(0.8, 0.8, 0.8, 1.0),
+        }
+    }
+}
+
+// a triangle with half precision coordinates, a third of the size of Triangle. every u32 holds two halves
+// with the first one in the lower bits so the shader can use unpackHalf2x16, the last half is the material index
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+pub struct PackedTriangle {
+    pub data: [u32; 5],
+}
+
+impl From<&Triangle> for PackedTriangle {
+    fn from(triangle: &Triangle) -> Self {
+        let mut halves = [
+            triangle.p0.x,
+            triangle.p0.y,
+            triangle.p0.z,
+            triangle.p1.x,
+            triangle.p1.y,
+            triangle.p1.z,
+            triangle.p2.x,
+            triangle.p2.y,
+            triangle.p2.z,
+            0.0,
+        ]
+        .map(f32_to_f16);
+        halves[9] = triangle.material() as u16;
+        let mut data = [0; 5];
+        for (i, pair) in halves.chunks(2).enumerate() {
+            data[i] = pair[0] as u32 | (pair[1] as u32) << 16;
+        }
+        PackedTriangle { data }
+    }
+}
+
+// how far a bvh build is, the callback gets the fraction of triangles that ended up in a leaf
+struct BuildProgress<'a> {
+    done: u32,
+    total: u32,
+    last_reported: f32,
+    // returns false to stop splitting, the nodes that are left become leaves as they are
+    callback: &'a mut dyn FnMut(f32) -> bool,
+    stopped: bool,
+}
+
+impl BuildProgress<'_> {
+    fn add_leaf(&mut self, count: u32) {
+        self.done += count;
+        let fraction = self.done as f32 / self.total.max(1) as f32;
+        // reporting every leaf would cost more than the build itself
+        if fraction - self.last_reported >= 0.01 || self.done == self.total {
+            self.last_reported = fraction;
+            self.stopped |= !(self.callback)(fraction);
+        }
+    }
+}
+
 #[repr(C)]
 pub struct Bvh {
     pub triangles: Vec<Triangle>,
     pub indices: Vec<u32>,
     pub bvh_nodes: Vec<BVHNode>,
     pub centroids: Vec<Vec3>,
+    pub materials: Vec<Material>,
 }
 
 impl Debug for Aabb {
@@ -87,9 +228,14 @@ impl Debug for BVHNode {
 }
 
 impl Bvh {
+    // loads an obj file, the materials of the mtllib it references become the materials of the triangles.
+    // material 0 is a default material for faces without usemtl
     pub fn new(filename: &str) -> Bvh {
         let mut vertices = Vec::new();
         let mut triangles = Vec::new();
+        let mut materials = vec![Material::default()];
+        let mut material_names = vec![String::new()];
+        let mut material = 0;
 
         let file = File::open(filename).unwrap();
         let reader = BufReader::new(file);
@@ -102,23 +248,33 @@ impl Bvh {
                 let p3 = splits[3].parse::<f32>().unwrap();
                 vertices.push(vec3(p1, p2, p3));
             }
+            if splits[0] == "mtllib" {
+                let mtl_file = Path::new(filename).with_file_name(splits[1]);
+                load_materials(&mtl_file, &mut materials, &mut material_names);
+            }
+            if splits[0] == "usemtl" {
+                material = material_names
+                    .iter()
+                    .position(|name| name == splits[1])
+                    .unwrap_or(0) as u32;
+            }
             if splits[0] == "f" {
                 match splits.len() {
                     4 => {
                         let p1 = splits[1].split('/').next().unwrap().parse::<u32>().unwrap() - 1;
                         let p2 = splits[2].split('/').next().unwrap().parse::<u32>().unwrap() - 1;
                         let p3 = splits[3].split('/').next().unwrap().parse::<u32>().unwrap() - 1;
-                        triangles.push([p1, p2, p3, 0]);
+                        triangles.push([p1, p2, p3, material]);
                     }
                     5 => {
                         let p1 = splits[1].split('/').next().unwrap().parse::<u32>().unwrap() - 1;
                         let p2 = splits[2].split('/').next().unwrap().parse::<u32>().unwrap() - 1;
                         let p3 = splits[4].split('/').next().unwrap().parse::<u32>().unwrap() - 1;
-                        triangles.push([p1, p2, p3, 0]);
+                        triangles.push([p1, p2, p3, material]);
                         let p1 = splits[2].split('/').next().unwrap().parse::<u32>().unwrap() - 1;
                         let p2 = splits[3].split('/').next().unwrap().parse::<u32>().unwrap() - 1;
                         let p3 = splits[4].split('/').next().unwrap().parse::<u32>().unwrap() - 1;
-                        triangles.push([p1, p2, p3, 0]);
+                        triangles.push([p1, p2, p3, material]);
                     }
                     _ => panic!("unknown model format"),
                 }
@@ -134,7 +290,7 @@ impl Bvh {
         let triangles: Vec<Triangle> = triangles
             .iter()
             .map(|tri| Triangle {
-                p0: (vertices[tri[0] as usize], 0.0).into(),
+                p0: (vertices[tri[0] as usize], f32::from_bits(tri[3])).into(),
                 p1: (vertices[tri[1] as usize], 0.0).into(),
                 p2: (vertices[tri[2] as usize], 0.0).into(),
             })
@@ -147,10 +303,76 @@ impl Bvh {
             indices,
             bvh_nodes,
             centroids: Default::default(),
+            materials,
         }
     }
 
-    pub fn build_bvh(&mut self) {
+    // finds the closest triangle along the ray on the cpu, the same way the shaders traverse the bvh. sets t and
+    // prim of the ray when it hits something closer than t
+    pub fn intersect(&self, ray: &mut Ray) {
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.bvh_nodes[node_index];
+            if node.intersect(ray) >= MISS {
+                continue;
+            }
+            if node.count > 0 {
+                let first = node.left_first as usize;
+                for i in first..first + node.count as usize {
+                    let t = self.triangles[i].intersect(ray);
+                    if t < ray.t {
+                        ray.t = t;
+                        ray.prim = i as u32;
+                    }
+                }
+            } else {
+                stack.push(node.left_first as usize);
+                stack.push(node.left_first as usize + 1);
+            }
+        }
+    }
+
+    // moves and uniformly scales the mesh so it fits in the cube from -0.5 to 0.5, so every mesh can be viewed from
+    // the same distance. call it before build_bvh, the returned transform maps the original coordinates to the
+    // new ones and its inverse undoes it
+    pub fn normalize_to_unit_cube(&mut self) -> Mat4 {
+        let (min, max) = self
+            .triangles
+            .iter()
+            .flat_map(|t| [t.p0.xyz(), t.p1.xyz(), t.p2.xyz()])
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), p| {
+                (min.min(p), max.max(p))
+            });
+        let extent = (max - min).max_element();
+        let scale = if extent > 0.0 { 1.0 / extent } else { 1.0 };
+        let transform =
+            Mat4::from_scale(Vec3::splat(scale)) * Mat4::from_translation(-(min + max) * 0.5);
+        for triangle in &mut self.triangles {
+            for p in [&mut triangle.p0, &mut triangle.p1, &mut triangle.p2] {
+                // w holds other data like the material index
+                *p = (transform.transform_point3(p.xyz()), p.w).into();
+            }
+        }
+        transform
+    }
+
+    // returns how long the build took
+    pub fn build_bvh(&mut self) -> Duration {
+        self.build_bvh_with_progress(|_| true)
+    }
+
+    // like build_bvh, but calls progress with the fraction of triangles placed in leaves so far. when it returns
+    // false the build stops splitting and finishes right away, the result is still a valid but slower bvh. this caps
+    // the time spent building, for example when reloading meshes interactively
+    pub fn build_bvh_with_progress(&mut self, mut progress: impl FnMut(f32) -> bool) -> Duration {
+        let start_time = Instant::now();
+        let mut progress = BuildProgress {
+            done: 0,
+            total: self.triangles.len() as u32,
+            last_reported: 0.0,
+            callback: &mut progress,
+            stopped: false,
+        };
         self.centroids = self
             .triangles
             .iter()
@@ -165,7 +387,7 @@ impl Bvh {
 
         let mut new_node_index = 2;
 
-        self.subdivide(0, 0, &mut new_node_index);
+        self.subdivide(0, 0, &mut new_node_index, &mut progress);
 
         self.centroids = Vec::new();
         self.bvh_nodes.truncate(new_node_index as usize);
@@ -176,11 +398,19 @@ impl Bvh {
             .iter()
             .map(|index| self.triangles[*index as usize])
             .collect();
+        start_time.elapsed()
     }
 
-    fn subdivide(&mut self, current_bvh_index: usize, start: u32, pool_index: &mut u32) {
-        if self.bvh_nodes[current_bvh_index].count <= 3 {
+    fn subdivide(
+        &mut self,
+        current_bvh_index: usize,
+        start: u32,
+        pool_index: &mut u32,
+        progress: &mut BuildProgress,
+    ) {
+        if self.bvh_nodes[current_bvh_index].count <= 3 || progress.stopped {
             self.bvh_nodes[current_bvh_index].left_first = start as i32;
+            progress.add_leaf(self.bvh_nodes[current_bvh_index].count as u32);
             return;
         }
         let index = *pool_index;
@@ -198,8 +428,8 @@ impl Bvh {
         let bounds = self.calculate_bounds(pivot, right_count as u32, false);
         self.set_bound(index as usize + 1, &bounds);
 
-        self.subdivide(index as usize, start, pool_index);
-        self.subdivide(index as usize + 1, pivot, pool_index);
+        self.subdivide(index as usize, start, pool_index, progress);
+        self.subdivide(index as usize + 1, pivot, pool_index, progress);
         self.bvh_nodes[current_bvh_index].count = 0;
     }
 
@@ -316,3 +546,28 @@ impl Bvh {
         a + (b - a) * p
     }
 }
+
+// adds the materials of an .mtl file, only their diffuse color is used
+fn load_materials(filename: &Path, materials: &mut Vec<Material>, names: &mut Vec<String>) {
+    let file = File::open(filename).unwrap();
+    let reader = BufReader::new(file);
+    for line in reader.lines() {
+        let line = line.unwrap();
+        let splits: Vec<&str> = line.split_whitespace().collect();
+        match splits.as_slice() {
+            ["newmtl", name] => {
+                names.push(name.to_string());
+                materials.push(Material::default());
+            }
+            ["Kd", r, g, b] if materials.len() > 1 => {
+                materials.last_mut().unwrap().diffuse = vec4(
+                    r.parse::<f32>().unwrap(),
+                    g.parse::<f32>().unwrap(),
+                    b.parse::<f32>().unwrap(),
+                    1.0,
+                );
+            }
+            _ => {}
+        }
+    }
+}
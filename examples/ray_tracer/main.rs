@@ -1,24 +1,47 @@
 use std::{f32::consts::PI, mem::size_of};
 
-use bvh::{BVHNode, Bvh, Triangle};
+use bvh::{BVHNode, Bvh, Material, PackedTriangle, Triangle};
 use cogrrs::{
-    anyhow::Result, bytemuck::Pod, bytemuck::Zeroable, div_ceil, egui, glam::vec3, glam::Vec3,
-    main_loop_run, CoGr, Game, Input, Pipeline, ResourceHandle, TextureFormat, TextureRes,
+    anyhow::bail, anyhow::Result, bytemuck::Pod, bytemuck::Zeroable, camera::Orbit,
+    check_gpu_layout, div_ceil, egui, glam::vec3, glam::Mat4, glam::Vec3, main_loop_run,
+    BackgroundTask, CoGr, Game, Input, Pipeline, ResourceHandle, TextureFormat, TextureRes,
 };
+use tlas::{GpuInstance, Scene};
 
 mod bvh;
+mod tlas;
+
+// stores triangles with half precision coordinates, has to match the PACKED_TRIANGLES define in trace.glsl
+const PACKED_TRIANGLES: bool = false;
+// every obj file in here shows up in the mesh dropdown
+const MESH_DIRECTORY: &str = "examples/ray_tracer";
+
+// the buffers of the loaded mesh, they are made again with the new sizes when another mesh is picked
+struct SceneBuffers {
+    triangles: ResourceHandle,
+    bvh_nodes: ResourceHandle,
+    materials: ResourceHandle,
+    tlas_nodes: ResourceHandle,
+    instances: ResourceHandle,
+}
 
 struct RayTracer {
     pub time: f32,
     pub distance: f32,
     to_draw: ResourceHandle,
-    triangles: ResourceHandle,
-    bvh_nodes: ResourceHandle,
+    scene: SceneBuffers,
+    meshes: Vec<String>,
+    selected_mesh: usize,
+    loading: Option<BackgroundTask<Bvh>>,
+    load_error: Option<String>,
     camera_data: ResourceHandle,
     trace_pipeline: Pipeline,
     timings: [f32; 1000],
     timings_ptr: usize,
     saved_timing: f32,
+    cull_backfaces: bool,
+    epsilon: f32,
+    start_turntable: bool,
 }
 
 #[repr(C)]
@@ -33,50 +56,150 @@ pub struct CameraData {
     pub up: Vec3,
     pub half_height: f32,
     pub time: f32,
-    padding1: u32,
-    padding2: u32,
-    padding3: u32,
+    // single sided triangles are skipped when hit from the back, has to match trace.glsl
+    pub cull_backfaces: u32,
+    // determinants and distances below this count as a miss, scale it with the size of the mesh
+    pub epsilon: f32,
+    padding: u32,
+}
+
+impl SceneBuffers {
+    fn new(gpu: &mut CoGr, bvh: &Bvh) -> Result<Self> {
+        if bvh.triangles.is_empty() {
+            bail!("the mesh has no triangles");
+        }
+        // a row of the mesh, each turned a bit further
+        let mut scene = Scene::default();
+        let mesh = scene.add_blas(bvh);
+        for i in 0..3 {
+            scene.add_instance(
+                mesh,
+                Mat4::from_translation(vec3(i as f32 - 1.0, 0.0, 0.0))
+                    * Mat4::from_rotation_y(i as f32 * 0.8),
+            );
+        }
+        let tlas = scene.build_tlas();
+
+        let triangle_size = if PACKED_TRIANGLES {
+            size_of::<PackedTriangle>()
+        } else {
+            size_of::<Triangle>()
+        };
+        let triangles = gpu.buffer("triangles", scene.triangles.len(), triangle_size);
+        let bvh_nodes = gpu.buffer("bvh_nodes", scene.blas_nodes.len(), size_of::<BVHNode>());
+        let materials = gpu.buffer("materials", scene.materials.len(), size_of::<Material>());
+        let tlas_nodes = gpu.buffer("tlas_nodes", tlas.nodes.len(), size_of::<BVHNode>());
+        let instances = gpu.buffer("instances", tlas.instances.len(), size_of::<GpuInstance>());
+
+        {
+            let mut encoder = gpu.get_encoder()?;
+            if PACKED_TRIANGLES {
+                // packed on the fly into the staging buffer, without a second copy of the mesh
+                encoder.set_buffer_data_iter(&triangles, scene.packed_triangles())?;
+            } else {
+                encoder.set_buffer_data(&triangles, &scene.triangles)?;
+            }
+            encoder.set_buffer_data(&bvh_nodes, &scene.blas_nodes)?;
+            encoder.set_buffer_data(&materials, &scene.materials)?;
+            encoder.set_buffer_data(&tlas_nodes, tlas.nodes)?;
+            encoder.set_buffer_data(&instances, tlas.instances)?;
+        }
+
+        Ok(SceneBuffers {
+            triangles,
+            bvh_nodes,
+            materials,
+            tlas_nodes,
+            instances,
+        })
+    }
+}
+
+// the obj files in the mesh directory, sorted by name
+fn find_meshes() -> Result<Vec<String>> {
+    let mut meshes = std::fs::read_dir(MESH_DIRECTORY)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "obj")
+        })
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    meshes.sort();
+    Ok(meshes)
+}
+
+// loads and builds a mesh on another thread so the current one keeps rendering in the meantime. a broken obj file
+// panics the task, which poll turns into an error
+fn load_mesh(file: &str) -> Result<BackgroundTask<Bvh>> {
+    let file = file.to_string();
+    BackgroundTask::spawn("load_mesh", move |progress| {
+        let mut bvh = Bvh::new(&file);
+        bvh.normalize_to_unit_cube();
+        let build_time = bvh.build_bvh_with_progress(|fraction| {
+            progress.set(fraction);
+            true
+        });
+        println!("built bvh of {} in {:.2}s", file, build_time.as_secs_f32());
+        bvh
+    })
 }
 
 impl Game for RayTracer {
     fn on_init(gpu: &mut CoGr) -> Result<Self> {
+        let meshes = find_meshes()?;
+        let selected_mesh = meshes
+            .iter()
+            .position(|mesh| mesh.ends_with("dragon.obj"))
+            .unwrap_or(0);
         let mut bvh = Bvh::new("examples/ray_tracer/dragon.obj");
-        bvh.build_bvh();
+        bvh.normalize_to_unit_cube();
+        let mut last_step = 0;
+        let build_time = bvh.build_bvh_with_progress(|fraction| {
+            let step = (fraction * 10.0) as u32;
+            if step > last_step {
+                last_step = step;
+                println!("building bvh: {}%", step * 10);
+            }
+            true
+        });
+        println!("built bvh in {:.2}s", build_time.as_secs_f32());
 
         let to_draw = gpu.texture(
             "to_draw_texture",
             TextureRes::FullRes,
             TextureFormat::Rgba8Unorm,
         );
-        let triangles = gpu.buffer("triangles", bvh.triangles.len(), size_of::<Triangle>());
-        let bvh_nodes = gpu.buffer("bvh_nodes", bvh.bvh_nodes.len(), size_of::<BVHNode>());
+        let scene = SceneBuffers::new(gpu, &bvh)?;
         let camera_data = gpu.buffer("camera_data", 1, size_of::<CameraData>());
+        check_gpu_layout::<CameraData>();
+        check_gpu_layout::<GpuInstance>();
         let trace_pipeline = gpu.pipeline("examples/ray_tracer/trace.glsl")?;
 
-        {
-            let mut encoder = gpu.get_encoder()?;
-            encoder.set_buffer_data(&triangles, bvh.triangles)?;
-            encoder.set_buffer_data(&bvh_nodes, bvh.bvh_nodes)?;
-        }
-
         Ok(RayTracer {
             time: 0f32,
-            distance: -1f32,
+            distance: -3f32,
             to_draw,
-            triangles,
-            bvh_nodes,
+            scene,
+            meshes,
+            selected_mesh,
+            loading: None,
+            load_error: None,
             camera_data,
             trace_pipeline,
             timings: [0f32; 1000],
             timings_ptr: 0,
             saved_timing: 0f32,
+            cull_backfaces: false,
+            epsilon: 0.00000001,
+            start_turntable: false,
         })
     }
 
     fn on_render(&mut self, gpu: &mut CoGr, input: &Input, dt: f32) -> Result<()> {
         self.time += 0.001 * PI;
-        let width = gpu.config.width;
-        let height = gpu.config.height;
+        let (width, height) = gpu.render_size();
         if self.timings_ptr < self.timings.len() {
             self.timings[self.timings_ptr] = dt;
             self.timings_ptr += 1;
@@ -86,13 +209,43 @@ impl Game for RayTracer {
         }
         self.distance += input.mouse_state.scroll_delta;
 
-        let ray_origin = vec3(
-            self.time.sin() * self.distance,
-            0f32,
-            self.time.cos() * self.distance,
-        );
-        let ray_direction = vec3(-ray_origin.x, 0f32, -ray_origin.z).normalize();
-        let ray_side = ray_direction.cross(vec3(0f32, 1f32, 0f32).normalize());
+        // swaps in the new mesh once it's built, the pipeline picks up the new buffers on its own. a mesh that fails
+        // to load leaves the current one on screen
+        if let Some(loading) = &mut self.loading {
+            let loaded = loading
+                .poll()
+                .and_then(|bvh| bvh.map(|bvh| SceneBuffers::new(gpu, &bvh)).transpose());
+            match loaded {
+                Ok(Some(scene)) => {
+                    self.scene = scene;
+                    self.loading = None;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    self.load_error = Some(format!(
+                        "can't load {}: {}",
+                        self.meshes[self.selected_mesh], err
+                    ));
+                    self.loading = None;
+                }
+            }
+        }
+
+        let (ray_origin, ray_direction) = match gpu.turntable_pose() {
+            Some(pose) => pose,
+            None => {
+                let ray_origin = vec3(
+                    self.time.sin() * self.distance,
+                    0f32,
+                    self.time.cos() * self.distance,
+                );
+                (
+                    ray_origin,
+                    vec3(-ray_origin.x, 0f32, -ray_origin.z).normalize(),
+                )
+            }
+        };
+        let ray_side = ray_direction.cross(vec3(0f32, 1f32, 0f32)).normalize();
         let ray_up = ray_direction.cross(ray_side);
 
         let camera_data = CameraData {
@@ -105,9 +258,9 @@ impl Game for RayTracer {
             height: height as f32,
             half_height: height as f32 / 2.0,
             time: self.time,
-            padding1: 0,
-            padding2: 0,
-            padding3: 0,
+            cull_backfaces: self.cull_backfaces as u32,
+            epsilon: self.epsilon,
+            padding: 0,
         };
 
         let mut encoder = gpu.get_encoder_for_draw()?;
@@ -117,18 +270,72 @@ impl Game for RayTracer {
             (div_ceil(width, 16), div_ceil(height, 16), 1),
             &[
                 &self.to_draw,
-                &self.triangles,
-                &self.bvh_nodes,
+                &self.scene.triangles,
+                &self.scene.bvh_nodes,
                 &self.camera_data,
+                &self.scene.materials,
+                &self.scene.tlas_nodes,
+                &self.scene.instances,
             ],
         )?;
 
         encoder.to_screen(&self.to_draw)?;
+        let mut picked_mesh = None;
         encoder.draw_ui(|ctx| {
             egui::Window::new("debug").show(ctx, |ui| {
                 ui.label(format!("ms: {}", self.saved_timing * 1000f32));
+                ui.add_enabled_ui(self.loading.is_none(), |ui| {
+                    egui::ComboBox::from_label("mesh")
+                        .selected_text(
+                            self.meshes
+                                .get(self.selected_mesh)
+                                .map_or("", String::as_str),
+                        )
+                        .show_ui(ui, |ui| {
+                            for (i, mesh) in self.meshes.iter().enumerate() {
+                                if ui.selectable_label(i == self.selected_mesh, mesh).clicked()
+                                    && i != self.selected_mesh
+                                {
+                                    picked_mesh = Some(i);
+                                }
+                            }
+                        });
+                });
+                if let Some(loading) = &self.loading {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("building bvh");
+                    });
+                    ui.add(egui::ProgressBar::new(loading.progress()).show_percentage());
+                }
+                if let Some(error) = &self.load_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                ui.checkbox(&mut self.cull_backfaces, "backface culling");
+                ui.add(
+                    egui::Slider::new(&mut self.epsilon, 0.0000000001..=0.01)
+                        .logarithmic(true)
+                        .text("epsilon"),
+                );
+                self.start_turntable = ui.button("render turntable").clicked();
             });
         })?;
+        drop(encoder);
+
+        if let Some(mesh) = picked_mesh {
+            self.selected_mesh = mesh;
+            self.load_error = None;
+            self.loading = Some(load_mesh(&self.meshes[mesh])?);
+        }
+
+        if self.start_turntable {
+            let orbit = Orbit {
+                target: Vec3::ZERO,
+                radius: self.distance.abs(),
+                height: 0.5,
+            };
+            gpu.turntable_capture(120, "turntable", orbit)?;
+        }
 
         Ok(())
     }
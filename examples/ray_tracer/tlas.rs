@@ -0,0 +1,174 @@
+use cogrrs::{
+    bytemuck::{Pod, Zeroable},
+    glam::{vec3, Mat4, Vec3},
+};
+
+use crate::bvh::{BVHNode, Bvh, Material, PackedTriangle, Triangle};
+
+// a placed copy of a blas, has to match Instance in trace.glsl
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy)]
+pub struct GpuInstance {
+    pub world_to_object: Mat4,
+    pub blas_root: u32,
+    _padding: [u32; 3],
+}
+
+// the meshes of a scene and the instances placing them. the triangles, nodes and materials of all meshes are
+// stored back to back, so every blas can be traversed from its own root node in the same buffers
+#[derive(Default)]
+pub struct Scene {
+    pub triangles: Vec<Triangle>,
+    pub blas_nodes: Vec<BVHNode>,
+    pub materials: Vec<Material>,
+    blas_roots: Vec<u32>,
+    instances: Vec<(usize, Mat4)>,
+}
+
+// the top level bvh over the world space bounds of the instances, its leaves point into instances
+pub struct Tlas {
+    pub nodes: Vec<BVHNode>,
+    pub instances: Vec<GpuInstance>,
+}
+
+struct InstanceBounds {
+    min: Vec3,
+    max: Vec3,
+    instance: GpuInstance,
+}
+
+impl Scene {
+    // adds a mesh with a built bvh, returns the index to place it with add_instance
+    pub fn add_blas(&mut self, bvh: &Bvh) -> usize {
+        let node_offset = self.blas_nodes.len() as i32;
+        let triangle_offset = self.triangles.len() as i32;
+        let material_offset = self.materials.len() as u32;
+        // inner nodes point to their children, leaves to their triangles
+        self.blas_nodes
+            .extend(bvh.bvh_nodes.iter().map(|node| BVHNode {
+                left_first: match node.count {
+                    0 => node.left_first + node_offset,
+                    _ => node.left_first + triangle_offset,
+                },
+                ..*node
+            }));
+        self.triangles.extend(bvh.triangles.iter().map(|triangle| {
+            let mut triangle = *triangle;
+            triangle.p0.w = f32::from_bits(triangle.material() + material_offset);
+            triangle
+        }));
+        self.materials.extend_from_slice(&bvh.materials);
+        self.blas_roots.push(node_offset as u32);
+        self.blas_roots.len() - 1
+    }
+
+    // places a mesh in the world, transform maps its coordinates to world space
+    pub fn add_instance(&mut self, blas: usize, transform: Mat4) {
+        self.instances.push((blas, transform));
+    }
+
+    // the triangles in the layout of PackedTriangle
+    pub fn packed_triangles(&self) -> impl Iterator<Item = PackedTriangle> + '_ {
+        self.triangles.iter().map(PackedTriangle::from)
+    }
+
+    pub fn build_tlas(&self) -> Tlas {
+        let mut bounds: Vec<InstanceBounds> = self
+            .instances
+            .iter()
+            .map(|(blas, transform)| {
+                let root = &self.blas_nodes[self.blas_roots[*blas] as usize];
+                let (min, max) = (0..8)
+                    .map(|corner| {
+                        vec3(
+                            if corner & 1 == 0 {
+                                root.minx
+                            } else {
+                                root.maxx
+                            },
+                            if corner & 2 == 0 {
+                                root.miny
+                            } else {
+                                root.maxy
+                            },
+                            if corner & 4 == 0 {
+                                root.minz
+                            } else {
+                                root.maxz
+                            },
+                        )
+                    })
+                    .map(|corner| transform.transform_point3(corner))
+                    .fold((Vec3::MAX, Vec3::MIN), |(min, max), p| {
+                        (min.min(p), max.max(p))
+                    });
+                InstanceBounds {
+                    min,
+                    max,
+                    instance: GpuInstance {
+                        world_to_object: transform.inverse(),
+                        blas_root: self.blas_roots[*blas],
+                        _padding: [0; 3],
+                    },
+                }
+            })
+            .collect();
+
+        let mut nodes = vec![BVHNode::zeroed()];
+        if !bounds.is_empty() {
+            let count = bounds.len();
+            Self::subdivide(&mut nodes, 0, &mut bounds, 0, count);
+        }
+        Tlas {
+            nodes,
+            instances: bounds.iter().map(|bounds| bounds.instance).collect(),
+        }
+    }
+
+    // splits the instances at the median of the longest axis, like the blas children are stored next to each other
+    fn subdivide(
+        nodes: &mut Vec<BVHNode>,
+        node: usize,
+        bounds: &mut [InstanceBounds],
+        first: usize,
+        count: usize,
+    ) {
+        let instances = &mut bounds[first..first + count];
+        let (min, max) = instances
+            .iter()
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), b| {
+                (min.min(b.min), max.max(b.max))
+            });
+        nodes[node] = BVHNode {
+            minx: min.x,
+            miny: min.y,
+            minz: min.z,
+            maxx: max.x,
+            maxy: max.y,
+            maxz: max.z,
+            left_first: first as i32,
+            count: count as i32,
+        };
+        if count <= 2 {
+            return;
+        }
+
+        let extent = max - min;
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+        instances.sort_by(|a, b| (a.min + a.max)[axis].total_cmp(&(b.min + b.max)[axis]));
+        let left = nodes.len();
+        nodes.push(BVHNode::zeroed());
+        nodes.push(BVHNode::zeroed());
+        nodes[node].left_first = left as i32;
+        nodes[node].count = 0;
+        let half = count / 2;
+        Self::subdivide(nodes, left, bounds, first, half);
+        Self::subdivide(nodes, left + 1, bounds, first + half, count - half);
+    }
+}
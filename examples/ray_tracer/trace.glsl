@@ -14,18 +14,33 @@ struct StackNode{
     float dist;
 };
 
+// has to match PACKED_TRIANGLES in main.rs
+// #define PACKED_TRIANGLES
+
+#ifdef PACKED_TRIANGLES
+// 9 half precision coordinates and the material index, two halves per uint, see PackedTriangle in bvh.rs
+struct Triangle{
+    uint data[5];
+};
+#else
 struct Triangle{
     vec3 p1;
-    float pad1;
+    uint material;
     vec3 p2;
     float pad2;
     vec3 p3;
     float pad3;
 };
+#endif
 
 layout(rgba8) uniform image2D to_draw_texture;
 buffer triangles_block { Triangle triangles[]; };
 buffer bvh_nodes_block { BVHNode bvh_nodes[]; };
+// has to match Material in bvh.rs
+struct Material{
+    vec4 diffuse;
+};
+
 buffer gpu_data
 {
     vec3 camera_dir;
@@ -37,16 +52,53 @@ buffer gpu_data
     vec3 camera_up;
     float half_height;
     float time;
-    uint padding1;
-    uint padding2;
-    uint padding3;
+    // has to match CameraData in main.rs
+    uint cull_backfaces;
+    float epsilon;
+    uint padding;
+};
+buffer materials_block { Material materials[]; };
+// has to match GpuInstance in tlas.rs
+struct Instance{
+    mat4 world_to_object;
+    uint blas_root;
+    uint pad1;
+    uint pad2;
+    uint pad3;
 };
+buffer tlas_nodes_block { BVHNode tlas_nodes[]; };
+buffer instances_block { Instance instances[]; };
 layout(local_size_x = 16, local_size_y = 16, local_size_z = 1) in;
 
 
 
 #define FLT_MAX 3.402823466e+38
 
+void load_triangle(in uint triangle_id, out vec3 p1, out vec3 p2, out vec3 p3){
+#ifdef PACKED_TRIANGLES
+    vec2 a = unpackHalf2x16(triangles[triangle_id].data[0]);
+    vec2 b = unpackHalf2x16(triangles[triangle_id].data[1]);
+    vec2 c = unpackHalf2x16(triangles[triangle_id].data[2]);
+    vec2 d = unpackHalf2x16(triangles[triangle_id].data[3]);
+    vec2 e = unpackHalf2x16(triangles[triangle_id].data[4]);
+    p1 = vec3(a, b.x);
+    p2 = vec3(b.y, c);
+    p3 = vec3(d, e.x);
+#else
+    p1 = triangles[triangle_id].p1;
+    p2 = triangles[triangle_id].p2;
+    p3 = triangles[triangle_id].p3;
+#endif
+}
+
+uint load_material(in uint triangle_id){
+#ifdef PACKED_TRIANGLES
+    return triangles[triangle_id].data[4] >> 16;
+#else
+    return triangles[triangle_id].material;
+#endif
+}
+
 vec3 my_cross(vec3 a, vec3 b){
     return vec3(  
         a.y * b.z - a.z * b.y,
@@ -63,13 +115,16 @@ void intersect_ray_triangle(
     inout uint prim_id,
     in uint triangle_id
 ){
-    vec3 p1 = triangles[triangle_id].p1;
-    vec3 p2 = triangles[triangle_id].p2;
-    vec3 p3 = triangles[triangle_id].p3;
+    vec3 p1, p2, p3;
+    load_triangle(triangle_id, p1, p2, p3);
     vec3 p1_to_p2 = p2 - p1;
     vec3 p1_to_p3 = p3 - p1;
     vec3 uvec = my_cross(ray_dir, p1_to_p3);
     float det = dot(p1_to_p2, uvec);
+    // a negative determinant means the ray hits the back of the triangle, near zero it runs parallel to it
+    if (cull_backfaces != 0 ? det < epsilon : abs(det) < epsilon){
+        return;
+    }
     float inv_det = 1/det;
     vec3 a_to_origin = ray_o - p1;
     float u = dot(a_to_origin, uvec) * inv_det;
@@ -82,7 +137,7 @@ void intersect_ray_triangle(
         return;
     }
     float dist = dot(p1_to_p3, vvec) * inv_det;
-    if (dist > 0.00000001 && dist < ray_t){
+    if (dist > epsilon && dist < ray_t){
         ray_t = dist;
         prim_id = triangle_id;
     }
@@ -117,10 +172,11 @@ void traverse(
     in vec3 ray_dir, 
     in vec3 ray_dirr, 
     inout float ray_t, 
-    inout uint prim_id
+    inout uint prim_id,
+    in uint root
 ){
     StackNode stack[32];
-    uint node_index = 0;
+    uint node_index = root;
     uint stack_ptr = 0;
     while(true){
         if (bvh_nodes[node_index].count > 0){
@@ -202,14 +258,56 @@ void traverse(
 }
 
 vec3 triangle_normal(uint triangle_id){
-    vec3 p1 = triangles[triangle_id].p1;
-    vec3 p2 = triangles[triangle_id].p2;
-    vec3 p3 = triangles[triangle_id].p3;
+    vec3 p1, p2, p3;
+    load_triangle(triangle_id, p1, p2, p3);
     p1 = p2 - p1;
     p2 = p2 - p3;
     return normalize(my_cross(normalize(p1), normalize(p2)));
 }
 
+// finds the instances whose bounds the ray hits, and traverses their blas with the ray in object space. the
+// direction isn't normalized after the transform, so distances stay in world space
+void traverse_tlas(
+    in vec3 ray_o, 
+    in vec3 ray_dir, 
+    inout float ray_t, 
+    inout uint prim_id,
+    inout uint instance_id
+){
+    vec3 ray_dirr = 1/ray_dir;
+    uint stack[32];
+    uint stack_ptr = 0;
+    stack[stack_ptr++] = 0;
+    while (stack_ptr > 0){
+        BVHNode node = tlas_nodes[stack[--stack_ptr]];
+        vec3 t_min = (vec3(node.minx, node.miny, node.minz) - ray_o) * ray_dirr;
+        vec3 t_max = (vec3(node.maxx, node.maxy, node.maxz) - ray_o) * ray_dirr;
+        vec3 t1 = min(t_min, t_max);
+        vec3 t2 = max(t_min, t_max);
+        float t_near = max(max(t1.x, t1.y), t1.z);
+        float t_far = min(min(t2.x, t2.y), t2.z);
+        if (t_far < t_near || t_near >= ray_t || t_far <= 0){
+            continue;
+        }
+        if (node.count > 0){
+            for (int i = 0; i < node.count; i++){
+                uint index = node.left_first + i;
+                mat4 world_to_object = instances[index].world_to_object;
+                vec3 object_o = (world_to_object * vec4(ray_o, 1)).xyz;
+                vec3 object_dir = (world_to_object * vec4(ray_dir, 0)).xyz;
+                float t = ray_t;
+                traverse(object_o, object_dir, 1/object_dir, ray_t, prim_id, instances[index].blas_root);
+                if (ray_t < t){
+                    instance_id = index;
+                }
+            }
+        } else {
+            stack[stack_ptr++] = node.left_first;
+            stack[stack_ptr++] = node.left_first + 1;
+        }
+    }
+}
+
 void main() {
     uvec3 global_invocation_id = gl_GlobalInvocationID;
     uint x = global_invocation_id.x;
@@ -223,22 +321,25 @@ void main() {
         + camera_up * (float(y) - half_height) /  height;
 
     dir = normalize(dir - camera_pos);
-    vec3 dirr = 1/dir;
     float t = FLT_MAX;
     uint prim = -1;
+    uint instance = 0;
 
-    traverse(
+    traverse_tlas(
         camera_pos, 
         dir, 
-        dirr, 
         t, 
-        prim
+        prim,
+        instance
     );
 
 
     if (prim != uint(-1)){
-        vec3 normal = triangle_normal(prim);
-        vec3 color = vec3(dot(normal.xyz, normalize(vec3(1,-1,1)))+1)/2;
+        // normals go back to world space with the inverse transpose of the object to world transform
+        mat3 world_to_object = mat3(instances[instance].world_to_object);
+        vec3 normal = normalize(transpose(world_to_object) * triangle_normal(prim));
+        vec3 diffuse = materials[load_material(prim)].diffuse.rgb;
+        vec3 color = diffuse * (dot(normal.xyz, normalize(vec3(1,-1,1)))+1)/2;
         imageStore(to_draw_texture, pos, vec4(color, 1));
     } else {
         imageStore(to_draw_texture, pos, vec4(0));
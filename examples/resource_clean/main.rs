@@ -13,7 +13,9 @@ impl Game for HelloWorld {
         gpu.buffer("buffer_3", 256usize, 8);
         gpu.buffer("buffer_4", 256usize, 8);
         let buffer_5 = gpu.buffer("buffer_5", 256usize, 8);
-        gpu.buffer("buffer_6", 256usize, 8);
+        // pinned resources stay alive without a handle
+        let buffer_6 = gpu.buffer("buffer_6", 256usize, 8);
+        gpu.pin(&buffer_6);
         gpu.buffer("buffer_7", 256usize, 8);
 
         Ok(HelloWorld {
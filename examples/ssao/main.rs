@@ -0,0 +1,131 @@
+use cogrrs::{
+    anyhow::Result,
+    bytemuck::{Pod, Zeroable},
+    div_ceil, egui, main_loop_run, CoGr, Game, Input, Pipeline, ResourceHandle, TextureFormat,
+    TextureRes,
+};
+
+// vertical field of view of the camera in scene.wgsl, ssao.wgsl needs the same one to reconstruct positions
+const FOV: f32 = std::f32::consts::FRAC_PI_3;
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct SceneConstants {
+    time: f32,
+    tan_half_fov: f32,
+    aspect: f32,
+    _padding: f32,
+}
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct SsaoConstants {
+    radius: f32,
+    strength: f32,
+    tan_half_fov: f32,
+    aspect: f32,
+    samples: u32,
+    ao_only: u32,
+}
+
+// ambient occlusion as a post process: the scene pass writes color, depth and normal textures which the ssao pass
+// reads as sampled textures
+struct Ssao {
+    color: ResourceHandle,
+    depth: ResourceHandle,
+    normal: ResourceHandle,
+    output: ResourceHandle,
+    scene_pipeline: Pipeline,
+    ssao_pipeline: Pipeline,
+    radius: f32,
+    strength: f32,
+    samples: u32,
+    ao_only: bool,
+}
+
+impl Game for Ssao {
+    fn on_init(gpu: &mut CoGr) -> Result<Self> {
+        let color = gpu.texture_sampled("color", TextureRes::FullRes, TextureFormat::Rgba8Unorm);
+        let depth = gpu.texture_sampled("depth", TextureRes::FullRes, TextureFormat::R32Float);
+        let normal = gpu.texture_sampled("normal", TextureRes::FullRes, TextureFormat::Rgba16Float);
+        let output = gpu.texture("output", TextureRes::FullRes, TextureFormat::Rgba8Unorm);
+        let scene_pipeline = gpu.pipeline(
+            "examples/ssao/scene.wgsl",
+            "main",
+            &[&color, &depth, &normal],
+        )?;
+        let ssao_pipeline = gpu.pipeline(
+            "examples/ssao/ssao.wgsl",
+            "main",
+            &[&color, &depth, &normal, &output],
+        )?;
+        gpu.validate()?;
+        Ok(Ssao {
+            color,
+            depth,
+            normal,
+            output,
+            scene_pipeline,
+            ssao_pipeline,
+            radius: 0.5,
+            strength: 1.0,
+            samples: 16,
+            ao_only: false,
+        })
+    }
+
+    fn on_render(&mut self, gpu: &mut CoGr, _input: &Input, dt: f32) -> Result<()> {
+        let (width, height) = gpu.render_size();
+        let work_groups = (div_ceil(width, 16), div_ceil(height, 16), 1);
+        let tan_half_fov = (FOV * 0.5).tan();
+        let aspect = width as f32 / height as f32;
+        let time = gpu.time();
+
+        let mut encoder = gpu.get_encoder_for_draw()?;
+        encoder.dispatch_pipeline_with_constants(
+            &mut self.scene_pipeline,
+            work_groups,
+            &[&self.color, &self.depth, &self.normal],
+            &SceneConstants {
+                time,
+                tan_half_fov,
+                aspect,
+                _padding: 0.0,
+            },
+        )?;
+        encoder.dispatch_pipeline_with_constants(
+            &mut self.ssao_pipeline,
+            work_groups,
+            &[&self.color, &self.depth, &self.normal, &self.output],
+            &SsaoConstants {
+                radius: self.radius,
+                strength: self.strength,
+                tan_half_fov,
+                aspect,
+                samples: self.samples,
+                ao_only: self.ao_only as u32,
+            },
+        )?;
+        encoder.to_screen(&self.output)?;
+        encoder.draw_ui(|ctx| {
+            egui::Window::new("ssao").show(ctx, |ui| {
+                ui.label(format!("fps: {}", 1f32 / dt));
+                ui.add(egui::Slider::new(&mut self.radius, 0.05..=2.0).text("radius"));
+                ui.add(egui::Slider::new(&mut self.strength, 0.0..=2.0).text("strength"));
+                ui.add(egui::Slider::new(&mut self.samples, 1..=64).text("samples"));
+                ui.checkbox(&mut self.ao_only, "only ambient occlusion");
+            });
+        })?;
+
+        Ok(())
+    }
+
+    fn on_tick(&mut self, _gpu: &mut CoGr, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    main_loop_run::<Ssao>(10f32)?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+struct SceneConstants {
+    time: f32,
+    tan_half_fov: f32,
+    aspect: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0)
+var color: texture_storage_2d<rgba8unorm, write>;
+// distance along the view direction, far away for pixels that miss the scene
+@group(0) @binding(1)
+var depth: texture_storage_2d<r32float, write>;
+// view space normal, zero for pixels that miss the scene
+@group(0) @binding(2)
+var normal: texture_storage_2d<rgba16float, write>;
+
+var<push_constant> constants: SceneConstants;
+
+const FAR: f32 = 10000.0;
+
+fn sd_box(p: vec3<f32>, half_size: vec3<f32>) -> f32 {
+    let q = abs(p) - half_size;
+    return length(max(q, vec3<f32>(0.0))) + min(max(q.x, max(q.y, q.z)), 0.0);
+}
+
+fn sd_torus(p: vec3<f32>, radius: f32, thickness: f32) -> f32 {
+    let q = vec2<f32>(length(p.xz) - radius, p.y);
+    return length(q) - thickness;
+}
+
+// distance to the closest surface and the material of that surface
+fn scene(p: vec3<f32>) -> vec2<f32> {
+    var closest = vec2<f32>(p.y, 0.0);
+    var shapes = array<f32, 4>(
+        length(p - vec3<f32>(0.0, 1.0, 0.0)) - 1.0,
+        length(p - vec3<f32>(2.2, 0.6, 0.5)) - 0.6,
+        sd_box(p - vec3<f32>(-2.0, 0.75, 0.3), vec3<f32>(0.75)),
+        sd_torus(p - vec3<f32>(0.5, 0.25, 2.0), 0.8, 0.25),
+    );
+    for (var i = 0; i < 4; i += 1) {
+        if (shapes[i] < closest.x) {
+            closest = vec2<f32>(shapes[i], f32(i + 1));
+        }
+    }
+    return closest;
+}
+
+fn scene_normal(p: vec3<f32>) -> vec3<f32> {
+    let e = vec2<f32>(0.001, 0.0);
+    return normalize(vec3<f32>(
+        scene(p + e.xyy).x - scene(p - e.xyy).x,
+        scene(p + e.yxy).x - scene(p - e.yxy).x,
+        scene(p + e.yyx).x - scene(p - e.yyx).x,
+    ));
+}
+
+// sphere traces a few shapes on a floor without any shadowing, so the ambient occlusion pass has something to add
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(color);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let pixel = vec2<i32>(id.xy);
+
+    // camera orbiting the shapes
+    let angle = constants.time * 0.2;
+    let look_at = vec3<f32>(0.0, 0.8, 0.0);
+    let position = look_at + vec3<f32>(sin(angle) * 7.0, 2.5, cos(angle) * 7.0);
+    let forward = normalize(look_at - position);
+    let right = normalize(cross(forward, vec3<f32>(0.0, 1.0, 0.0)));
+    let up = cross(right, forward);
+
+    // x right, y up and z forward, the ssao pass reconstructs positions in this space
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    let ndc = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    let view_dir = normalize(vec3<f32>(
+        ndc.x * constants.aspect * constants.tan_half_fov,
+        ndc.y * constants.tan_half_fov,
+        1.0,
+    ));
+    let dir = right * view_dir.x + up * view_dir.y + forward * view_dir.z;
+
+    var t = 0.0;
+    var hit = vec2<f32>(FAR, -1.0);
+    for (var i = 0; i < 128; i += 1) {
+        let d = scene(position + dir * t);
+        if (d.x < 0.0005 * t) {
+            hit = vec2<f32>(t, d.y);
+            break;
+        }
+        t += d.x;
+        if (t > 50.0) {
+            break;
+        }
+    }
+
+    if (hit.y < 0.0) {
+        let sky = mix(vec3<f32>(0.8, 0.85, 0.9), vec3<f32>(0.4, 0.55, 0.8), max(dir.y, 0.0));
+        textureStore(color, pixel, vec4<f32>(sky, 1.0));
+        textureStore(depth, pixel, vec4<f32>(FAR));
+        textureStore(normal, pixel, vec4<f32>(0.0));
+        return;
+    }
+
+    let p = position + dir * hit.x;
+    let n = scene_normal(p);
+    var albedos = array<vec3<f32>, 5>(
+        vec3<f32>(0.8),
+        vec3<f32>(0.9, 0.35, 0.3),
+        vec3<f32>(0.3, 0.6, 0.9),
+        vec3<f32>(0.9, 0.8, 0.35),
+        vec3<f32>(0.4, 0.85, 0.45),
+    );
+    let sun = normalize(vec3<f32>(0.4, 0.8, 0.3));
+    let light = max(dot(n, sun), 0.0) * 0.7 + 0.3;
+    textureStore(color, pixel, vec4<f32>(albedos[u32(hit.y)] * light, 1.0));
+    textureStore(depth, pixel, vec4<f32>(hit.x * view_dir.z));
+    textureStore(normal, pixel, vec4<f32>(dot(n, right), dot(n, up), dot(n, forward), 0.0));
+}
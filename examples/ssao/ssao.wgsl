@@ -0,0 +1,100 @@
+struct SsaoConstants {
+    radius: f32,
+    strength: f32,
+    tan_half_fov: f32,
+    aspect: f32,
+    samples: u32,
+    ao_only: u32,
+}
+
+// the outputs of the scene pass, read as sampled textures
+@group(0) @binding(0)
+var color: texture_2d<f32>;
+@group(0) @binding(1)
+var depth: texture_2d<f32>;
+@group(0) @binding(2)
+var normal: texture_2d<f32>;
+@group(0) @binding(3)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+var<push_constant> constants: SsaoConstants;
+
+fn pcg(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// 0..1, different for every pixel and index
+fn random(pixel: vec2<u32>, index: u32) -> f32 {
+    return f32(pcg(pixel.x + pcg(pixel.y + pcg(index)))) / 4294967295.0;
+}
+
+// view space position of a point at a depth behind a screen position, the inverse of project
+fn view_position(uv: vec2<f32>, d: f32) -> vec3<f32> {
+    let ndc = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    return vec3<f32>(
+        ndc.x * constants.aspect * constants.tan_half_fov,
+        ndc.y * constants.tan_half_fov,
+        1.0,
+    ) * d;
+}
+
+fn project(p: vec3<f32>) -> vec2<f32> {
+    let ndc = vec2<f32>(
+        p.x / (p.z * constants.aspect * constants.tan_half_fov),
+        p.y / (p.z * constants.tan_half_fov),
+    );
+    return vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+}
+
+// screen space ambient occlusion: points in the hemisphere around the normal which end up behind the depth buffer
+// are inside geometry, the fraction of those darkens the pixel
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let pixel = vec2<i32>(id.xy);
+    let base = textureLoad(color, pixel, 0);
+    let d = textureLoad(depth, pixel, 0).r;
+    let n = textureLoad(normal, pixel, 0).xyz;
+    if (dot(n, n) < 0.5) {
+        textureStore(output, pixel, select(base, vec4<f32>(1.0), constants.ao_only == 1u));
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    let p = view_position(uv, d);
+    let helper = select(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), abs(n.y) > 0.9);
+    let tangent = normalize(cross(helper, n));
+    let bitangent = cross(n, tangent);
+    // every pixel rotates the sample pattern differently, which turns banding into noise
+    let rotation = random(id.xy, 0u) * 6.2831853;
+    let max_pixel = vec2<i32>(size) - vec2<i32>(1);
+
+    var occlusion = 0.0;
+    for (var i = 0u; i < constants.samples; i += 1u) {
+        // cosine weighted directions on a golden angle spiral, at random distances biased towards the center
+        let fi = f32(i) + 0.5;
+        let u = fi / f32(constants.samples);
+        let phi = fi * 2.3999632 + rotation;
+        let sin_theta = sqrt(u);
+        let cos_theta = sqrt(1.0 - u);
+        let reach = mix(0.1, 1.0, pow(random(id.xy, i + 1u), 2.0)) * constants.radius;
+        let dir = (tangent * cos(phi) + bitangent * sin(phi)) * sin_theta + n * cos_theta;
+        let s = p + dir * reach;
+        if (s.z <= 0.0) {
+            continue;
+        }
+        let s_pixel = clamp(vec2<i32>(project(s) * vec2<f32>(size)), vec2<i32>(0), max_pixel);
+        let scene_depth = textureLoad(depth, s_pixel, 0).r;
+        // geometry far in front of the point shouldn't darken it, that would give halos around silhouettes
+        let in_range = smoothstep(0.0, 1.0, constants.radius / abs(d - scene_depth));
+        occlusion += select(0.0, 1.0, scene_depth < s.z - 0.02) * in_range;
+    }
+    let ao = clamp(1.0 - occlusion / f32(constants.samples) * constants.strength, 0.0, 1.0);
+    let result = select(base.rgb * ao, vec3<f32>(ao), constants.ao_only == 1u);
+    textureStore(output, pixel, vec4<f32>(result, 1.0));
+}
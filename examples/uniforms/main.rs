@@ -0,0 +1,93 @@
+use std::mem::size_of;
+
+use cogrrs::{
+    anyhow::Result,
+    bytemuck::{Pod, Zeroable},
+    div_ceil,
+    glam::{uvec2, vec3, UVec2, Vec3},
+    main_loop_run, CoGr, Game, Input, Pipeline, ResourceHandle, TextureFormat, TextureRes,
+};
+
+// the time is passed as push constant, the camera in a uniform buffer bound next to the output texture
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct PushConstants {
+    time: f32,
+}
+
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone)]
+struct CameraGpu {
+    position: Vec3,
+    focal_length: f32,
+    direction: Vec3,
+    _padding0: f32,
+    direction_side: Vec3,
+    _padding1: f32,
+    direction_up: Vec3,
+    _padding2: f32,
+    screen_dimensions: UVec2,
+    _padding3: UVec2,
+}
+
+struct Uniforms {
+    output: ResourceHandle,
+    camera: ResourceHandle,
+    pipeline: Pipeline,
+    time: f32,
+}
+
+impl Game for Uniforms {
+    fn on_init(gpu: &mut CoGr) -> Result<Self> {
+        let output = gpu.texture("output", TextureRes::FullRes, TextureFormat::Rgba8Unorm);
+        let camera = gpu.uniform_buffer("camera", size_of::<CameraGpu>());
+        let pipeline =
+            gpu.pipeline("examples/uniforms/shader.wgsl", "main", &[&output, &camera])?;
+        gpu.validate()?;
+        Ok(Uniforms {
+            output,
+            camera,
+            pipeline,
+            time: 0f32,
+        })
+    }
+
+    fn on_render(&mut self, gpu: &mut CoGr, _input: &Input, dt: f32) -> Result<()> {
+        self.time += dt;
+        let (width, height) = gpu.render_size();
+        // orbit around the sphere at the origin
+        let position = vec3(self.time.cos(), 0.3, self.time.sin()) * 4.0;
+        let direction = -position.normalize();
+        let direction_side = direction.cross(Vec3::Y).normalize();
+        let camera = CameraGpu {
+            position,
+            focal_length: 1.5,
+            direction,
+            direction_side,
+            direction_up: direction_side.cross(direction),
+            screen_dimensions: uvec2(width, height),
+            ..Zeroable::zeroed()
+        };
+
+        let mut encoder = gpu.get_encoder_for_draw()?;
+        encoder.set_buffer_data(&self.camera, [camera])?;
+        encoder.dispatch_pipeline_with_constants(
+            &mut self.pipeline,
+            (div_ceil(width, 16), div_ceil(height, 16), 1),
+            &[&self.output, &self.camera],
+            &PushConstants { time: self.time },
+        )?;
+        encoder.to_screen(&self.output)?;
+
+        Ok(())
+    }
+
+    fn on_tick(&mut self, _gpu: &mut CoGr, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    main_loop_run::<Uniforms>(10f32)?;
+    Ok(())
+}
@@ -0,0 +1,50 @@
+struct Camera {
+    position: vec3<f32>,
+    focal_length: f32,
+    direction: vec3<f32>,
+    _padding0: f32,
+    direction_side: vec3<f32>,
+    _padding1: f32,
+    direction_up: vec3<f32>,
+    _padding2: f32,
+    screen_dimensions: vec2<u32>,
+    _padding3: vec2<u32>,
+}
+
+struct PushConstants {
+    time: f32,
+}
+
+@group(0) @binding(0)
+var output: texture_storage_2d<rgba8unorm, read_write>;
+@group(0) @binding(1)
+var<uniform> camera: Camera;
+
+var<push_constant> constants: PushConstants;
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= camera.screen_dimensions.x || id.y >= camera.screen_dimensions.y) {
+        return;
+    }
+    let dimensions = vec2<f32>(camera.screen_dimensions);
+    let uv = (vec2<f32>(id.xy) - dimensions * 0.5) / dimensions.y;
+    let direction = normalize(
+        camera.direction * camera.focal_length + camera.direction_side * uv.x - camera.direction_up * uv.y
+    );
+
+    // unit sphere at the origin with a pulsing radius
+    let radius = 1.0 + 0.1 * sin(constants.time * 3.0);
+    let b = dot(camera.position, direction);
+    let c = dot(camera.position, camera.position) - radius * radius;
+    let discriminant = b * b - c;
+    var color = vec3<f32>(0.1, 0.1, 0.15);
+    if (discriminant > 0.0) {
+        let t = -b - sqrt(discriminant);
+        if (t > 0.0) {
+            let normal = normalize(camera.position + direction * t);
+            color = normal * 0.5 + 0.5;
+        }
+    }
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(color, 1.0));
+}
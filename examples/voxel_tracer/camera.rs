@@ -4,11 +4,11 @@ use bytemuck::Pod;
 use egui::{Slider, Ui};
 use glam::{UVec2, Vec3, Vec2};
 use bytemuck::Zeroable;
-use cogrrs::{Encoder, ResourceHandle, Pipeline, CoGr, TextureRes, div_ceil, Input};
+use cogrrs::{Encoder, ResourceHandle, Pipeline, CoGr, TextureRes, div_ceil, Input, InputMap};
 use wgpu::TextureFormat;
 use dolly::{rig::CameraRig, drivers::{YawPitch, Position, Smooth}};
 
-use crate::key_mapping::{MOVE_RIGHT, MOVE_LEFT, MOVE_DOWN, MOVE_BACKWARD, MOVE_FORWARD, MOVE_UP, ENABLE_MOVEMENT};
+use crate::key_mapping::{Action, default_key_mapping};
 
 pub struct Camera {
     camera: CameraRig,
@@ -21,6 +21,10 @@ pub struct Camera {
     pub aperture: f32,
     pub focal_length: f32,
     pub sensor_height: f32,
+    // on_render only gets a shared Input, so the look settings are applied on top of its deltas
+    pub look_sensitivity: f32,
+    pub invert_y: bool,
+    pub key_mapping: InputMap<Action>,
 }
 
 #[repr(C)]
@@ -65,6 +69,9 @@ impl Camera{
             aperture: 1000f32,
             focal_length: 1.7,
             sensor_height: 1.57f32,
+            look_sensitivity: 1.0,
+            invert_y: false,
+            key_mapping: default_key_mapping(),
         }
     }
     pub fn dispatch(&mut self, encoder: &mut Encoder) -> PrimaryRayGenResults {
@@ -102,18 +109,22 @@ impl Camera{
     }
 
     pub fn update(&mut self, input: &Input, dt: f32) {
-        if input.key_pressed(ENABLE_MOVEMENT){
+        let keys = &self.key_mapping;
+        if input.action_pressed(keys, Action::EnableMovement){
 
-                let move_right = bool_to_f32(input.key_pressed(MOVE_RIGHT)) - bool_to_f32(input.key_pressed(MOVE_LEFT));
-                let move_up = bool_to_f32(input.key_pressed(MOVE_UP)) - bool_to_f32(input.key_pressed(MOVE_DOWN));
-                let move_forward = bool_to_f32(input.key_pressed(MOVE_FORWARD)) - bool_to_f32(input.key_pressed(MOVE_BACKWARD));
+                let move_right = keys.axis(input, Action::MoveRight, Action::MoveLeft);
+                let move_up = keys.axis(input, Action::MoveUp, Action::MoveDown);
+                let move_forward = keys.axis(input, Action::MoveForward, Action::MoveBackward);
                 
                 let move_vec = self.camera.final_transform.rotation * Vec3::new(-move_right, move_up, -move_forward).clamp_length_max(1.0);
                 
+                let look = input.raw_mouse_change();
+                let pitch_sign = if self.invert_y { 1.0 } else { -1.0 };
                 self.camera
                 .driver_mut::<YawPitch>()
-                .rotate_yaw_pitch(input.mouse_change()[0], -input.mouse_change()[1]);
-            self.camera.driver_mut::<Position>().translate(move_vec * dt * 10.0);
+                .rotate_yaw_pitch(look[0] * self.look_sensitivity, pitch_sign * look[1] * self.look_sensitivity);
+            let speed = if input.shift_held() { 40.0 } else { 10.0 };
+            self.camera.driver_mut::<Position>().translate(move_vec * dt * speed);
         }
         self.camera.update(dt);
     }
@@ -121,6 +132,8 @@ impl Camera{
         ui.add(Slider::new(&mut self.aperture, 0.0..=1.0).text("Aperture"));
         ui.add(Slider::new(&mut self.focal_length, 1.7..=5.0).text("Focal length"));
         ui.add(Slider::new(&mut self.sensor_height, 0.0..=10.0).text("Sensor height"));
+        ui.add(Slider::new(&mut self.look_sensitivity, 0.1..=5.0).text("Look sensitivity"));
+        ui.checkbox(&mut self.invert_y, "Invert Y");
     }
     pub fn debug_ray_direction(&mut self, encoder: &mut Encoder, to_screen: &ResourceHandle) {
         encoder
@@ -1,9 +1,23 @@
-use cogrrs::VirtualKeyCode;
+use cogrrs::{InputMap, VirtualKeyCode};
 
-pub const MOVE_RIGHT: VirtualKeyCode = VirtualKeyCode::D;
-pub const MOVE_LEFT: VirtualKeyCode = VirtualKeyCode::A;
-pub const MOVE_FORWARD: VirtualKeyCode = VirtualKeyCode::W;
-pub const MOVE_BACKWARD: VirtualKeyCode = VirtualKeyCode::S;
-pub const MOVE_UP: VirtualKeyCode = VirtualKeyCode::E;
-pub const MOVE_DOWN: VirtualKeyCode = VirtualKeyCode::Q;
-pub const ENABLE_MOVEMENT: VirtualKeyCode = VirtualKeyCode::LControl;
\ No newline at end of file
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Action {
+    MoveRight,
+    MoveLeft,
+    MoveForward,
+    MoveBackward,
+    MoveUp,
+    MoveDown,
+    EnableMovement,
+}
+
+pub fn default_key_mapping() -> InputMap<Action> {
+    InputMap::new()
+        .with(Action::MoveRight, VirtualKeyCode::D)
+        .with(Action::MoveLeft, VirtualKeyCode::A)
+        .with(Action::MoveForward, VirtualKeyCode::W)
+        .with(Action::MoveBackward, VirtualKeyCode::S)
+        .with(Action::MoveUp, VirtualKeyCode::E)
+        .with(Action::MoveDown, VirtualKeyCode::Q)
+        .with(Action::EnableMovement, VirtualKeyCode::LControl)
+}
@@ -34,12 +34,15 @@ impl Game for VoxelTracer {
         self.time += dt;
 
         self.camera.update(input, dt);
-        let camera_results = self.camera.dispatch(&mut encoder);
+        encoder.scope("frame", |encoder| {
+            let camera_results = self.camera.dispatch(encoder);
 
-        match self.render_mode{
-            RenderMode::Trace => {self.trace.dispatch(&mut encoder, self.time, &camera_results, &self.to_screen, camera_results.camera.position);},
-            RenderMode::Directions =>self.camera.debug_ray_direction(&mut encoder, &self.to_screen),
-        }
+            match self.render_mode{
+                RenderMode::Trace => {self.trace.dispatch(encoder, self.time, &camera_results, &self.to_screen, camera_results.camera.position);},
+                RenderMode::Directions =>self.camera.debug_ray_direction(encoder, &self.to_screen),
+            }
+            Ok(())
+        })?;
         
         encoder.to_screen(&self.to_screen)?;
 
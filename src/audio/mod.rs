@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+// samples per fft, the spectrum has half as many bins
+pub const FFT_SIZE: usize = 1024;
+// lowest frequency of the first band returned by AudioSpectrum::bands
+const LOWEST_BAND_FREQUENCY: f32 = 30.0;
+
+// captures the default input device and turns the latest samples into a magnitude spectrum, meant to drive shaders
+pub struct AudioSpectrum {
+    // capture stops when the stream is dropped
+    _stream: cpal::Stream,
+    samples: Arc<Mutex<VecDeque<f32>>>,
+    fft: Arc<dyn Fft<f32>>,
+    sample_rate: u32,
+    spectrum: Vec<f32>,
+    // how much of the previous spectrum is kept every update, higher values give calmer visuals
+    pub smoothing: f32,
+}
+
+impl AudioSpectrum {
+    pub fn new() -> Result<Self> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .context("no audio input device available")?;
+        let config = device.default_input_config()?;
+        let sample_rate = config.sample_rate().0;
+        let channels = config.channels() as usize;
+        let samples = Arc::new(Mutex::new(VecDeque::with_capacity(FFT_SIZE)));
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => {
+                capture::<f32>(&device, &config.into(), channels, samples.clone())?
+            }
+            cpal::SampleFormat::I16 => {
+                capture::<i16>(&device, &config.into(), channels, samples.clone())?
+            }
+            cpal::SampleFormat::U16 => {
+                capture::<u16>(&device, &config.into(), channels, samples.clone())?
+            }
+            format => bail!("audio input sample format {} isn't supported", format),
+        };
+        stream.play()?;
+        Ok(Self {
+            _stream: stream,
+            samples,
+            fft: FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            sample_rate,
+            spectrum: vec![0.0; FFT_SIZE / 2],
+            smoothing: 0.6,
+        })
+    }
+    // recomputes the spectrum from the latest samples, call this once per frame
+    pub fn update(&mut self) {
+        puffin::profile_function!();
+        let mut buffer = vec![Complex::new(0.0, 0.0); FFT_SIZE];
+        {
+            let samples = self.samples.lock().unwrap();
+            // hann window, so the edges of the captured block don't show up as noise over the whole spectrum
+            for (i, (value, sample)) in buffer.iter_mut().zip(samples.iter()).enumerate() {
+                let window = 0.5
+                    - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (FFT_SIZE - 1) as f32).cos();
+                value.re = sample * window;
+            }
+        }
+        self.fft.process(&mut buffer);
+        let scale = 2.0 / FFT_SIZE as f32;
+        for (bin, value) in self.spectrum.iter_mut().zip(buffer.iter()) {
+            *bin = *bin * self.smoothing + value.norm() * scale * (1.0 - self.smoothing);
+        }
+    }
+    // magnitude per frequency bin, bin i is centered on i * sample_rate / FFT_SIZE hz. the slice can be uploaded to
+    // a storage buffer of FFT_SIZE / 2 f32s
+    pub fn spectrum(&self) -> &[f32] {
+        &self.spectrum
+    }
+    // the spectrum averaged into logarithmically spaced bands from 30hz up to the nyquist frequency, which matches
+    // how pitch is heard better than the linear bins
+    pub fn bands(&self, count: usize) -> Vec<f32> {
+        let bin_width = self.sample_rate as f32 / FFT_SIZE as f32;
+        let highest = self.sample_rate as f32 / 2.0;
+        let frequency = |band: usize| {
+            LOWEST_BAND_FREQUENCY
+                * (highest / LOWEST_BAND_FREQUENCY).powf(band as f32 / count as f32)
+        };
+        (0..count)
+            .map(|band| {
+                let first = (frequency(band) / bin_width) as usize;
+                let last = ((frequency(band + 1) / bin_width) as usize)
+                    .max(first + 1)
+                    .min(self.spectrum.len());
+                let bins = &self.spectrum[first.min(last - 1)..last];
+                bins.iter().sum::<f32>() / bins.len() as f32
+            })
+            .collect()
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+// opens an input stream which downmixes to mono and keeps the last FFT_SIZE samples
+fn capture<T: SizedSample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    channels: usize,
+    samples: Arc<Mutex<VecDeque<f32>>>,
+) -> Result<cpal::Stream>
+where
+    f32: FromSample<T>,
+{
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            let mut samples = samples.lock().unwrap();
+            for frame in data.chunks_exact(channels) {
+                let mono =
+                    frame.iter().map(|s| s.to_sample::<f32>()).sum::<f32>() / channels as f32;
+                if samples.len() == FFT_SIZE {
+                    samples.pop_front();
+                }
+                samples.push_back(mono);
+            }
+        },
+        |err| println!("{}", err),
+        None,
+    )?;
+    Ok(stream)
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+// how far a background task is, set by the task and read by the game
+#[derive(Clone, Debug, Default)]
+pub struct TaskProgress(Arc<AtomicU32>);
+
+impl TaskProgress {
+    // a fraction from 0 to 1
+    pub fn set(&self, fraction: f32) {
+        self.0
+            .store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+// runs cpu work like loading a mesh and building its bvh on another thread, so the window keeps responding in the
+// meantime. gpu resources can only be made on the main thread, so the task returns plain data and the game turns it
+// into buffers once poll hands it over
+pub struct BackgroundTask<T> {
+    name: String,
+    progress: TaskProgress,
+    receiver: Receiver<T>,
+    finished: bool,
+}
+
+impl<T: Send + 'static> BackgroundTask<T> {
+    pub fn spawn(
+        name: &str,
+        task: impl FnOnce(&TaskProgress) -> T + Send + 'static,
+    ) -> Result<Self> {
+        let progress = TaskProgress::default();
+        let task_progress = progress.clone();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let result = task(&task_progress);
+                task_progress.set(1.0);
+                // the receiver is gone when the game dropped the task, then nobody needs the result
+                let _ = sender.send(result);
+            })?;
+        Ok(Self {
+            name: name.to_string(),
+            progress,
+            receiver,
+            finished: false,
+        })
+    }
+    // the fraction the task reported so far
+    pub fn progress(&self) -> f32 {
+        self.progress.get()
+    }
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+    // the result of the task once it's done, only the first call after that returns it. fails when the task panicked
+    pub fn poll(&mut self) -> Result<Option<T>> {
+        if self.finished {
+            return Ok(None);
+        }
+        match self.receiver.try_recv() {
+            Ok(result) => {
+                self.finished = true;
+                Ok(Some(result))
+            }
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => {
+                self.finished = true;
+                bail!("background task {} panicked", self.name)
+            }
+        }
+    }
+}
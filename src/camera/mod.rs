@@ -0,0 +1,58 @@
+use glam::{Vec2, Vec3};
+
+// the wgsl version of screen_ray, prepend it to a shader to generate the same rays on the gpu:
+// gpu.pipeline_from_source("trace", &format!("{}{}", SCREEN_RAY_WGSL, include_str!("trace.wgsl")), "main", ..)
+pub const SCREEN_RAY_WGSL: &str = include_str!("screen_ray.wgsl");
+
+// a circle around a target at a fixed height, used for turntable renders
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Orbit {
+    pub target: Vec3,
+    pub radius: f32,
+    pub height: f32,
+}
+
+impl Orbit {
+    // position and normalized view direction at an angle in radians around the y axis, looking at the target
+    pub fn pose(&self, angle: f32) -> (Vec3, Vec3) {
+        let pos = self.target
+            + Vec3::new(
+                angle.sin() * self.radius,
+                self.height,
+                angle.cos() * self.radius,
+            );
+        (pos, (self.target - pos).normalize())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+// the ray of a pinhole camera at pos looking along dir through a point on the screen. fov is the vertical field of
+// view in radians and aspect is width / height, so wide windows see more to the sides instead of getting
+// stretched. pixel is in pixels from the top left corner, add 0.5 to hit the center of a pixel
+pub fn screen_ray(
+    pos: Vec3,
+    dir: Vec3,
+    up: Vec3,
+    fov: f32,
+    aspect: f32,
+    pixel: Vec2,
+    resolution: Vec2,
+) -> Ray {
+    let forward = dir.normalize();
+    let side = forward.cross(up).normalize();
+    let up = side.cross(forward);
+    let half_height = (fov * 0.5).tan();
+    let half_width = half_height * aspect;
+    // -1..1 with y pointing down like the pixel coordinates
+    let screen = pixel / resolution * 2.0 - 1.0;
+    Ray {
+        origin: pos,
+        direction: (forward + side * (screen.x * half_width) - up * (screen.y * half_height))
+            .normalize(),
+    }
+}
@@ -0,0 +1,28 @@
+// matches cogrrs::camera::screen_ray, see its documentation
+struct Ray {
+    origin: vec3<f32>,
+    direction: vec3<f32>,
+}
+
+fn screen_ray(
+    pos: vec3<f32>,
+    dir: vec3<f32>,
+    up: vec3<f32>,
+    fov: f32,
+    aspect: f32,
+    pixel: vec2<f32>,
+    resolution: vec2<f32>,
+) -> Ray {
+    let forward = normalize(dir);
+    let side = normalize(cross(forward, up));
+    let camera_up = cross(side, forward);
+    let half_height = tan(fov * 0.5);
+    let half_width = half_height * aspect;
+    // -1..1 with y pointing down like the pixel coordinates
+    let screen = pixel / resolution * 2.0 - 1.0;
+    var ray: Ray;
+    ray.origin = pos;
+    ray.direction = normalize(forward + side * (screen.x * half_width) - camera_up * (screen.y * half_height));
+    return ray;
+}
+
@@ -0,0 +1,171 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType,
+    BufferDescriptor, BufferUsages, CommandEncoder, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, Device, PipelineLayoutDescriptor, Queue, ShaderModuleDescriptor,
+    ShaderSource, ShaderStages, StorageTextureAccess, TextureFormat, TextureView,
+    TextureViewDimension,
+};
+
+use super::to_screen_pipeline::wgsl_storage_format;
+use crate::div_ceil;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct AutoExposureParams {
+    adaptation: f32,
+    key: f32,
+    pixel_count: u32,
+    partial_count: u32,
+}
+
+// measures the average luminance of a texture and adapts an exposure to it, all on the gpu so drawing
+// the frame never waits on a readback. the exposure buffer is read by the tonemapping to screen pipeline
+#[derive(Debug)]
+pub(crate) struct AutoExposure {
+    luminance_pipeline: ComputePipeline,
+    adapt_pipeline: ComputePipeline,
+    bind_group: BindGroup,
+    params: Buffer,
+    pub(crate) exposure: Buffer,
+    size: (u32, u32),
+    work_groups: (u32, u32),
+}
+
+impl AutoExposure {
+    pub(crate) fn new(
+        device: &Device,
+        texture_view: &TextureView,
+        texture_format: TextureFormat,
+        size: (u32, u32),
+    ) -> Self {
+        let work_groups = (div_ceil(size.0, 16), div_ceil(size.1, 16));
+        let partials = device.create_buffer(&BufferDescriptor {
+            label: Some("auto_exposure_partials"),
+            size: (work_groups.0 * work_groups.1) as u64 * 4,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let exposure = device.create_buffer(&BufferDescriptor {
+            label: Some("auto_exposure_exposure"),
+            size: 4,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let params = device.create_buffer(&BufferDescriptor {
+            label: Some("auto_exposure_params"),
+            size: std::mem::size_of::<AutoExposureParams>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("auto_exposure_bindgroup_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::StorageTexture {
+                        access: StorageTextureAccess::ReadOnly,
+                        view_dimension: TextureViewDimension::D2,
+                        format: texture_format,
+                    },
+                    count: None,
+                },
+                buffer_entry(1, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(2, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(3, BufferBindingType::Uniform),
+            ],
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("auto_exposure_bindgroup"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(texture_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: partials.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: exposure.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        });
+
+        let source = include_str!("auto_exposure.wgsl")
+            .replace("STORAGE_FORMAT", wgsl_storage_format(texture_format));
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("auto_exposure.wgsl"),
+            source: ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("auto_exposure_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let compute_pipeline = |entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        };
+
+        AutoExposure {
+            luminance_pipeline: compute_pipeline("luminance_main"),
+            adapt_pipeline: compute_pipeline("adapt_main"),
+            bind_group,
+            params,
+            exposure,
+            size,
+            work_groups,
+        }
+    }
+    // moves the exposure a fraction 1 - e^(-speed * delta_time) of the way to its target, so the adaptation
+    // takes the same time at every frame rate
+    pub(crate) fn record(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        delta_time: f32,
+        speed: f32,
+        key: f32,
+    ) {
+        let params = AutoExposureParams {
+            adaptation: 1.0 - (-speed * delta_time).exp(),
+            key,
+            pixel_count: self.size.0 * self.size.1,
+            partial_count: self.work_groups.0 * self.work_groups.1,
+        };
+        queue.write_buffer(&self.params, 0, bytemuck::bytes_of(&params));
+
+        let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("auto_exposure"),
+        });
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        compute_pass.set_pipeline(&self.luminance_pipeline);
+        compute_pass.dispatch_workgroups(self.work_groups.0, self.work_groups.1, 1);
+        compute_pass.set_pipeline(&self.adapt_pipeline);
+        compute_pass.dispatch_workgroups(1, 1, 1);
+    }
+}
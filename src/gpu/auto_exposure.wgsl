@@ -0,0 +1,82 @@
+// STORAGE_FORMAT is replaced with the format of the texture that gets drawn
+struct Params {
+    // fraction of the way the exposure moves to its target this frame
+    adaptation: f32,
+    // average luminance the frame is exposed to, 0.18 is middle grey
+    key: f32,
+    pixel_count: u32,
+    partial_count: u32,
+}
+
+@group(0) @binding(0)
+var source: texture_storage_2d<STORAGE_FORMAT, read>;
+@group(0) @binding(1)
+var<storage, read_write> partials: array<f32>;
+@group(0) @binding(2)
+var<storage, read_write> exposure: f32;
+@group(0) @binding(3)
+var<uniform> params: Params;
+
+var<workgroup> partial: array<f32, 256>;
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// sums the log luminance of 16x16 texels per workgroup
+@compute @workgroup_size(16, 16)
+fn luminance_main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) group_id: vec3<u32>,
+    @builtin(num_workgroups) num_groups: vec3<u32>,
+) {
+    let dims = textureDimensions(source);
+    var value = 0.0;
+    if id.x < dims.x && id.y < dims.y {
+        let color = textureLoad(source, vec2<i32>(id.xy)).rgb;
+        // nan and inf texels would poison the average
+        let lum = clamp(luminance(color), 0.0, 65504.0);
+        if lum == lum {
+            value = log(lum + 0.0001);
+        }
+    }
+    partial[local_index] = value;
+    workgroupBarrier();
+    for (var stride = 128u; stride > 0u; stride >>= 1u) {
+        if local_index < stride {
+            partial[local_index] += partial[local_index + stride];
+        }
+        workgroupBarrier();
+    }
+    if local_index == 0u {
+        partials[group_id.x + group_id.y * num_groups.x] = partial[0];
+    }
+}
+
+// averages the partial sums and moves the exposure towards the one that maps the average to the key
+@compute @workgroup_size(256)
+fn adapt_main(@builtin(local_invocation_index) local_index: u32) {
+    var value = 0.0;
+    for (var i = local_index; i < params.partial_count; i += 256u) {
+        value += partials[i];
+    }
+    partial[local_index] = value;
+    workgroupBarrier();
+    for (var stride = 128u; stride > 0u; stride >>= 1u) {
+        if local_index < stride {
+            partial[local_index] += partial[local_index + stride];
+        }
+        workgroupBarrier();
+    }
+    if local_index == 0u {
+        let average = exp(partial[0] / f32(params.pixel_count));
+        let target_exposure = params.key / average;
+        // the first frame starts from the target instead of fading in from 0
+        if exposure <= 0.0 {
+            exposure = target_exposure;
+        } else {
+            exposure = mix(exposure, target_exposure, params.adaptation);
+        }
+    }
+}
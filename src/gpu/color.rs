@@ -0,0 +1,75 @@
+// helpers to get pixel data in a shape storage textures accept, they only come in 1, 2 and 4 channel formats
+
+// appends an alpha channel to every rgb pixel
+pub fn rgb_to_rgba<T: Copy>(rgb: &[T], alpha: T) -> Vec<T> {
+    rgb.chunks_exact(3)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], alpha])
+        .collect()
+}
+
+// multiplies the color channels of every rgba pixel by its alpha
+pub fn premultiply_alpha(rgba: &mut [f32]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        let alpha = pixel[3];
+        pixel[..3].iter_mut().for_each(|channel| *channel *= alpha);
+    }
+}
+
+// premultiply_alpha for 8 bit unorm pixels, rounding to the nearest value
+pub fn premultiply_alpha_u8(rgba: &mut [u8]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u16;
+        pixel[..3]
+            .iter_mut()
+            .for_each(|channel| *channel = ((*channel as u16 * alpha + 127) / 255) as u8);
+    }
+}
+
+// converts to the bits of an IEEE half, rounding towards zero. values too large become infinity
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        // too small for a normal half, store as subnormal or zero
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        return sign | (mantissa >> (14 - exponent)) as u16;
+    }
+    sign | (exponent as u16) << 10 | (mantissa >> 13) as u16
+}
+
+// converts the bits of an IEEE half to a float, every half is exactly representable
+pub fn f16_to_f32(value: u16) -> f32 {
+    let sign = ((value & 0x8000) as u32) << 16;
+    let exponent = ((value >> 10) & 0x1f) as u32;
+    let mantissa = (value & 0x3ff) as u32;
+    match exponent {
+        0 => {
+            // subnormal or zero
+            let magnitude = mantissa as f32 * 2f32.powi(-24);
+            f32::from_bits(sign | magnitude.to_bits())
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | mantissa << 13),
+        _ => f32::from_bits(sign | (exponent + 127 - 15) << 23 | mantissa << 13),
+    }
+}
+
+pub fn f32_slice_to_f16(values: &[f32]) -> Vec<u16> {
+    values.iter().copied().map(f32_to_f16).collect()
+}
+
+pub fn f16_slice_to_f32(values: &[u16]) -> Vec<f32> {
+    values.iter().copied().map(f16_to_f32).collect()
+}
@@ -1,36 +1,54 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::mem::size_of_val;
+use std::collections::VecDeque;
+use std::mem::size_of;
 use std::ops::{Deref, DerefMut};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use egui::Ui;
 
-use crate::gpu::Pipeline;
-use bytemuck::{AnyBitPattern, NoUninit, Pod};
+use crate::gpu::{bind_group_entries, texture_array_views, MeshPipeline, PendingRead, Pipeline};
+use bytemuck::Pod;
 use egui_wgpu::renderer::ScreenDescriptor;
-use tracing::info;
-use wgpu::util::DeviceExt;
-use wgpu::IndexFormat::Uint16;
+use tracing::{error, info, trace, warn};
+use wgpu::IndexFormat::{Uint16, Uint32};
 use wgpu::{
     CommandEncoder, Extent3d, ImageCopyTexture, RenderPassDescriptor, SurfaceTexture, TextureView,
 };
 use wgpu_profiler::{wgpu_profiler, GpuTimerScopeResult};
 
 use crate::gpu::ResourceHandle;
-use crate::CoGr;
+use crate::{CoGr, WorkgroupCeiling};
+use crate::{TimingMetric, FRAME_TIME_HISTORY};
 
+use super::auto_exposure::AutoExposure;
 use super::to_screen_pipeline::ToScreenPipeline;
+use super::ToTexturePipeline;
 
 pub struct Encoder<'a> {
     pub(crate) command_encoder: Option<CommandEncoder>,
     pub(crate) gpu_context: &'a mut CoGr,
+    pub(crate) last_dispatch_dims: Option<(u32, u32, u32)>,
 }
 
+enum MeshDraw<'a> {
+    Direct {
+        indices: Option<&'a ResourceHandle>,
+        instances: u32,
+    },
+    Indirect(&'a ResourceHandle),
+}
+
+// encoder for a frame that ends up on the window. everything is recorded into one command encoder and runs on
+// the gpu in the order it was recorded, so dispatches that produce the image have to be recorded before
+// to_screen. dispatches recorded after to_screen still run this frame, but their results only
+// show up when the texture is drawn to the screen in a later frame, which is fine for work like preparing
+// the next frame. the frame is submitted and presented when the encoder is dropped
 pub struct DrawEncoder<'a> {
     pub(crate) encoder: Option<Encoder<'a>>,
     pub(crate) surface_texture: Option<SurfaceTexture>,
     pub(crate) texture_view: TextureView,
+    // a storage view of the surface for dispatch_to_surface, when the surface supports it
+    pub(crate) storage_view: Option<TextureView>,
+    pub(crate) present: bool,
 }
 
 impl<'a> Deref for DrawEncoder<'a> {
@@ -48,7 +66,70 @@ impl<'a> DerefMut for DrawEncoder<'a> {
 }
 
 impl<'a> DrawEncoder<'a> {
+    // copies the texture to the window, scaled to the window size. it sees all writes of the dispatches recorded
+    // before this call and none of the ones after it
     pub fn to_screen(&mut self, to_screen_texture: &ResourceHandle) -> Result<()> {
+        self.record_to_screen(to_screen_texture, false, None)
+    }
+    // like to_screen, but for float textures with values above 1. the exposure adapts to the average luminance
+    // of the texture over time and the result is tonemapped to the screen, the adaptation speed can be changed
+    // with CoGr::set_auto_exposure_speed or in the auto_exposure window of the ui
+    pub fn to_screen_auto_exposure(&mut self, to_screen_texture: &ResourceHandle) -> Result<()> {
+        self.record_to_screen(to_screen_texture, true, None)
+    }
+    // like to_screen, but every color is looked up in a color grading lut. the lut is created with
+    // CoGr::texture_sampled in a filterable format, either as a size x size x size 3d texture or as a
+    // size * size x size strip with the blue slices next to each other. colors are clamped to 0..1 before the lookup
+    pub fn to_screen_with_lut(
+        &mut self,
+        to_screen_texture: &ResourceHandle,
+        lut: &ResourceHandle,
+    ) -> Result<()> {
+        let ctx = &self
+            .encoder
+            .as_ref()
+            .expect("there was no encoder")
+            .gpu_context;
+        if !matches!(lut, ResourceHandle::Texture(_)) {
+            bail!("{} can't be used as lut", ctx.resource_pool.named(lut));
+        }
+        let lut_texture = ctx.resource_pool.grab_texture(lut);
+        if !lut_texture
+            .usage
+            .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+        {
+            bail!(
+                "lut {} can't be sampled, create it with CoGr::texture_sampled",
+                lut_texture.name
+            );
+        }
+        if lut_texture.format.sample_type(None)
+            != Some(wgpu::TextureSampleType::Float { filterable: true })
+        {
+            bail!(
+                "lut {} has format {:?}, which can't be filtered",
+                lut_texture.name,
+                lut_texture.format
+            );
+        }
+        let (width, height, depth) = lut_texture.size;
+        let is_3d = width == height && height == depth && depth > 1;
+        let is_strip = depth == 1 && width == height * height;
+        if !is_3d && !is_strip {
+            bail!(
+                "lut {} is {:?}, it has to be n x n x n or a strip of n * n x n",
+                lut_texture.name,
+                lut_texture.size
+            );
+        }
+        self.record_to_screen(to_screen_texture, false, Some(lut))
+    }
+    fn record_to_screen(
+        &mut self,
+        to_screen_texture: &ResourceHandle,
+        auto_exposure: bool,
+        lut: Option<&ResourceHandle>,
+    ) -> Result<()> {
         puffin::profile_function!();
         let encoder = &mut self.encoder.as_mut().expect("there was no encoder");
         let ctx = &mut encoder.gpu_context;
@@ -65,6 +146,60 @@ impl<'a> DrawEncoder<'a> {
             {
                 let texture = ctx.resource_pool.grab_texture(to_screen_texture);
                 let texture_view = texture.texture_view.as_ref().unwrap();
+                let lut_texture = lut.map(|lut| ctx.resource_pool.grab_texture(lut));
+                let lut_changed = match (lut, lut_texture, &ctx.last_to_screen_lut) {
+                    (None, _, None) => false,
+                    (Some(lut), Some(lut_texture), Some((last_lut, last_generation))) => {
+                        !lut.ptr_eq(last_lut) || lut_texture.generation != *last_generation
+                    }
+                    _ => true,
+                };
+
+                if ctx.last_to_screen_texture_handle.is_none()
+                    || !to_screen_texture
+                        .ptr_eq(ctx.last_to_screen_texture_handle.as_ref().unwrap())
+                    || ctx.last_to_screen_generation != texture.generation
+                    || ctx.auto_exposure.is_some() != auto_exposure
+                    || lut_changed
+                {
+                    ctx.last_to_screen_texture_handle = Some(to_screen_texture.clone());
+                    ctx.last_to_screen_generation = texture.generation;
+                    ctx.auto_exposure = match auto_exposure {
+                        true => Some(AutoExposure::new(
+                            &ctx.device,
+                            texture_view,
+                            texture.format,
+                            (texture.size.0, texture.size.1),
+                        )),
+                        false => None,
+                    };
+                    ctx.last_to_screen_lut = lut
+                        .zip(lut_texture)
+                        .map(|(lut, lut_texture)| (lut.clone(), lut_texture.generation));
+                    ctx.last_to_screen_pipeline = Some(ToScreenPipeline::new(
+                        &ctx.device,
+                        texture_view,
+                        texture.format,
+                        ctx.auto_exposure.as_ref().map(|a| &a.exposure),
+                        lut_texture.map(|lut_texture| {
+                            (
+                                lut_texture.texture_view.as_ref().unwrap(),
+                                lut_texture.view_dims,
+                            )
+                        }),
+                        ctx.surface_view_format(),
+                    ));
+                }
+
+                if let Some(auto_exposure) = &ctx.auto_exposure {
+                    auto_exposure.record(
+                        &ctx.queue,
+                        command_encoder,
+                        ctx.frame_times.back().copied().unwrap_or(0.0),
+                        ctx.auto_exposure_speed,
+                        ctx.auto_exposure_key,
+                    );
+                }
 
                 let mut render_pass =
                     command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -80,18 +215,6 @@ impl<'a> DrawEncoder<'a> {
                         depth_stencil_attachment: None,
                     });
 
-                if ctx.last_to_screen_texture_handle.is_none()
-                    || !to_screen_texture
-                        .ptr_eq(ctx.last_to_screen_texture_handle.as_ref().unwrap())
-                {
-                    ctx.last_to_screen_texture_handle = Some(to_screen_texture.clone());
-                    ctx.last_to_screen_pipeline = Some(ToScreenPipeline::new(
-                        &ctx.device,
-                        &texture.texture_view,
-                        texture.format,
-                    ));
-                }
-
                 // run pipeline
                 let pipeline = ctx.last_to_screen_pipeline.as_ref().unwrap();
                 render_pass.set_pipeline(&pipeline.pipeline); // 2.
@@ -103,22 +226,200 @@ impl<'a> DrawEncoder<'a> {
         Ok(())
     }
 
-    fn draw_gpu_timings(egui_ctx: &egui::Context, frame_timings: &Vec<GpuTimerScopeResult>) {
+    // submits all recorded work but doesn't show the surface texture on screen
+    pub fn submit_without_present(mut self) {
+        self.present = false;
+    }
+
+    // dispatches a pipeline with the output bound at binding 0 followed by the given resources. when the surface
+    // can be a storage texture the surface itself is bound and written at window resolution, skipping the srgb
+    // encoding. otherwise an intermediate at render resolution is bound and drawn with to_screen. binding 0 has
+    // to be declared with the format of CoGr::surface_output_format
+    pub fn dispatch_to_surface(
+        &mut self,
+        pipeline: &mut Pipeline,
+        resources: &[&ResourceHandle],
+    ) -> Result<()> {
+        puffin::profile_function!();
+        let surface_output = self.gpu_context.surface_output()?;
+        let mut bindings = vec![&surface_output];
+        bindings.extend_from_slice(resources);
+        let size = match &self.storage_view {
+            Some(_) => (
+                self.gpu_context.config.width,
+                self.gpu_context.config.height,
+                1,
+            ),
+            None => (self.width(), self.height(), 1),
+        };
+        let work_groups = pipeline.work_groups(size);
+        check_dispatch_coverage(
+            pipeline,
+            (
+                work_groups.0 * pipeline.workgroup_size[0],
+                work_groups.1 * pipeline.workgroup_size[1],
+                work_groups.2 * pipeline.workgroup_size[2],
+            ),
+            size,
+        );
+        let Some(storage_view) = &self.storage_view else {
+            self.dispatch_pipeline(pipeline, work_groups, &bindings)?;
+            return self.to_screen(&surface_output);
+        };
+        self.encoder
+            .as_mut()
+            .context("encoder not available")?
+            .dispatch(pipeline, work_groups, &bindings, &[], Some(storage_view))
+    }
+
+    // draws a mesh directly to the screen, the index buffer can contain either u16 or u32 elements
+    pub fn draw_mesh(
+        &mut self,
+        pipeline: &mut MeshPipeline,
+        vertices: &ResourceHandle,
+        indices: Option<&ResourceHandle>,
+        instances: u32,
+        resources: &[&ResourceHandle],
+    ) -> Result<()> {
+        puffin::profile_function!();
+        self.record_mesh(
+            pipeline,
+            vertices,
+            MeshDraw::Direct { indices, instances },
+            resources,
+        )
+    }
+
+    // draws a mesh with the draw arguments read from a buffer, which can be filled by a compute shader
+    pub fn draw_mesh_indirect(
+        &mut self,
+        pipeline: &mut MeshPipeline,
+        vertices: &ResourceHandle,
+        draw_arguments: &ResourceHandle,
+        resources: &[&ResourceHandle],
+    ) -> Result<()> {
+        puffin::profile_function!();
+        self.record_mesh(
+            pipeline,
+            vertices,
+            MeshDraw::Indirect(draw_arguments),
+            resources,
+        )
+    }
+
+    fn record_mesh(
+        &mut self,
+        pipeline: &mut MeshPipeline,
+        vertices: &ResourceHandle,
+        draw: MeshDraw,
+        resources: &[&ResourceHandle],
+    ) -> Result<()> {
+        let encoder = &mut self.encoder.as_mut().expect("there was no encoder");
+        let ctx = &mut encoder.gpu_context;
+        trace!(
+            pipeline = %pipeline.pipeline_name,
+            vertices = %ctx.resource_pool.named(vertices),
+            resources = ?resources
+                .iter()
+                .map(|handle| ctx.resource_pool.named(handle))
+                .collect::<Vec<_>>(),
+            "draw mesh"
+        );
+        pipeline.check_hot_reload(ctx, resources);
+        let command_encoder = encoder
+            .command_encoder
+            .as_mut()
+            .context("encoder not available")?;
+
+        wgpu_profiler!(
+            &pipeline.pipeline_name,
+            &mut ctx.profiler,
+            command_encoder,
+            &ctx.device,
+            {
+                let bind_group_hash = ctx.resource_pool.hash_bind_group(resources);
+                if pipeline.last_bind_group.is_none()
+                    || bind_group_hash != pipeline.last_bind_group_hash
+                {
+                    let texture_array_views = texture_array_views(&ctx.resource_pool, resources);
+                    let bind_group_entries =
+                        bind_group_entries(&ctx.resource_pool, resources, &texture_array_views);
+                    pipeline.last_bind_group =
+                        Some(ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                            label: Some("mesh bind group"),
+                            layout: &pipeline.bind_group_layout,
+                            entries: bind_group_entries.as_slice(),
+                        }));
+                    pipeline.last_bind_group_hash = bind_group_hash;
+                }
+
+                let mut render_pass = command_encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("mesh render pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &self.texture_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                render_pass.set_pipeline(&pipeline.pipeline);
+                render_pass.set_bind_group(0, pipeline.last_bind_group.as_ref().unwrap(), &[]);
+                let vertex_buffer = ctx.resource_pool.grab_buffer(vertices);
+                render_pass.set_vertex_buffer(0, vertex_buffer.buffer.as_ref().unwrap().slice(..));
+                match draw {
+                    MeshDraw::Direct {
+                        indices: Some(indices),
+                        instances,
+                    } => {
+                        let index_buffer = ctx.resource_pool.grab_buffer(indices);
+                        let index_format = match index_buffer.element_size {
+                            2 => Uint16,
+                            4 => Uint32,
+                            size => {
+                                bail!("index buffer elements must be 2 or 4 bytes, not {}", size)
+                            }
+                        };
+                        render_pass.set_index_buffer(
+                            index_buffer.buffer.as_ref().unwrap().slice(..),
+                            index_format,
+                        );
+                        let index_count = ctx.resource_pool.element_count(indices) as u32;
+                        render_pass.draw_indexed(0..index_count, 0, 0..instances);
+                    }
+                    MeshDraw::Direct {
+                        indices: None,
+                        instances,
+                    } => {
+                        let vertex_count = ctx.resource_pool.element_count(vertices) as u32;
+                        render_pass.draw(0..vertex_count, 0..instances);
+                    }
+                    MeshDraw::Indirect(draw_arguments) => {
+                        let draw_arguments = ctx.resource_pool.grab_buffer(draw_arguments);
+                        render_pass.draw_indirect(draw_arguments.buffer.as_ref().unwrap(), 0);
+                    }
+                }
+            }
+        );
+        Ok(())
+    }
+
+    fn draw_gpu_timings(
+        egui_ctx: &egui::Context,
+        frame_timings: &Vec<GpuTimerScopeResult>,
+        timing_metrics: &[TimingMetric],
+        frames_in_flight: usize,
+    ) {
         puffin::profile_function!();
 
         egui::Window::new("gpu_timings").show(egui_ctx, |ui: &mut Ui| {
             egui::Grid::new("gpu_timings_grid").show(ui, |ui| {
                 let mut time_sum = 0.0;
                 for timing in frame_timings {
-                    assert!(
-                        timing.nested_scopes.is_empty(),
-                        "we dont ever wanna capture nested scopes"
-                    );
-                    let time = timing.time.end - timing.time.start;
-                    ui.label(format!("{}:", timing.label,));
-                    ui.label(format!("{:.4}ms", time * 1000.0));
-                    ui.end_row();
-                    time_sum += time;
+                    Self::draw_timing(ui, timing, 0);
+                    time_sum += timing.time.end - timing.time.start;
                 }
                 ui.separator();
                 ui.separator();
@@ -128,14 +429,63 @@ impl<'a> DrawEncoder<'a> {
                 ui.end_row();
                 ui.label("fps:");
                 ui.label(format!("{:.4}fps", 1.0 / time_sum));
+                ui.end_row();
+                ui.label("frames in flight:");
+                ui.label(format!("{}", frames_in_flight));
+                ui.end_row();
+                for metric in timing_metrics {
+                    ui.label(format!("{}:", metric.label));
+                    ui.horizontal(|ui| Self::draw_time_graph(ui, &metric.times));
+                    ui.end_row();
+                }
             });
         });
     }
 
+    // a row per scope, nested scopes from Encoder::scope are indented below their parent
+    fn draw_timing(ui: &mut Ui, timing: &GpuTimerScopeResult, depth: usize) {
+        let time = timing.time.end - timing.time.start;
+        ui.label(format!("{}{}:", "    ".repeat(depth), timing.label));
+        ui.label(format!("{:.4}ms", time * 1000.0));
+        ui.end_row();
+        for nested in &timing.nested_scopes {
+            Self::draw_timing(ui, nested, depth + 1);
+        }
+    }
+
+    // sparkline of the most recent times, scaled to the slowest time in the history
+    fn draw_time_graph(ui: &mut Ui, frame_times: &VecDeque<f32>) {
+        puffin::profile_function!();
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(150.0, 16.0), egui::Sense::hover());
+        let max_time = frame_times.iter().cloned().fold(f32::EPSILON, f32::max);
+        let points = frame_times
+            .iter()
+            .enumerate()
+            .map(|(i, time)| {
+                egui::pos2(
+                    rect.left() + rect.width() * i as f32 / FRAME_TIME_HISTORY as f32,
+                    rect.bottom() - rect.height() * time / max_time,
+                )
+            })
+            .collect::<Vec<_>>();
+        ui.painter().add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.0, ui.visuals().text_color()),
+        ));
+        if let Some(last) = frame_times.back() {
+            ui.label(format!("{:.2}ms", last * 1000.0));
+        }
+    }
+
     pub fn draw_ui(&mut self, ui_builder: impl FnOnce(&egui::Context)) -> Result<()> {
         puffin::profile_function!();
         let encoder = &mut self.encoder.as_mut().expect("there was no encoder");
         let ctx = &mut encoder.gpu_context;
+        ctx.update_egui_textures();
+        let raw_input = match &mut ctx.window {
+            Some(window) => window.state.take_egui_input(window.window.as_ref()),
+            None => egui::RawInput::default(),
+        };
         let command_encoder = encoder
             .command_encoder
             .as_mut()
@@ -151,48 +501,97 @@ impl<'a> DrawEncoder<'a> {
                     size_in_pixels: [ctx.config.width, ctx.config.height],
                     pixels_per_point: 1f32,
                 };
-                let full_output =
-                    ctx.context
-                        .run(ctx.state.take_egui_input(ctx.window.as_ref()), |egui_ctx| {
-                            egui::TopBottomPanel::top("top_bar").show(egui_ctx, |ui| {
-                                ui.horizontal_wrapped(|ui| {
-                                    if ui
-                                        .selectable_label(ctx.draw_cpu_profiler, "cpu_profiler")
-                                        .clicked()
-                                    {
-                                        ctx.draw_cpu_profiler ^= true;
-                                    }
-                                    if ui
-                                        .selectable_label(ctx.draw_gpu_profiler, "gpu_profiler")
-                                        .clicked()
-                                    {
-                                        ctx.draw_gpu_profiler ^= true;
-                                    }
-                                    if ui.selectable_label(ctx.draw_user_ui, "user_ui").clicked() {
-                                        ctx.draw_user_ui ^= true;
-                                    }
-                                });
-                            });
-
-                            if ctx.draw_gpu_profiler {
-                                Self::draw_gpu_timings(egui_ctx, &ctx.frame_timings);
+                let full_output = ctx.context.run(raw_input, |egui_ctx| {
+                    egui::TopBottomPanel::top("top_bar").show(egui_ctx, |ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            if ui
+                                .selectable_label(ctx.draw_cpu_profiler, "cpu_profiler")
+                                .clicked()
+                            {
+                                ctx.draw_cpu_profiler ^= true;
+                            }
+                            if ui
+                                .selectable_label(ctx.draw_gpu_profiler, "gpu_profiler")
+                                .clicked()
+                            {
+                                ctx.draw_gpu_profiler ^= true;
+                            }
+                            if ui.selectable_label(ctx.draw_user_ui, "user_ui").clicked() {
+                                ctx.draw_user_ui ^= true;
+                            }
+                            if ui
+                                .selectable_label(ctx.draw_frame_graph, "frame_graph")
+                                .clicked()
+                            {
+                                ctx.draw_frame_graph ^= true;
+                            }
+                            if ui
+                                .selectable_label(ctx.draw_shader_editor, "shader_editor")
+                                .clicked()
+                            {
+                                ctx.draw_shader_editor ^= true;
                             }
-                            if ctx.draw_cpu_profiler {
-                                puffin_egui::profiler_window(egui_ctx);
+                            if ctx.auto_exposure.is_some()
+                                && ui
+                                    .selectable_label(ctx.draw_auto_exposure, "auto_exposure")
+                                    .clicked()
+                            {
+                                ctx.draw_auto_exposure ^= true;
                             }
-                            if ctx.draw_user_ui {
-                                ui_builder(egui_ctx);
+                            for section in ctx.top_bar_sections.iter_mut() {
+                                ui.separator();
+                                section(ui);
+                            }
+                            if ctx.draw_frame_graph {
+                                Self::draw_time_graph(ui, &ctx.frame_times);
                             }
                         });
+                    });
+
+                    if ctx.draw_gpu_profiler {
+                        Self::draw_gpu_timings(
+                            egui_ctx,
+                            &ctx.frame_timings,
+                            &ctx.timing_metrics,
+                            ctx.submissions_in_flight.len(),
+                        );
+                    }
+                    if ctx.draw_cpu_profiler {
+                        puffin_egui::profiler_window(egui_ctx);
+                    }
+                    if ctx.draw_shader_editor
+                        && ctx.shader_editor.draw(egui_ctx, &ctx.pipeline_infos)
+                    {
+                        ctx.reload_generation += 1;
+                    }
+                    if ctx.draw_auto_exposure && ctx.auto_exposure.is_some() {
+                        egui::Window::new("auto_exposure").show(egui_ctx, |ui| {
+                            ui.add(
+                                egui::Slider::new(&mut ctx.auto_exposure_speed, 0.0..=10.0)
+                                    .text("adaptation speed"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut ctx.auto_exposure_key, 0.01..=1.0)
+                                    .text("key"),
+                            );
+                        });
+                    }
+                    if ctx.draw_user_ui {
+                        ui_builder(egui_ctx);
+                    }
+                });
 
                 let paint_jobs = ctx.context.tessellate(full_output.shapes);
                 let tdelta = full_output.textures_delta;
 
-                {
-                    for d in tdelta.set {
-                        ctx.renderer
-                            .update_texture(&ctx.device, &ctx.queue, d.0, &d.1);
+                for (id, delta) in &tdelta.set {
+                    ctx.renderer
+                        .update_texture(&ctx.device, &ctx.queue, *id, delta);
+                    if let Some((_, renderer)) = &mut ctx.ui_target_renderer {
+                        renderer.update_texture(&ctx.device, &ctx.queue, *id, delta);
                     }
+                }
+                if ctx.ui_on_screen {
                     ctx.renderer.update_buffers(
                         &ctx.device,
                         &ctx.queue,
@@ -219,6 +618,46 @@ impl<'a> DrawEncoder<'a> {
                         &screen_descriptor,
                     );
                 }
+                if let (Some(target), Some((_, renderer))) =
+                    (&ctx.ui_target, &mut ctx.ui_target_renderer)
+                {
+                    let texture = ctx.resource_pool.grab_texture(target);
+                    // the ui keeps its layout in points and is scaled to the width of the target
+                    let target_descriptor = ScreenDescriptor {
+                        size_in_pixels: [texture.size.0, texture.size.1],
+                        pixels_per_point: texture.size.0 as f32 / ctx.config.width as f32,
+                    };
+                    renderer.update_buffers(
+                        &ctx.device,
+                        &ctx.queue,
+                        command_encoder,
+                        &paint_jobs,
+                        &target_descriptor,
+                    );
+                    let mut render_pass =
+                        command_encoder.begin_render_pass(&RenderPassDescriptor {
+                            label: Some("ui target render pass"),
+                            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                                view: texture
+                                    .texture_view
+                                    .as_ref()
+                                    .context("texture was not allocated")?,
+                                resolve_target: None,
+                                ops: wgpu::Operations {
+                                    load: wgpu::LoadOp::Load,
+                                    store: true,
+                                },
+                            })],
+                            ..Default::default()
+                        });
+                    renderer.render(&mut render_pass, paint_jobs.as_slice(), &target_descriptor);
+                }
+                for id in &tdelta.free {
+                    ctx.renderer.free_texture(id);
+                    if let Some((_, renderer)) = &mut ctx.ui_target_renderer {
+                        renderer.free_texture(id);
+                    }
+                }
             }
         );
         Ok(())
@@ -226,11 +665,304 @@ impl<'a> DrawEncoder<'a> {
 }
 
 impl Encoder<'_> {
+    // size of full resolution textures, this differs from the window size when a render scale is set
     pub fn width(&self) -> u32 {
-        self.gpu_context.config.width
+        self.gpu_context.render_size().0
     }
     pub fn height(&self) -> u32 {
-        self.gpu_context.config.height
+        self.gpu_context.render_size().1
+    }
+    // groups everything recorded in the closure under one named scope in the gpu profiler
+    pub fn scope(&mut self, name: &str, f: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let encoder = self
+            .command_encoder
+            .as_mut()
+            .context("encoder not available")?;
+        self.gpu_context
+            .profiler
+            .begin_scope(name, encoder, &self.gpu_context.device);
+        let result = f(self);
+        let encoder = self
+            .command_encoder
+            .as_mut()
+            .context("encoder not available")?;
+        self.gpu_context.profiler.end_scope(encoder);
+        result
+    }
+    // submits everything recorded so far and continues in a fresh command encoder, so the gpu can start working
+    // while the cpu records the rest of the frame. the profiler frame only ends when the encoder is dropped, so
+    // this can't be called inside Encoder::scope
+    pub fn flush(&mut self) -> Result<()> {
+        puffin::profile_function!();
+        let mut command_encoder = self
+            .command_encoder
+            .take()
+            .context("encoder not available")?;
+        command_encoder.pop_debug_group();
+        self.gpu_context
+            .profiler
+            .resolve_queries(&mut command_encoder);
+        let submission = self
+            .gpu_context
+            .queue
+            .submit(std::iter::once(command_encoder.finish()));
+        self.gpu_context.limit_frames_in_flight(submission);
+
+        let mut command_encoder =
+            self.gpu_context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+        command_encoder.push_debug_group("user_encoder");
+        self.command_encoder = Some(command_encoder);
+        Ok(())
+    }
+    // runs one invocation per element using the x workgroup size of the entry point. when that needs more
+    // workgroups than allowed in one dimension they are split over y and z, so the shader should compute its index as
+    // id.x + (id.y + id.z * num_workgroups.y) * num_workgroups.x * workgroup_size.x. when the shader declares a
+    // var<push_constant> the element count is passed as its first u32, indices past it should be skipped
+    pub fn dispatch_1d(
+        &mut self,
+        pipeline: &mut Pipeline,
+        element_count: u32,
+        resources: &[&ResourceHandle],
+    ) -> Result<()> {
+        let work_groups = div_ceil(element_count, pipeline.workgroup_size[0]);
+        let max_work_groups = self
+            .gpu_context
+            .device
+            .limits()
+            .max_compute_workgroups_per_dimension;
+        let work_groups = split_work_groups(work_groups, max_work_groups)?;
+        // split dispatches round up over y and z, those always overshoot
+        if work_groups.1 == 1 && work_groups.2 == 1 {
+            let invocations = work_groups.0 * pipeline.workgroup_size[0];
+            check_dispatch_coverage(pipeline, (invocations, 1, 1), (element_count, 1, 1));
+        }
+        let push_constants = match pipeline.push_constant_size >= 4 {
+            true => element_count.to_ne_bytes().to_vec(),
+            false => Vec::new(),
+        };
+        self.dispatch(pipeline, work_groups, resources, &push_constants, None)
+    }
+    // runs one invocation per cell of a volume using the 3d workgroup size of the entry point, so the
+    // global_invocation_id is the cell. when the shader declares a var<push_constant> of at least 12 bytes the volume
+    // size is passed as its first three u32s, cells past it should be skipped
+    pub fn dispatch_3d(
+        &mut self,
+        pipeline: &mut Pipeline,
+        size: (u32, u32, u32),
+        resources: &[&ResourceHandle],
+    ) -> Result<()> {
+        let work_groups = pipeline.work_groups(size);
+        let max_work_groups = self
+            .gpu_context
+            .device
+            .limits()
+            .max_compute_workgroups_per_dimension;
+        if work_groups.0 > max_work_groups
+            || work_groups.1 > max_work_groups
+            || work_groups.2 > max_work_groups
+        {
+            bail!(
+                "{} needs {:?} workgroups to cover a volume of {:?}, at most {} per dimension are allowed",
+                pipeline.pipeline_name,
+                work_groups,
+                size,
+                max_work_groups
+            );
+        }
+        let invocations = (
+            work_groups.0 * pipeline.workgroup_size[0],
+            work_groups.1 * pipeline.workgroup_size[1],
+            work_groups.2 * pipeline.workgroup_size[2],
+        );
+        check_dispatch_coverage(pipeline, invocations, size);
+        let push_constants = match pipeline.push_constant_size >= 12 {
+            true => bytemuck::cast_slice(&[size.0, size.1, size.2]).to_vec(),
+            false => Vec::new(),
+        };
+        self.dispatch(pipeline, work_groups, resources, &push_constants, None)
+    }
+    // one invocation per texel of output, which has to be one of the resources. for passes whose output has another
+    // resolution than the screen or than their inputs, like a half res blur or a small histogram image
+    pub fn dispatch_for_texture(
+        &mut self,
+        pipeline: &mut Pipeline,
+        output: &ResourceHandle,
+        resources: &[&ResourceHandle],
+    ) -> Result<()> {
+        if !resources.iter().any(|resource| resource.ptr_eq(output)) {
+            bail!(
+                "{} isn't bound to {}, the dispatch can't cover it",
+                self.gpu_context.resource_pool.named(output),
+                pipeline.pipeline_name
+            );
+        }
+        let work_groups = self.work_groups_for_texture(pipeline, output)?;
+        self.dispatch(pipeline, work_groups, resources, &[], None)
+    }
+    // the workgroups needed to run one invocation per texel of the texture with the workgroup size of the pipeline,
+    // for dispatches that also set push constants
+    pub fn work_groups_for_texture(
+        &self,
+        pipeline: &mut Pipeline,
+        texture: &ResourceHandle,
+    ) -> Result<(u32, u32, u32)> {
+        if !matches!(texture, ResourceHandle::Texture(_)) {
+            bail!(
+                "{} is not a texture",
+                self.gpu_context.resource_pool.named(texture)
+            );
+        }
+        let size = self.gpu_context.resource_pool.grab_texture(texture).size;
+        let work_groups = pipeline.work_groups(size);
+        let invocations = (
+            work_groups.0 * pipeline.workgroup_size[0],
+            work_groups.1 * pipeline.workgroup_size[1],
+            work_groups.2 * pipeline.workgroup_size[2],
+        );
+        check_dispatch_coverage(pipeline, invocations, size);
+        Ok(work_groups)
+    }
+    // like dispatch_pipeline, but also sets the var<push_constant> of the shader
+    pub fn dispatch_pipeline_with_constants<T: Pod>(
+        &mut self,
+        pipeline: &mut Pipeline,
+        work_groups: (u32, u32, u32),
+        resources: &[&ResourceHandle],
+        constants: &T,
+    ) -> Result<()> {
+        let push_constants = bytemuck::bytes_of(constants);
+        if push_constants.len() as u32 > pipeline.push_constant_size
+            || push_constants.len() % 4 != 0
+        {
+            bail!(
+                "{} push constants of {} bytes don't fit in the {} bytes the shader declares, they have to be a multiple of 4",
+                pipeline.pipeline_name,
+                push_constants.len(),
+                pipeline.push_constant_size
+            );
+        }
+        self.dispatch(pipeline, work_groups, resources, push_constants, None)
+    }
+    // draws a texture into another one the way to_screen draws to the window, scaled to the size of the target. the
+    // target has to be created with CoGr::texture_render_target. this works without a window, so the result can be
+    // composited offscreen and saved with CoGr::save_png
+    pub fn to_texture(&mut self, source: &ResourceHandle, target: &ResourceHandle) -> Result<()> {
+        puffin::profile_function!();
+        let ctx = &mut self.gpu_context;
+        if !matches!(source, ResourceHandle::Texture(_))
+            || !matches!(target, ResourceHandle::Texture(_))
+        {
+            bail!(
+                "to_texture draws a texture into a texture, got {} and {}",
+                ctx.resource_pool.named(source),
+                ctx.resource_pool.named(target)
+            );
+        }
+        let target_texture = ctx.resource_pool.grab_texture(target);
+        if !target_texture
+            .usage
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            bail!(
+                "{} can't be drawn to, create it with CoGr::texture_render_target",
+                target_texture.name
+            );
+        }
+        let source_texture = ctx.resource_pool.grab_texture(source);
+
+        ctx.to_texture_pipelines
+            .retain(|pipeline| pipeline.source.upgrade().is_some());
+        let cached = ctx.to_texture_pipelines.iter().position(|pipeline| {
+            pipeline
+                .source
+                .upgrade()
+                .is_some_and(|handle| handle.ptr_eq(source))
+                && pipeline.generation == source_texture.generation
+                && pipeline.target_format == target_texture.format
+        });
+        let pipeline_index = match cached {
+            Some(index) => index,
+            None => {
+                ctx.to_texture_pipelines.retain(|pipeline| {
+                    !pipeline
+                        .source
+                        .upgrade()
+                        .is_some_and(|handle| handle.ptr_eq(source))
+                        || pipeline.target_format != target_texture.format
+                });
+                ctx.to_texture_pipelines.push(ToTexturePipeline {
+                    source: source.downgrade(),
+                    generation: source_texture.generation,
+                    target_format: target_texture.format,
+                    pipeline: ToScreenPipeline::new(
+                        &ctx.device,
+                        source_texture
+                            .texture_view
+                            .as_ref()
+                            .context("texture was not allocated")?,
+                        source_texture.format,
+                        None,
+                        None,
+                        target_texture.format,
+                    ),
+                });
+                ctx.to_texture_pipelines.len() - 1
+            }
+        };
+
+        let command_encoder = self
+            .command_encoder
+            .as_mut()
+            .context("encoder not available")?;
+        wgpu_profiler!(
+            "to_texture",
+            &mut ctx.profiler,
+            command_encoder,
+            &ctx.device,
+            {
+                let mut render_pass =
+                    command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("To texture render pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: target_texture
+                                .texture_view
+                                .as_ref()
+                                .context("texture was not allocated")?,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: true,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                    });
+                let pipeline = &ctx.to_texture_pipelines[pipeline_index].pipeline;
+                render_pass.set_pipeline(&pipeline.pipeline);
+                render_pass.set_bind_group(0, &pipeline.bind_group, &[]);
+                render_pass.set_index_buffer(pipeline.index_buffer.slice(..), Uint16);
+                render_pass.draw_indexed(0..pipeline.num_indices, 0, 0..1);
+            }
+        );
+        Ok(())
+    }
+    // like dispatch_pipeline, but every resource comes with the name of the shader variable it's bound to, so the
+    // order doesn't matter and the shader can add or reorder bindings without breaking the call
+    pub fn dispatch_named(
+        &mut self,
+        pipeline: &mut Pipeline,
+        work_groups: (u32, u32, u32),
+        resources: &[(&str, &ResourceHandle)],
+    ) -> Result<()> {
+        let resources = pipeline.named_bindings(resources)?;
+        self.dispatch(pipeline, work_groups, &resources, &[], None)
+    }
+    // the workgroup counts of the last dispatch recorded with this encoder, to check a dispatch covers what it should
+    pub fn last_dispatch_dims(&self) -> Option<(u32, u32, u32)> {
+        self.last_dispatch_dims
     }
     // todo: change resources to accept either texture or buffer handle
     pub fn dispatch_pipeline(
@@ -238,9 +970,53 @@ impl Encoder<'_> {
         pipeline: &mut Pipeline,
         work_groups: (u32, u32, u32),
         resources: &[&ResourceHandle],
+    ) -> Result<()> {
+        self.dispatch(pipeline, work_groups, resources, &[], None)
+    }
+    fn dispatch(
+        &mut self,
+        pipeline: &mut Pipeline,
+        work_groups: (u32, u32, u32),
+        resources: &[&ResourceHandle],
+        push_constants: &[u8],
+        surface_view: Option<&TextureView>,
     ) -> Result<()> {
         puffin::profile_function!();
-        pipeline.check_hot_reload(&self.gpu_context, resources);
+        trace!(
+            pipeline = %pipeline.pipeline_name,
+            workgroups = ?work_groups,
+            push_constant_bytes = push_constants.len(),
+            resources = ?resources
+                .iter()
+                .map(|handle| self.gpu_context.resource_pool.named(handle))
+                .collect::<Vec<_>>(),
+            "dispatch"
+        );
+        let total_work_groups = work_groups.0 as u64 * work_groups.1 as u64 * work_groups.2 as u64;
+        match self.gpu_context.workgroup_ceiling {
+            WorkgroupCeiling::Error(ceiling) if total_work_groups > ceiling => bail!(
+                "{} dispatches {:?} workgroups, {} in total, which is more than the ceiling of {}. the dispatch might be given pixels instead of workgroups",
+                pipeline.pipeline_name,
+                work_groups,
+                total_work_groups,
+                ceiling
+            ),
+            WorkgroupCeiling::Warn(ceiling)
+                if total_work_groups > ceiling && !pipeline.ceiling_warned =>
+            {
+                warn!(
+                    pipeline = %pipeline.pipeline_name,
+                    workgroups = ?work_groups,
+                    total = total_work_groups,
+                    ceiling,
+                    "dispatch goes over the workgroup ceiling, it might be given pixels instead of workgroups"
+                );
+                pipeline.ceiling_warned = true;
+            }
+            _ => (),
+        }
+        pipeline.check_hot_reload(self.gpu_context, resources);
+        self.last_dispatch_dims = Some(work_groups);
         let encoder = self
             .command_encoder
             .as_mut()
@@ -252,35 +1028,46 @@ impl Encoder<'_> {
             encoder,
             &self.gpu_context.device,
             {
+                // the surface changes every frame, so its bind group isn't cached and the cached one gets rebuilt
+                // by the next dispatch
+                let surface_bind_group = surface_view.map(|surface_view| {
+                    let texture_array_views =
+                        texture_array_views(&self.gpu_context.resource_pool, resources);
+                    let mut bind_group_entries = bind_group_entries(
+                        &self.gpu_context.resource_pool,
+                        resources,
+                        &texture_array_views,
+                    );
+                    for entry in bind_group_entries
+                        .iter_mut()
+                        .filter(|entry| entry.binding == 0)
+                    {
+                        entry.resource = wgpu::BindingResource::TextureView(surface_view);
+                    }
+                    pipeline.last_bind_group = None;
+                    self.gpu_context
+                        .device
+                        .create_bind_group(&wgpu::BindGroupDescriptor {
+                            label: Some("surface bind group"),
+                            layout: &pipeline.bind_group_layout,
+                            entries: bind_group_entries.as_slice(),
+                        })
+                });
                 let mut compute_pass =
                     encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
                 // hash resources to check if we can reuse the previous bind group of this pipeline
-                let mut hasher = DefaultHasher::new();
-                resources.hash(&mut hasher);
-                let last_bind_group_hash = hasher.finish();
-                if last_bind_group_hash != pipeline.last_bind_group_hash {
-                    let bind_group_entries = resources
-                        .iter()
-                        .enumerate()
-                        .map(|(i, val)| wgpu::BindGroupEntry {
-                            binding: i as u32,
-                            resource: match val {
-                                ResourceHandle::Texture(_) => wgpu::BindingResource::TextureView(
-                                    &self
-                                        .gpu_context
-                                        .resource_pool
-                                        .grab_texture(val)
-                                        .texture_view,
-                                ),
-                                ResourceHandle::Buffer(_) => self
-                                    .gpu_context
-                                    .resource_pool
-                                    .grab_buffer(val)
-                                    .buffer
-                                    .as_entire_binding(),
-                            },
-                        })
-                        .collect::<Vec<wgpu::BindGroupEntry>>();
+                let bind_group_hash = self.gpu_context.resource_pool.hash_bind_group(resources);
+                if surface_bind_group.is_none()
+                    && (pipeline.last_bind_group.is_none()
+                        || bind_group_hash != pipeline.last_bind_group_hash)
+                {
+                    let texture_array_views =
+                        texture_array_views(&self.gpu_context.resource_pool, resources);
+                    let bind_group_entries = bind_group_entries(
+                        &self.gpu_context.resource_pool,
+                        resources,
+                        &texture_array_views,
+                    );
 
                     let bind_group =
                         self.gpu_context
@@ -292,135 +1079,450 @@ impl Encoder<'_> {
                             });
 
                     pipeline.last_bind_group = Some(bind_group);
+                    pipeline.last_bind_group_hash = bind_group_hash;
                 }
 
                 compute_pass.set_pipeline(&pipeline.pipeline);
-                compute_pass.set_bind_group(0, pipeline.last_bind_group.as_ref().unwrap(), &[]);
+                let bind_group = surface_bind_group
+                    .as_ref()
+                    .or(pipeline.last_bind_group.as_ref())
+                    .unwrap();
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                if !push_constants.is_empty() {
+                    compute_pass.set_push_constants(0, push_constants);
+                }
                 compute_pass.dispatch_workgroups(work_groups.0, work_groups.1, work_groups.2);
             }
         );
 
         Ok(())
     }
-    /*
-    pub fn set_buffer_data<T: AnyBitPattern + NoUninit, K: AsRef<[T]>>(
+    // writes a slice to the start of a buffer, fails when it doesn't fit. the copy runs when this encoder is submitted
+    pub fn set_buffer_data<T: Pod, K: AsRef<[T]>>(
         &mut self,
         buffer: &ResourceHandle,
         data: K,
+    ) -> Result<()> {
+        self.set_buffer_data_iter(buffer, data.as_ref().iter().copied())
+    }
+    // writes the elements of an iterator to the start of a buffer without collecting them first, they go straight
+    // into a staging buffer mapped at creation. fails when the iterator produces more elements than fit in the
+    // buffer, iterators with an exact size_hint only get a staging buffer of that size
+    pub fn set_buffer_data_iter<T: Pod, I: IntoIterator<Item = T>>(
+        &mut self,
+        buffer: &ResourceHandle,
+        data: I,
     ) -> Result<()> {
         puffin::profile_function!();
-        let data = data.as_ref();
+        if !matches!(buffer, ResourceHandle::Buffer(_)) {
+            bail!(
+                "{} is not a buffer",
+                self.gpu_context.resource_pool.named(buffer)
+            );
+        }
+        if size_of::<T>() == 0 {
+            bail!("can't write elements of 0 bytes to a buffer");
+        }
+        let pool_buffer = self.gpu_context.resource_pool.grab_buffer(buffer);
+        let capacity = pool_buffer.size as usize / size_of::<T>();
+        let mut data = data.into_iter();
+        let elements = match data.size_hint() {
+            (lower, Some(upper)) if lower == upper => lower,
+            _ => capacity,
+        };
+        if elements > capacity {
+            bail!(
+                "can't write {} elements of {} bytes to {} which fits {}",
+                elements,
+                size_of::<T>(),
+                pool_buffer.name,
+                capacity
+            );
+        }
+        if elements == 0 {
+            return Ok(());
+        }
+
+        let staging_buffer = self
+            .gpu_context
+            .device
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&(pool_buffer.name.clone() + "_upload")),
+                size: wgpu::util::align_to(
+                    (elements * size_of::<T>()) as u64,
+                    wgpu::COPY_BUFFER_ALIGNMENT,
+                ),
+                usage: wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: true,
+            });
+        let mut written = 0;
+        {
+            let mut mapped = staging_buffer.slice(..).get_mapped_range_mut();
+            // the staging buffer is padded to whole words, which must not take more elements
+            let slots = mapped[..elements * size_of::<T>()].chunks_exact_mut(size_of::<T>());
+            for (bytes, element) in slots.zip(data.by_ref()) {
+                bytes.copy_from_slice(bytemuck::bytes_of(&element));
+                written += 1;
+            }
+        }
+        staging_buffer.unmap();
+        if data.next().is_some() {
+            bail!(
+                "the iterator written to {} produced more than the {} elements that fit",
+                pool_buffer.name,
+                elements
+            );
+        }
+        let copy_size = (written * size_of::<T>()) as u64;
+        if copy_size % wgpu::COPY_BUFFER_ALIGNMENT != 0 || copy_size > pool_buffer.size {
+            bail!(
+                "can't copy {} bytes to {} of {} bytes, copies have to be a multiple of {} bytes",
+                copy_size,
+                pool_buffer.name,
+                pool_buffer.size,
+                wgpu::COPY_BUFFER_ALIGNMENT
+            );
+        }
         info!(
-            "writing buffer data to {:?}, from buffer with {} elements",
-            buffer,
-            data.len(),
+            resource = %self.gpu_context.resource_pool.named(buffer),
+            elements = written,
+            bytes = copy_size,
+            "writing buffer data"
         );
+
         let encoder = self
             .command_encoder
             .as_mut()
             .context("encoder not available")?;
-        wgpu_profiler!(
-            "to_screen",
-            &mut self.gpu_context.profiler,
-            encoder,
-            &self.gpu_context.device,
-            {
-                let buffer = self.gpu_context.resource_pool.grab_buffer(buffer);
-                let uploading_buffer =
-                    self.gpu_context
-                        .device
-                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some("uploading Buffer"),
-                            contents: bytemuck::cast_slice(data),
-                            usage: wgpu::BufferUsages::COPY_SRC,
-                        });
-
-                encoder.copy_buffer_to_buffer(
-                    &uploading_buffer,
-                    0,
-                    &buffer.buffer,
-                    0,
-                    size_of_val(data) as u64,
-                );
-            }
+        encoder.copy_buffer_to_buffer(
+            &staging_buffer,
+            0,
+            pool_buffer
+                .buffer
+                .as_ref()
+                .context("buffer was not allocated")?,
+            0,
+            copy_size,
         );
         Ok(())
     }
-
+    // replaces the whole texture with tightly packed rows of texels, T can be a whole texel or a single channel. the
+    // copy runs when this encoder is submitted, in order with the dispatches recorded around it
     pub fn set_texture_data<T: Pod, K: AsRef<[T]>>(
         &mut self,
         texture: &ResourceHandle,
         data: K,
     ) -> Result<()> {
         puffin::profile_function!();
-        let data = data.as_ref();
+        if !matches!(texture, ResourceHandle::Texture(_)) {
+            bail!(
+                "{} is not a texture",
+                self.gpu_context.resource_pool.named(texture)
+            );
+        }
+        let data: &[u8] = bytemuck::cast_slice(data.as_ref());
+        let pool_texture = self.gpu_context.resource_pool.grab_texture(texture);
+        let (width, height, depth) = pool_texture.size;
+        let texel_bytes = pool_texture
+            .format
+            .block_size(None)
+            .context("could not get block size")?;
+        let row_bytes = width as usize * texel_bytes as usize;
+        let rows = height as usize * depth as usize;
+        if data.len() != row_bytes * rows {
+            bail!(
+                "{} of size {:?} takes {} bytes, got {}",
+                pool_texture.name,
+                pool_texture.size,
+                row_bytes * rows,
+                data.len()
+            );
+        }
         info!(
-            "writing texture data to {:?}, the data source has size {}",
-            texture,
-            size_of_val(data)
+            resource = %self.gpu_context.resource_pool.named(texture),
+            bytes = data.len(),
+            "writing texture data"
         );
-
+        // every row in the staging buffer starts at a multiple of the copy alignment
+        let padded_row_bytes =
+            wgpu::util::align_to(row_bytes as u32, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let staging_buffer = self
+            .gpu_context
+            .device
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&(pool_texture.name.clone() + "_upload")),
+                size: padded_row_bytes as u64 * rows as u64,
+                usage: wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: true,
+            });
+        {
+            let mut mapped = staging_buffer.slice(..).get_mapped_range_mut();
+            for (padded_row, row) in mapped
+                .chunks_exact_mut(padded_row_bytes as usize)
+                .zip(data.chunks_exact(row_bytes))
+            {
+                padded_row[..row_bytes].copy_from_slice(row);
+            }
+        }
+        staging_buffer.unmap();
         let encoder = self
             .command_encoder
             .as_mut()
             .context("encoder not available")?;
-        wgpu_profiler!(
-            "to_screen",
-            &mut self.gpu_context.profiler,
-            encoder,
-            &self.gpu_context.device,
-            {
-                let texture = self.gpu_context.resource_pool.grab_texture(texture);
-
-                match texture.resolution {
-                    crate::gpu::TextureRes::Custom(x, y, z) => {
-                        let bytes_per_pixel = texture
-                            .format
-                            .block_size(None)
-                            .expect("could not get block size");
-
-                        if size_of_val(data) / bytes_per_pixel as usize != (x * y * z) as usize {
-                            panic!(
-                                "data had a size of {} while the texture had a size of {}",
-                                size_of_val(data),
-                                (x * y * z) as usize * bytes_per_pixel as usize
-                            );
-                        }
-
-                        let (copy_texture, _) = self.gpu_context.device.init_texture_with_data(
-                            &self.gpu_context.queue,
-                            "copy_texture",
-                            (x, y, z),
-                            texture.format,
-                            bytemuck::cast_slice(data),
-                        )?;
-                        encoder.copy_texture_to_texture(
-                            ImageCopyTexture {
-                                texture: &copy_texture,
-                                mip_level: 0,
-                                origin: Default::default(),
-                                aspect: Default::default(),
-                            },
-                            ImageCopyTexture {
-                                texture: &texture.texture,
-                                mip_level: 0,
-                                origin: Default::default(),
-                                aspect: Default::default(),
-                            },
-                            Extent3d {
-                                width: x,
-                                height: y,
-                                depth_or_array_layers: z,
-                            },
-                        );
-                    }
-                    _ => unimplemented!(),
-                }
-            }
+        encoder.copy_buffer_to_texture(
+            wgpu::ImageCopyBuffer {
+                buffer: &staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            ImageCopyTexture {
+                texture: pool_texture
+                    .texture
+                    .as_ref()
+                    .context("texture was not allocated")?,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: depth,
+            },
         );
-
         Ok(())
-    }*/
+    }
+    // reads a single value back from a buffer, like a counter or a bounding box computed on the gpu. the value
+    // can be taken from the PendingRead once this encoder is submitted
+    pub fn read_one<T: Pod>(&mut self, buffer: &ResourceHandle) -> Result<PendingRead<T>> {
+        let staging_buffer = self.copy_to_staging_buffer(buffer, size_of::<T>() as u64)?;
+        Ok(PendingRead::new(staging_buffer, |bytes| {
+            bytemuck::pod_read_unaligned(&bytes[..size_of::<T>()])
+        }))
+    }
+    // reads a box of texels back, for example the texel under the cursor or one tile of a large texture. T can be a
+    // whole texel or a single channel, the rows are tightly packed in the result
+    pub fn read_texture_region<T: Pod>(
+        &mut self,
+        texture: &ResourceHandle,
+        origin: (u32, u32, u32),
+        size: (u32, u32, u32),
+    ) -> Result<PendingRead<Vec<T>>> {
+        puffin::profile_function!();
+        if !matches!(texture, ResourceHandle::Texture(_)) {
+            bail!(
+                "{} is not a texture",
+                self.gpu_context.resource_pool.named(texture)
+            );
+        }
+        let pool_texture = self.gpu_context.resource_pool.grab_texture(texture);
+        // checked, origin + size can overflow for regions far outside of the texture
+        let fits = |origin: u32, size: u32, texture_size: u32| {
+            size != 0
+                && origin
+                    .checked_add(size)
+                    .is_some_and(|end| end <= texture_size)
+        };
+        if !fits(origin.0, size.0, pool_texture.size.0)
+            || !fits(origin.1, size.1, pool_texture.size.1)
+            || !fits(origin.2, size.2, pool_texture.size.2)
+        {
+            bail!(
+                "region at {:?} of size {:?} doesn't fit in {} of size {:?}",
+                origin,
+                size,
+                pool_texture.name,
+                pool_texture.size
+            );
+        }
+        let texel_bytes = pool_texture
+            .format
+            .block_size(None)
+            .context("could not get block size")?;
+        let row_bytes = size
+            .0
+            .checked_mul(texel_bytes)
+            .context("the rows of the region are too large to read")?;
+        if row_bytes as usize % size_of::<T>() != 0 {
+            bail!(
+                "rows of {} bytes of {:?} can't be read as elements of {} bytes",
+                row_bytes,
+                pool_texture.format,
+                size_of::<T>()
+            );
+        }
+        // every row in the staging buffer starts at a multiple of the copy alignment
+        let padded_row_bytes = row_bytes
+            .checked_add(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT - 1)
+            .map(|bytes| {
+                bytes / wgpu::COPY_BYTES_PER_ROW_ALIGNMENT * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+            })
+            .context("the rows of the region are too large to read")?;
+        let staging_bytes = (padded_row_bytes as u64)
+            .checked_mul(size.1 as u64)
+            .and_then(|bytes| bytes.checked_mul(size.2 as u64))
+            .context("the region is too large to read")?;
+        info!(
+            resource = %self.gpu_context.resource_pool.named(texture),
+            origin = ?origin,
+            size = ?size,
+            "reading texture region"
+        );
+        let staging_buffer = self
+            .gpu_context
+            .device
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&(pool_texture.name.clone() + "_staging")),
+                size: staging_bytes,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+        let encoder = self
+            .command_encoder
+            .as_mut()
+            .context("encoder not available")?;
+        encoder.copy_texture_to_buffer(
+            ImageCopyTexture {
+                texture: pool_texture
+                    .texture
+                    .as_ref()
+                    .context("texture was not allocated")?,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: origin.0,
+                    y: origin.1,
+                    z: origin.2,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(size.1),
+                },
+            },
+            Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: size.2,
+            },
+        );
+        Ok(PendingRead::new(staging_buffer, move |bytes| {
+            bytes
+                .chunks_exact(padded_row_bytes as usize)
+                .flat_map(|row| bytemuck::pod_collect_to_vec::<u8, T>(&row[..row_bytes as usize]))
+                .collect()
+        }))
+    }
+    // reads the whole buffer back and waits for the gpu, so everything recorded so far is submitted first and the
+    // encoder continues with a new command buffer. T has to have the element size the buffer was created with
+    pub fn read_buffer<T: Pod>(&mut self, buffer: &ResourceHandle) -> Result<Vec<T>> {
+        puffin::profile_function!();
+        if !matches!(buffer, ResourceHandle::Buffer(_)) {
+            bail!(
+                "{} is not a buffer",
+                self.gpu_context.resource_pool.named(buffer)
+            );
+        }
+        let pool_buffer = self.gpu_context.resource_pool.grab_buffer(buffer);
+        if pool_buffer.element_size != size_of::<T>() {
+            bail!(
+                "{} has elements of {} bytes, which can't be read as elements of {} bytes",
+                pool_buffer.name,
+                pool_buffer.element_size,
+                size_of::<T>()
+            );
+        }
+        let element_bytes = pool_buffer.size as usize / size_of::<T>() * size_of::<T>();
+        let pending = self.read_buffer_bytes(buffer)?;
+        self.flush()?;
+        let bytes = pending.wait(self.gpu_context)?;
+        Ok(bytemuck::pod_collect_to_vec(&bytes[..element_bytes]))
+    }
+    // reads the whole texture back and waits for the gpu like read_buffer, for example to compare a rendered texture
+    // with a golden image. the rows are tightly packed in the result, T can be a whole texel or a single channel
+    pub fn read_texture<T: Pod>(&mut self, texture: &ResourceHandle) -> Result<Vec<T>> {
+        puffin::profile_function!();
+        if !matches!(texture, ResourceHandle::Texture(_)) {
+            bail!(
+                "{} is not a texture",
+                self.gpu_context.resource_pool.named(texture)
+            );
+        }
+        let size = self.gpu_context.resource_pool.grab_texture(texture).size;
+        let pending = self.read_texture_region(texture, (0, 0, 0), size)?;
+        self.flush()?;
+        pending.wait(self.gpu_context)
+    }
+    // reads back the whole buffer as bytes
+    pub(crate) fn read_buffer_bytes(
+        &mut self,
+        buffer: &ResourceHandle,
+    ) -> Result<PendingRead<Vec<u8>>> {
+        let bytes = self.gpu_context.resource_pool.grab_buffer(buffer).size;
+        let staging_buffer = self.copy_to_staging_buffer(buffer, bytes)?;
+        Ok(PendingRead::new(staging_buffer, move |data| {
+            data[..bytes as usize].to_vec()
+        }))
+    }
+    // copies the first bytes of a buffer into a buffer the cpu can map, rounded up to the copy alignment
+    fn copy_to_staging_buffer(
+        &mut self,
+        buffer: &ResourceHandle,
+        bytes: u64,
+    ) -> Result<wgpu::Buffer> {
+        puffin::profile_function!();
+        if !matches!(buffer, ResourceHandle::Buffer(_)) {
+            bail!(
+                "{} is not a buffer",
+                self.gpu_context.resource_pool.named(buffer)
+            );
+        }
+        let pool_buffer = self.gpu_context.resource_pool.grab_buffer(buffer);
+        let copy_size = wgpu::util::align_to(bytes, wgpu::COPY_BUFFER_ALIGNMENT);
+        if copy_size > pool_buffer.size {
+            bail!(
+                "can't read {} bytes from {} which has a size of {} bytes",
+                copy_size,
+                pool_buffer.name,
+                pool_buffer.size
+            );
+        }
+        info!(
+            resource = %self.gpu_context.resource_pool.named(buffer),
+            bytes = copy_size,
+            "reading buffer data"
+        );
+        let staging_buffer = self
+            .gpu_context
+            .device
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&(pool_buffer.name.clone() + "_staging")),
+                size: copy_size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+        let encoder = self
+            .command_encoder
+            .as_mut()
+            .context("encoder not available")?;
+        encoder.copy_buffer_to_buffer(
+            pool_buffer
+                .buffer
+                .as_ref()
+                .context("buffer was not allocated")?,
+            0,
+            &staging_buffer,
+            0,
+            copy_size,
+        );
+        Ok(staging_buffer)
+    }
 }
 
 impl<'a> Drop for Encoder<'a> {
@@ -430,13 +1532,23 @@ impl<'a> Drop for Encoder<'a> {
         self.gpu_context
             .profiler
             .resolve_queries(self.command_encoder.as_mut().unwrap());
-        self.gpu_context.queue.submit(std::iter::once(
+        let submission = self.gpu_context.queue.submit(std::iter::once(
             self.command_encoder.take().unwrap().finish(),
         ));
+        self.gpu_context.limit_frames_in_flight(submission);
+        if self.gpu_context.validation {
+            // pushed in get_encoder, submitting reports the errors of the recorded commands
+            if let Some(err) = pollster::block_on(self.gpu_context.device.pop_error_scope()) {
+                error!(
+                    frame = self.gpu_context.frame(),
+                    "wgpu validation error: {}", err
+                );
+            }
+        }
 
         self.gpu_context.profiler.end_frame().unwrap();
         if let Some(timings) = self.gpu_context.profiler.process_finished_frame() {
-            self.gpu_context.frame_timings = timings;
+            self.gpu_context.update_timings(timings);
         }
     }
 }
@@ -444,12 +1556,298 @@ impl<'a> Drop for Encoder<'a> {
 impl<'a> Drop for DrawEncoder<'a> {
     fn drop(&mut self) {
         puffin::profile_function!();
-        drop(self.encoder.take());
         let surface = self.surface_texture.take().unwrap();
-        surface.present();
+        let encoder = self.encoder.as_mut().unwrap();
+        if let (Some(previous_frame), Some(command_encoder)) = (
+            encoder.gpu_context.previous_frame.as_ref(),
+            encoder.command_encoder.as_mut(),
+        ) {
+            let previous_frame = encoder
+                .gpu_context
+                .resource_pool
+                .grab_texture(previous_frame);
+            command_encoder.copy_texture_to_texture(
+                surface.texture.as_image_copy(),
+                previous_frame.texture.as_ref().unwrap().as_image_copy(),
+                surface.texture.size(),
+            );
+        }
+        drop(self.encoder.take());
+        if self.present {
+            surface.present();
+        }
     }
 }
 
+// warns once per pipeline when the invocations of a dispatch don't reach the edge of what it should cover, which
+// leaves the last rows or columns unwritten, or when there is a whole workgroup or more left over
+fn check_dispatch_coverage(
+    pipeline: &mut Pipeline,
+    invocations: (u32, u32, u32),
+    target: (u32, u32, u32),
+) {
+    if pipeline.coverage_warned {
+        return;
+    }
+    let invocations = [invocations.0, invocations.1, invocations.2];
+    let target = [target.0, target.1, target.2];
+    let too_small = (0..3).any(|i| invocations[i] < target[i]);
+    let too_large = (0..3).any(|i| invocations[i] >= target[i] + pipeline.workgroup_size[i]);
+    if too_small || too_large {
+        warn!(
+            pipeline = %pipeline.pipeline_name,
+            invocations = ?invocations,
+            target = ?target,
+            workgroup_size = ?pipeline.workgroup_size,
+            "dispatch is {} than what it should cover",
+            if too_small { "smaller" } else { "larger" }
+        );
+        pipeline.coverage_warned = true;
+    }
+}
+
+// spreads a number of workgroups over as few dimensions as possible without going over the per dimension limit
+pub fn split_work_groups(work_groups: u32, max_per_dimension: u32) -> Result<(u32, u32, u32)> {
+    let x = work_groups.min(max_per_dimension);
+    let y = div_ceil(work_groups, x.max(1)).min(max_per_dimension);
+    let z = div_ceil(work_groups, x.max(1) * y.max(1));
+    if z > max_per_dimension {
+        bail!(
+            "{} workgroups don't fit in a dispatch with at most {} per dimension",
+            work_groups,
+            max_per_dimension
+        );
+    }
+    Ok((x, y, z.max(1)))
+}
+
+// val / div rounded up, like (val + div - 1) / div without overflowing for values close to u32::MAX
 pub fn div_ceil(val: u32, div: u32) -> u32 {
-    (val / div) + (val % div)
+    val / div + (val % div != 0) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::div_ceil;
+    use crate::gpu::tests::headless;
+    use crate::{TextureFormat, TextureRes};
+    use anyhow::Result;
+
+    // writes the texel position to red and green and TAG to blue
+    const FILL_WGSL: &str = "
+@group(0) @binding(0)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(vec3<f32>(f32(id.x), f32(id.y), TAG) / 255.0, 1.0));
+}
+";
+
+    #[test]
+    fn div_ceil_rounds_up() {
+        assert_eq!(div_ceil(0, 16), 0);
+        assert_eq!(div_ceil(1, 16), 1);
+        assert_eq!(div_ceil(15, 16), 1);
+        assert_eq!(div_ceil(16, 16), 1);
+        assert_eq!(div_ceil(17, 16), 2);
+        assert_eq!(div_ceil(31, 16), 2);
+        assert_eq!(div_ceil(32, 16), 2);
+        assert_eq!(div_ceil(33, 16), 3);
+        assert_eq!(div_ceil(3, 4), 1);
+        assert_eq!(div_ceil(5, 4), 2);
+        assert_eq!(div_ceil(7, 1), 7);
+        assert_eq!(div_ceil(u32::MAX, 16), u32::MAX / 16 + 1);
+        assert_eq!(div_ceil(u32::MAX, u32::MAX), 1);
+    }
+
+    // to_screen needs a window, to_texture draws with the same shader and works headless
+    #[test]
+    fn to_texture_sees_dispatches_recorded_before_it() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let source = gpu.texture(
+            "source",
+            TextureRes::Custom(8, 8, 1),
+            TextureFormat::Rgba8Unorm,
+        );
+        let target = gpu.texture_render_target(
+            "target",
+            TextureRes::Custom(8, 8, 1),
+            TextureFormat::Rgba8Unorm,
+        );
+        let mut before = gpu.pipeline_from_source(
+            "before",
+            &FILL_WGSL.replace("TAG", "1.0"),
+            "main",
+            &[&source],
+        )?;
+        let mut after = gpu.pipeline_from_source(
+            "after",
+            &FILL_WGSL.replace("TAG", "2.0"),
+            "main",
+            &[&source],
+        )?;
+
+        let mut encoder = gpu.get_encoder()?;
+        encoder.dispatch_pipeline(&mut before, (1, 1, 1), &[&source])?;
+        encoder.to_texture(&source, &target)?;
+        encoder.dispatch_pipeline(&mut after, (1, 1, 1), &[&source])?;
+        let texels = encoder.read_texture::<[u8; 4]>(&target)?;
+        let source_texels = encoder.read_texture::<[u8; 4]>(&source)?;
+
+        let expected = |tag| {
+            (0..8)
+                .flat_map(|y| (0..8).map(move |x| [x, y, tag, 255]))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texels, expected(1));
+        assert_eq!(source_texels, expected(2));
+        Ok(())
+    }
+
+    #[test]
+    fn regions_past_the_end_of_u32_are_rejected() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let texture = gpu.texture(
+            "texture",
+            TextureRes::Custom(8, 8, 1),
+            TextureFormat::Rgba8Unorm,
+        );
+        let mut encoder = gpu.get_encoder()?;
+        assert!(encoder
+            .read_texture_region::<[u8; 4]>(&texture, (u32::MAX, 0, 0), (2, 1, 1))
+            .is_err());
+        assert!(encoder
+            .read_texture_region::<[u8; 4]>(&texture, (0, 0, 0), (u32::MAX, 1, 1))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn zero_sized_elements_are_rejected() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let buffer = gpu.buffer("buffer", crate::BufferSize::Custom(4), 4);
+        let mut encoder = gpu.get_encoder()?;
+        assert!(encoder.set_buffer_data_iter(&buffer, [(); 4]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn set_buffer_data_iter_rejects_partial_words() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let buffer = gpu.buffer("buffer", crate::BufferSize::Custom(3), 2);
+        let mut encoder = gpu.get_encoder()?;
+        // the size hint of filter isn't exact, so this fills the 6 bytes of the buffer and has one element left
+        assert!(encoder
+            .set_buffer_data_iter(&buffer, (0u16..4).filter(|_| true))
+            .is_err());
+        assert!(encoder.set_buffer_data_iter(&buffer, [1u16]).is_err());
+        encoder.set_buffer_data_iter(&buffer, [1u16, 2])?;
+        Ok(())
+    }
+
+    #[test]
+    fn set_buffer_data_round_trips() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let buffer = gpu.buffer("buffer", crate::BufferSize::Custom(4), 4);
+        let mut encoder = gpu.get_encoder()?;
+        encoder.set_buffer_data(&buffer, [1u32, 2, 3, 4])?;
+        assert_eq!(encoder.read_buffer::<u32>(&buffer)?, vec![1, 2, 3, 4]);
+        assert!(encoder.set_buffer_data(&buffer, [0u32; 5]).is_err());
+        Ok(())
+    }
+
+    // like the bvh upload of the hello_async_mesh example, from a borrowed vec of structs into buffers created
+    // right before the encoder
+    #[test]
+    fn set_buffer_data_uploads_borrowed_structs() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let nodes = (0..5)
+            .map(|i| [i as f32, i as f32 + 0.5, -(i as f32), 1.0])
+            .collect::<Vec<[f32; 4]>>();
+        let buffer = gpu.buffer("nodes", nodes.len(), std::mem::size_of::<[f32; 4]>());
+        let mut encoder = gpu.get_encoder()?;
+        encoder.set_buffer_data(&buffer, &nodes)?;
+        assert_eq!(encoder.read_buffer::<[f32; 4]>(&buffer)?, nodes);
+        Ok(())
+    }
+
+    // life_3d uploads new cells after the frame's dispatches, uploads and dispatches run in recording order
+    #[test]
+    fn set_buffer_data_is_ordered_with_dispatches() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let cells = gpu.buffer("cells", 64, 4);
+        let mut fill = gpu.pipeline_from_source(
+            "fill",
+            "@group(0) @binding(0)
+var<storage, read_write> cells: array<u32>;
+
+@compute @workgroup_size(4, 4, 4)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    cells[id.x + id.y * 4u + id.z * 16u] = 7u;
+}",
+            "main",
+            &[&cells],
+        )?;
+        let uploaded = (0..64).collect::<Vec<u32>>();
+        let mut encoder = gpu.get_encoder()?;
+        encoder.dispatch_3d(&mut fill, (4, 4, 4), &[&cells])?;
+        encoder.set_buffer_data(&cells, &uploaded)?;
+        assert_eq!(encoder.read_buffer::<u32>(&cells)?, uploaded);
+        encoder.set_buffer_data(&cells, &uploaded)?;
+        encoder.dispatch_3d(&mut fill, (4, 4, 4), &[&cells])?;
+        assert_eq!(encoder.read_buffer::<u32>(&cells)?, vec![7; 64]);
+        Ok(())
+    }
+
+    // the uniforms example writes its camera to a uniform buffer, which is padded to 16 bytes
+    #[test]
+    fn set_buffer_data_fills_uniform_buffers() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let camera = gpu.uniform_buffer("camera", 12);
+        let mut encoder = gpu.get_encoder()?;
+        encoder.set_buffer_data(&camera, [[1.0f32, 2.0, 3.0]])?;
+        assert_eq!(
+            encoder.read_buffer::<[f32; 3]>(&camera)?,
+            vec![[1.0, 2.0, 3.0]]
+        );
+        Ok(())
+    }
+
+    // the lut of the image_filter example, a 3d texture written in one go
+    #[test]
+    fn set_texture_data_round_trips() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let lut = gpu.texture_sampled(
+            "lut",
+            TextureRes::Custom(4, 4, 4),
+            TextureFormat::Rgba8Unorm,
+        );
+        let texels = (0..64u8)
+            .map(|i| [i, i / 4, i / 16, 255])
+            .collect::<Vec<_>>();
+        let mut encoder = gpu.get_encoder()?;
+        encoder.set_texture_data(&lut, &texels)?;
+        assert_eq!(encoder.read_texture::<[u8; 4]>(&lut)?, texels);
+        assert!(encoder.set_texture_data(&lut, &texels[1..]).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,22 @@
+// STORAGE_FORMAT is replaced with the format of the texture that gets checked
+@group(0) @binding(0)
+var source: texture_storage_2d<STORAGE_FORMAT, read>;
+@group(0) @binding(1)
+var<storage, read_write> non_finite: atomic<u32>;
+
+// compares the exponent bits, comparisons with nan can get optimized away by fast math
+fn is_finite(value: vec4<f32>) -> bool {
+    let exponent = bitcast<vec4<u32>>(value) & vec4<u32>(0x7f800000u);
+    return all(exponent != vec4<u32>(0x7f800000u));
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let dims = textureDimensions(source);
+    if id.x >= dims.x || id.y >= dims.y {
+        return;
+    }
+    if !is_finite(textureLoad(source, vec2<i32>(id.xy))) {
+        atomicAdd(&non_finite, 1u);
+    }
+}
@@ -0,0 +1,101 @@
+use wgpu::{
+    BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType, CommandEncoder,
+    ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Device,
+    PipelineLayoutDescriptor, ShaderModuleDescriptor, ShaderSource, ShaderStages,
+    StorageTextureAccess, TextureFormat, TextureView, TextureViewDimension,
+};
+
+use super::to_screen_pipeline::wgsl_storage_format;
+use crate::div_ceil;
+
+// counts the texels of a float texture that contain a nan or inf in any channel
+#[derive(Debug)]
+pub(crate) struct FiniteCheckPipeline {
+    pub(crate) format: TextureFormat,
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+}
+
+impl FiniteCheckPipeline {
+    pub(crate) fn new(device: &Device, format: TextureFormat) -> Self {
+        let source = include_str!("finite_check.wgsl")
+            .replace("STORAGE_FORMAT", wgsl_storage_format(format));
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("finite_check.wgsl"),
+            source: ShaderSource::Wgsl(source.into()),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("finite_check_bindgroup_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::StorageTexture {
+                        access: StorageTextureAccess::ReadOnly,
+                        view_dimension: TextureViewDimension::D2,
+                        format,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("finite_check_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("finite_check.wgsl"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        FiniteCheckPipeline {
+            format,
+            pipeline,
+            bind_group_layout,
+        }
+    }
+    // adds the number of non finite texels of the texture to the u32 in counter
+    pub(crate) fn record(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        texture_view: &TextureView,
+        size: (u32, u32),
+        counter: &Buffer,
+    ) {
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("finite_check_bindgroup"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(texture_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: counter.as_entire_binding(),
+                },
+            ],
+        });
+        let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("finite_check"),
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(div_ceil(size.0, 16), div_ceil(size.1, 16), 1);
+    }
+}
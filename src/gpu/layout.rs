@@ -0,0 +1,30 @@
+// helpers to get cpu data in the layout shaders expect for storage buffers, where a vec3 is aligned to 16 bytes
+// and structs containing one are padded to a multiple of 16 bytes
+
+use std::any::type_name;
+use std::mem::size_of;
+
+use bytemuck::Pod;
+use glam::{Vec3, Vec4};
+use tracing::warn;
+
+// turns every vec3 into a vec4 with w set to 0, the layout of an array<vec3<f32>> in a shader
+pub fn pad_vec3s(values: &[Vec3]) -> Vec<Vec4> {
+    values.iter().map(|value| value.extend(0.0)).collect()
+}
+
+// warns when the size of a struct uploaded to a storage buffer is 4 bytes short of a multiple of 16, which usually
+// means a vec3 at its end is missing its padding and every element after the first would be read at the wrong
+// offset. other sizes are left alone, structs of scalars don't need to be padded. returns whether the size looks right
+pub fn check_gpu_layout<T: Pod>() -> bool {
+    let size = size_of::<T>();
+    if size % 16 != 12 {
+        return true;
+    }
+    warn!(
+        "{} is {} bytes, 4 bytes short of a multiple of 16. a vec3 at its end likely needs a padding f32",
+        type_name::<T>(),
+        size
+    );
+    false
+}
@@ -0,0 +1,169 @@
+use std::time::SystemTime;
+
+use anyhow::Result;
+
+use wgpu::{
+    BindGroup, BindGroupLayout, BlendState, ColorTargetState, ColorWrites, FragmentState,
+    FrontFace, MultisampleState, PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipeline,
+    RenderPipelineDescriptor, VertexAttribute, VertexBufferLayout, VertexFormat, VertexState,
+    VertexStepMode,
+};
+
+use crate::{gpu::shader::Shader, hash_handles, ResourceHandle};
+
+use super::{bind_group_layout_entries, check_bindings, last_modified, CoGr};
+
+// the entry points of mesh pipelines, shown in CoGr::pipelines
+pub(crate) const MESH_ENTRY_POINTS: &str = "vs_main/fs_main";
+
+// rasterizes user geometry with a single vertex buffer, the shader has to contain a vs_main and fs_main entry point
+#[derive(Debug)]
+pub struct MeshPipeline {
+    pub pipeline_name: String,
+    pub source: String,
+    // the files on disk the shader and its includes were read from, see Pipeline::files
+    pub files: Vec<String>,
+    pub last_update: SystemTime,
+    pub vertex_attributes: Vec<VertexFormat>,
+    pub topology: PrimitiveTopology,
+    pub pipeline: RenderPipeline,
+    pub bind_group_layout: BindGroupLayout,
+    pub bindings_hash: u64,
+    pub reload_generation: u64,
+    pub last_bind_group_hash: u64,
+    pub last_bind_group: Option<BindGroup>,
+}
+
+impl MeshPipeline {
+    pub(crate) fn new(
+        gpu_context: &CoGr,
+        shader_file: &str,
+        vertex_attributes: &[VertexFormat],
+        topology: PrimitiveTopology,
+        bindings: &[&ResourceHandle],
+    ) -> Result<Self> {
+        let source = gpu_context.load_shader(shader_file)?;
+        let shader = Shader::compile_mapped(gpu_context, shader_file, &source)?;
+        let files = gpu_context.files_on_disk(&source);
+        check_bindings(
+            &gpu_context.resource_pool,
+            &shader,
+            &["vs_main", "fs_main"],
+            bindings,
+        )?;
+
+        // attributes are tightly packed and get consecutive shader locations
+        let mut offset = 0;
+        let attributes = vertex_attributes
+            .iter()
+            .enumerate()
+            .map(|(location, format)| {
+                let attribute = VertexAttribute {
+                    format: *format,
+                    offset,
+                    shader_location: location as u32,
+                };
+                offset += format.size();
+                attribute
+            })
+            .collect::<Vec<_>>();
+
+        let bind_group_layout_entries = bind_group_layout_entries(gpu_context, bindings, &shader);
+        let bind_group_layout =
+            gpu_context
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(&(shader_file.to_owned() + "_bindgroup_layout")),
+                    entries: bind_group_layout_entries.as_slice(),
+                });
+
+        let pipeline_layout =
+            gpu_context
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some(&(shader_file.to_owned() + "_layout")),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                });
+
+        let pipeline = gpu_context
+            .device
+            .create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(shader_file),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &shader.shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[VertexBufferLayout {
+                        array_stride: offset,
+                        step_mode: VertexStepMode::Vertex,
+                        attributes: &attributes,
+                    }],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader.shader_module,
+                    entry_point: "fs_main",
+                    targets: &[Some(ColorTargetState {
+                        format: gpu_context.surface_view_format(),
+                        blend: Some(BlendState::REPLACE),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState {
+                    topology,
+                    strip_index_format: None,
+                    front_face: FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                multiview: None,
+            });
+
+        Ok(MeshPipeline {
+            pipeline_name: shader_file.to_string(),
+            source: shader_file.to_string(),
+            last_update: last_modified(&files),
+            files,
+            vertex_attributes: vertex_attributes.to_vec(),
+            topology,
+            pipeline,
+            bind_group_layout,
+            bindings_hash: hash_handles(bindings),
+            reload_generation: gpu_context.reload_generation,
+            last_bind_group_hash: 0,
+            last_bind_group: None,
+        })
+    }
+
+    pub fn check_hot_reload(&mut self, gpu_context: &mut CoGr, bindings: &[&ResourceHandle]) {
+        if hash_handles(bindings) != self.bindings_hash
+            || self.last_update < last_modified(&self.files)
+            || self.reload_generation != gpu_context.reload_generation
+        {
+            let new_pipe = MeshPipeline::new(
+                gpu_context,
+                &self.source,
+                &self.vertex_attributes,
+                self.topology,
+                bindings,
+            );
+            gpu_context.record_pipeline_reload(
+                &self.source,
+                MESH_ENTRY_POINTS,
+                new_pipe.as_ref().err(),
+            );
+            match new_pipe {
+                Ok(new_pipe) => *self = new_pipe,
+                Err(err) => {
+                    println!("{}", err);
+                    self.reload_generation = gpu_context.reload_generation;
+                    self.last_update = last_modified(&self.files);
+                }
+            }
+        }
+    }
+}
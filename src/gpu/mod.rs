@@ -1,18 +1,26 @@
 use egui::epaint::Shadow;
 use egui::Style;
 use egui::Visuals;
-use tracing::info;
+use tracing::{error, info, warn};
 use wgpu::Backends;
 use wgpu::Features;
 use wgpu_profiler::GpuProfiler;
 use wgpu_profiler::GpuTimerScopeResult;
 
+use self::auto_exposure::AutoExposure;
+use self::finite_check_pipeline::FiniteCheckPipeline;
+use self::shader_editor::ShaderEditor;
 use self::to_screen_pipeline::ToScreenPipeline;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use egui_winit::State;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Debug;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 use wgpu::InstanceDescriptor;
+use wgpu::SubmissionIndex;
 use wgpu::TextureFormat;
 use wgpu::TextureFormat::Bgra8UnormSrgb;
 use wgpu::TextureView;
@@ -20,18 +28,45 @@ use winit::event::WindowEvent;
 use winit::event_loop::EventLoop;
 use winit::window::Window;
 
+use crate::camera::Orbit;
+use crate::{VideoModeRequest, WindowConfig};
+
+mod auto_exposure;
+mod color;
 mod encoder;
+mod finite_check_pipeline;
+mod layout;
+mod mesh_pipeline;
 mod pipeline;
+mod pipeline_builder;
+mod pipeline_modes;
+mod readback;
+mod reduce_pipeline;
 mod resources;
 mod shader;
+mod shader_editor;
+mod snapshot;
+mod source_map;
 mod to_screen_pipeline;
 
+pub use color::*;
 pub use encoder::*;
+pub use layout::*;
+pub use mesh_pipeline::*;
 pub use pipeline::*;
+pub use pipeline_builder::*;
+pub use pipeline_modes::*;
+pub use readback::*;
+pub use reduce_pipeline::*;
 pub use resources::*;
 pub use shader::*;
+pub use snapshot::*;
+pub use source_map::*;
 pub use to_screen_pipeline::*;
 
+// number of frame times shown in the frame graph of the top bar
+pub(crate) const FRAME_TIME_HISTORY: usize = 240;
+
 #[allow(dead_code)]
 #[derive(Debug)]
 pub(crate) struct BufferDescriptor {
@@ -56,6 +91,60 @@ struct PipelineDescriptor {
     pipeline: Pipeline,
     workgroup_size: (u32, u32, u32),
 }
+// rolling history of the gpu time of every scope with this label, drawn in the gpu profiler
+#[derive(Debug)]
+pub(crate) struct TimingMetric {
+    pub(crate) label: String,
+    pub(crate) times: VecDeque<f32>,
+}
+// a pipeline created through CoGr, updated on every hot reload so failed reloads can be inspected
+#[derive(Debug, Clone)]
+pub struct PipelineInfo {
+    pub name: String,
+    pub source: String,
+    pub entry_point: String,
+    pub last_reload: SystemTime,
+    pub last_error: Option<String>,
+}
+// the resources a pipeline was created with, used by CoGr::validate
+#[derive(Debug)]
+struct PipelineBindings {
+    name: String,
+    bindings: Vec<WeakResourceHandle>,
+    uniform_bindings: Vec<u32>,
+    sampled_bindings: Vec<u32>,
+}
+impl PipelineBindings {
+    // whether all resources the pipeline was created with still exist
+    fn alive(&self) -> bool {
+        self.bindings
+            .iter()
+            .all(|handle| handle.upgrade().is_some())
+    }
+}
+// a pool texture shown in the ui, the view is swapped out when the texture gets reallocated
+#[derive(Debug)]
+struct EguiTexture {
+    handle: WeakResourceHandle,
+    id: egui::TextureId,
+    generation: u64,
+}
+// a to_screen pipeline drawing into a texture instead of the window, for Encoder::to_texture
+#[derive(Debug)]
+pub(crate) struct ToTexturePipeline {
+    source: WeakResourceHandle,
+    generation: u64,
+    target_format: TextureFormat,
+    pipeline: ToScreenPipeline,
+}
+// saves what is drawn to the screen every frame while the camera orbits once around the target
+#[derive(Debug)]
+struct Turntable {
+    orbit: Orbit,
+    frames: u32,
+    frame: u32,
+    output_dir: PathBuf,
+}
 #[allow(dead_code)]
 #[derive(Debug)]
 struct ToScreenPipelineDescriptor {
@@ -63,31 +152,186 @@ struct ToScreenPipelineDescriptor {
     pipeline: ToScreenPipeline,
 }
 
-pub struct CoGr {
+// summed time of all scopes with this label, including the ones nested in other scopes
+fn scope_time(timings: &[GpuTimerScopeResult], label: &str) -> f64 {
+    timings
+        .iter()
+        .map(|timing| {
+            let time = match timing.label == label {
+                true => timing.time.end - timing.time.start,
+                false => 0.0,
+            };
+            time + scope_time(&timing.nested_scopes, label)
+        })
+        .sum()
+}
+
+// requests the device with the given features and the optional ones the adapter has
+fn request_device(
+    adapter: &wgpu::Adapter,
+    features: Features,
+    trace_path: Option<&Path>,
+) -> Result<(wgpu::Device, wgpu::Queue)> {
+    // ask for generous limits, but never more than the adapter supports or request_device fails
+    let adapter_limits = adapter.limits();
+    let limits = wgpu::Limits {
+        max_storage_buffers_per_shader_stage: adapter_limits
+            .max_storage_buffers_per_shader_stage
+            .min(16),
+        max_storage_buffer_binding_size: adapter_limits
+            .max_storage_buffer_binding_size
+            .min(1073741824),
+        max_storage_textures_per_shader_stage: adapter_limits
+            .max_storage_textures_per_shader_stage
+            .min(16),
+        max_push_constant_size: adapter_limits.max_push_constant_size.min(128),
+        ..Default::default()
+    };
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            features: features | (adapter.features() & OPTIONAL_FEATURES),
+            limits,
+            label: None,
+        },
+        trace_path,
+    ))?;
+    info!("{:?}", device.features());
+    info!("{:?}", device.limits());
+    Ok((device, queue))
+}
+
+// the srgb view of the surface when the config has one, its own format otherwise
+fn view_format(config: &wgpu::SurfaceConfiguration) -> TextureFormat {
+    config
+        .view_formats
+        .first()
+        .copied()
+        .unwrap_or(config.format)
+}
+
+fn surface_config(width: u32, height: u32) -> wgpu::SurfaceConfiguration {
+    wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: Bgra8UnormSrgb,
+        width,
+        height,
+        present_mode: wgpu::PresentMode::Immediate,
+        alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+        view_formats: vec![Bgra8UnormSrgb],
+    }
+}
+
+// what CoGr::set_workgroup_ceiling does with dispatches of more workgroups than the ceiling in total
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkgroupCeiling {
+    Off,
+    // warns once per pipeline
+    Warn(u64),
+    // fails the dispatch
+    Error(u64),
+}
+
+// debugging options for creating the device, everything is off by default since it costs performance
+#[derive(Debug, Clone, Default)]
+pub struct CoGrConfig {
+    // checks every submitted encoder for wgpu validation errors and logs them with the frame they happened in,
+    // instead of panicking on the first one. the validation layers of the backend itself are enabled by wgpu in debug builds
+    pub validation: bool,
+    // writes an api trace of the device to this directory for replaying with wgpu's player, needs the trace feature
+    pub trace_path: Option<PathBuf>,
+}
+
+// the window and everything tied to it, contexts made with CoGr::headless don't have one
+struct WindowParts {
+    window: Arc<Window>,
     surface: wgpu::Surface,
+    state: State,
+}
+
+// the features cogrrs needs, OPTIONAL_FEATURES are only requested when the adapter has them
+const REQUIRED_FEATURES: Features = Features::TIMESTAMP_QUERY
+    .union(Features::TIMESTAMP_QUERY_INSIDE_PASSES)
+    .union(Features::SPIRV_SHADER_PASSTHROUGH)
+    .union(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+const OPTIONAL_FEATURES: Features = Features::BGRA8UNORM_STORAGE
+    .union(Features::TEXTURE_BINDING_ARRAY)
+    .union(Features::STORAGE_RESOURCE_BINDING_ARRAY)
+    .union(Features::PUSH_CONSTANTS);
+
+pub struct CoGr {
     device: wgpu::Device,
     queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
-    window: Arc<Window>,
+    window: Option<WindowParts>,
 
     profiler: GpuProfiler,
     frame_timings: Vec<GpuTimerScopeResult>,
+    timing_metrics: Vec<TimingMetric>,
+    max_frames_in_flight: usize,
+    submissions_in_flight: VecDeque<SubmissionIndex>,
 
     pub resource_pool: ResourcePool,
     last_to_screen_texture_handle: Option<ResourceHandle>,
     last_to_screen_pipeline: Option<ToScreenPipeline>,
+    last_to_screen_generation: u64,
+    // the lut of to_screen_with_lut and its generation
+    last_to_screen_lut: Option<(ResourceHandle, u64)>,
+    auto_exposure: Option<AutoExposure>,
+    to_texture_pipelines: Vec<ToTexturePipeline>,
+    auto_exposure_speed: f32,
+    auto_exposure_key: f32,
+    workgroup_ceiling: WorkgroupCeiling,
+    pub(crate) validation: bool,
+    surface_output: Option<ResourceHandle>,
+    pub(crate) previous_frame: Option<ResourceHandle>,
+    pipeline_bindings: Vec<PipelineBindings>,
+    pipeline_infos: Vec<PipelineInfo>,
+    // shader code by path, consulted before the filesystem, see register_virtual_shader
+    virtual_shaders: HashMap<String, String>,
+    pub(crate) reload_generation: u64,
+    reduce_pipelines: Vec<ReducePipeline>,
+    finite_check_pipelines: Vec<FiniteCheckPipeline>,
+    check_finite: bool,
 
     // ui
     context: egui::Context,
     renderer: egui_wgpu::Renderer,
-    state: State,
+    // set_ui_target, the target gets a renderer of its own since renderers are made for one format
+    ui_target: Option<ResourceHandle>,
+    ui_target_renderer: Option<(TextureFormat, egui_wgpu::Renderer)>,
+    ui_on_screen: bool,
     draw_cpu_profiler: bool,
     draw_gpu_profiler: bool,
     draw_user_ui: bool,
+    draw_frame_graph: bool,
+    draw_shader_editor: bool,
+    draw_auto_exposure: bool,
+    pub(crate) shader_editor: ShaderEditor,
+    egui_textures: Vec<EguiTexture>,
+    top_bar_sections: Vec<Box<dyn FnMut(&mut egui::Ui)>>,
+    frame_times: VecDeque<f32>,
+    last_frame: Instant,
+
+    render_scale: f32,
+    clear_color: Option<[f32; 4]>,
+
+    paused: bool,
+    turntable: Option<Turntable>,
+    focused: bool,
+    occluded: bool,
+    time: f32,
+    frame: u64,
 }
 
 impl CoGr {
     pub fn new(window: &Arc<Window>, event_loop: &EventLoop<()>) -> Result<Self> {
+        Self::with_config(window, event_loop, &CoGrConfig::default())
+    }
+    pub fn with_config(
+        window: &Arc<Window>,
+        event_loop: &EventLoop<()>,
+        gpu_config: &CoGrConfig,
+    ) -> Result<Self> {
         let instance = wgpu::Instance::new(InstanceDescriptor {
             backends: Backends::METAL,
             ..Default::default()
@@ -104,38 +348,89 @@ impl CoGr {
         info!("{:?}", adapter.get_info());
         info!("{:?}", adapter.limits());
         info!("{:?}", adapter.get_downlevel_capabilities());
-        let limits = wgpu::Limits {
-            max_storage_buffers_per_shader_stage: 16,
-            max_storage_buffer_binding_size: 1073741824,
-            max_storage_textures_per_shader_stage: 16,
+        if let Some(trace_path) = &gpu_config.trace_path {
+            if cfg!(feature = "trace") {
+                std::fs::create_dir_all(trace_path)?;
+            } else {
+                warn!("trace_path is set but cogrrs is built without the trace feature, no trace gets written");
+            }
+        }
+        let (device, queue) = request_device(
+            &adapter,
+            REQUIRED_FEATURES,
+            gpu_config.trace_path.as_deref(),
+        )?;
+        let mut config = surface_config(window.inner_size().width, window.inner_size().height);
+        // dispatch_to_surface binds the surface itself when it can be a bgra8unorm storage texture, everything
+        // else keeps drawing to it through the srgb view
+        let capabilities = surface.get_capabilities(&adapter);
+        if device.features().contains(Features::BGRA8UNORM_STORAGE)
+            && capabilities.formats.contains(&TextureFormat::Bgra8Unorm)
+            && capabilities
+                .usages
+                .contains(wgpu::TextureUsages::STORAGE_BINDING)
+        {
+            config.format = TextureFormat::Bgra8Unorm;
+            config.usage |= wgpu::TextureUsages::STORAGE_BINDING;
+        }
+
+        let mut gpu =
+            Self::from_device(window, event_loop, &adapter, device, queue, surface, config)?;
+        gpu.validation = gpu_config.validation;
+        if gpu.validation {
+            // errors outside of a frame are logged instead of panicking as well
+            gpu.device.on_uncaptured_error(Box::new(|err| {
+                error!("wgpu validation error: {}", err);
+            }));
+        }
+        Ok(gpu)
+    }
+    // a context without window or surface, for tests and offline rendering. everything except drawing to the
+    // screen works, the full resolution is width x height. fails when there is no gpu adapter
+    pub fn headless(width: u32, height: u32) -> Result<Self> {
+        let instance = wgpu::Instance::new(InstanceDescriptor {
+            backends: Backends::PRIMARY,
             ..Default::default()
-        };
-        let (device, queue) = pollster::block_on(adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                features: Features::TIMESTAMP_QUERY
-                    | Features::TIMESTAMP_QUERY_INSIDE_PASSES
-                    | Features::SPIRV_SHADER_PASSTHROUGH
-                    | Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
-                limits,
-                label: None,
-            },
-            None, // Trace path
-        ))?;
-        info!("{:?}", device.features());
-        info!("{:?}", device.limits());
-
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: Bgra8UnormSrgb,
-            width: window.inner_size().width,
-            height: window.inner_size().height,
-            present_mode: wgpu::PresentMode::Immediate,
-            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
-            view_formats: vec![Bgra8UnormSrgb],
-        };
+        });
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }))
+        .context("can't initialize gpu adapter")?;
+        info!("{:?}", adapter.get_info());
+        // the features cogrrs can do without aren't required here, so tests also run on software adapters
+        let (device, queue) =
+            request_device(&adapter, adapter.features() & REQUIRED_FEATURES, None)?;
+        Self::build(None, &adapter, device, queue, surface_config(width, height))
+    }
+    // builds the context on top of gpu objects created by the user, for integrating in an existing wgpu app.
+    // the surface gets configured with the given config
+    pub fn from_device(
+        window: &Arc<Window>,
+        event_loop: &EventLoop<()>,
+        adapter: &wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        surface: wgpu::Surface,
+        config: wgpu::SurfaceConfiguration,
+    ) -> Result<Self> {
         surface.configure(&device, &config);
-
-        let renderer = egui_wgpu::renderer::Renderer::new(&device, config.format, None, 1);
+        let window = WindowParts {
+            window: window.clone(),
+            surface,
+            state: egui_winit::State::new(event_loop),
+        };
+        Self::build(Some(window), adapter, device, queue, config)
+    }
+    fn build(
+        window: Option<WindowParts>,
+        adapter: &wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        config: wgpu::SurfaceConfiguration,
+    ) -> Result<Self> {
+        let renderer = egui_wgpu::renderer::Renderer::new(&device, view_format(&config), None, 1);
         let context = egui::Context::default();
         context.set_style(Style {
             visuals: Visuals {
@@ -144,63 +439,147 @@ impl CoGr {
             },
             ..Default::default()
         });
-        let state = egui_winit::State::new(event_loop);
-
-        let profiler = GpuProfiler::new(&adapter, &device, &queue, 4);
+        let profiler = GpuProfiler::new(adapter, &device, &queue, 4);
 
         Ok(Self {
-            surface,
             device,
             queue,
             config,
-            window: window.clone(),
+            window,
             resource_pool: ResourcePool::default(),
 
             profiler,
             frame_timings: Vec::new(),
+            timing_metrics: Vec::new(),
+            max_frames_in_flight: 2,
+            submissions_in_flight: VecDeque::new(),
 
             renderer,
+            ui_target: None,
+            ui_target_renderer: None,
+            ui_on_screen: true,
             context,
-            state,
             last_to_screen_texture_handle: None,
             last_to_screen_pipeline: None,
+            last_to_screen_generation: 0,
+            last_to_screen_lut: None,
+            auto_exposure: None,
+            to_texture_pipelines: Vec::new(),
+            auto_exposure_speed: 1.5,
+            auto_exposure_key: 0.18,
+            workgroup_ceiling: WorkgroupCeiling::Off,
+            validation: false,
+            surface_output: None,
+            previous_frame: None,
+            pipeline_bindings: Vec::new(),
+            pipeline_infos: Vec::new(),
+            virtual_shaders: HashMap::new(),
+            reload_generation: 0,
+            reduce_pipelines: Vec::new(),
+            finite_check_pipelines: Vec::new(),
+            check_finite: cfg!(debug_assertions),
             draw_cpu_profiler: false,
             draw_gpu_profiler: false,
             draw_user_ui: false,
+            draw_frame_graph: false,
+            draw_shader_editor: false,
+            draw_auto_exposure: false,
+            shader_editor: ShaderEditor::default(),
+            egui_textures: Vec::new(),
+            top_bar_sections: Vec::new(),
+            frame_times: VecDeque::with_capacity(FRAME_TIME_HISTORY),
+            last_frame: Instant::now(),
+
+            render_scale: 1.0,
+            clear_color: Some([0.0, 0.0, 0.0, 1.0]),
+
+            paused: false,
+            turntable: None,
+            focused: true,
+            occluded: false,
+            time: 0.0,
+            frame: 0,
         })
     }
     pub fn get_encoder_for_draw(&mut self) -> Result<DrawEncoder> {
         puffin::profile_function!();
-        let surface_texture = self.surface.get_current_texture()?;
+        let Some(window) = &self.window else {
+            bail!("a headless context has no surface to draw to, use get_encoder instead");
+        };
+        if self.frame_times.len() == FRAME_TIME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times
+            .push_back(self.last_frame.elapsed().as_secs_f32());
+        self.last_frame = Instant::now();
+        let surface_texture = window.surface.get_current_texture()?;
         let texture_view_config = wgpu::TextureViewDescriptor {
-            format: Some(self.config.format),
+            format: Some(self.surface_view_format()),
             ..Default::default()
         };
         let surface_texture_view = surface_texture.texture.create_view(&texture_view_config);
-        let encoder = self.get_encoder()?;
+        let storage_view = self.surface_storage().then(|| {
+            surface_texture
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+        let clear_color = self.clear_color;
+        let mut encoder = self.get_encoder()?;
+        if let Some([r, g, b, a]) = clear_color {
+            let command_encoder = encoder
+                .command_encoder
+                .as_mut()
+                .context("encoder not available")?;
+            command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("clear_surface"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &surface_texture_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: r as f64,
+                            g: g as f64,
+                            b: b as f64,
+                            a: a as f64,
+                        }),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+        }
 
         Ok(DrawEncoder {
             encoder: Some(encoder),
             surface_texture: Some(surface_texture),
             texture_view: surface_texture_view,
+            storage_view,
+            present: true,
         })
     }
     pub fn get_encoder(&mut self) -> Result<Encoder> {
         puffin::profile_function!();
         self.resource_pool
-            .prepare_resources(&self.device, &self.config);
+            .prepare_resources(&self.device, self.render_size())?;
 
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
             });
+        if self.validation {
+            // popped when the encoder is submitted
+            self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        }
+        self.resource_pool.clear_textures(&mut encoder);
         encoder.push_debug_group("user_encoder");
         Ok(Encoder {
             command_encoder: Some(encoder),
             gpu_context: self,
+            last_dispatch_dims: None,
         })
     }
+    // panics when element_size is 0
     pub fn buffer<S: Into<BufferSize>>(
         &mut self,
         name: &str,
@@ -211,6 +590,11 @@ impl CoGr {
         self.resource_pool
             .buffer(name.to_string(), elements, element_size)
     }
+    // a buffer holding a single struct of size bytes that can only be bound to a var<uniform>, like per frame camera
+    // data. uniform bindings are smaller and faster to read than storage buffers
+    pub fn uniform_buffer(&mut self, name: &str, size: usize) -> ResourceHandle {
+        self.resource_pool.uniform_buffer(name.to_string(), size)
+    }
     pub fn texture(
         &mut self,
         name: &str,
@@ -221,15 +605,1305 @@ impl CoGr {
             .texture(name.to_string(), elements, format)
     }
 
+    // the format everything is drawn to the surface with, the srgb view when the config has one
+    pub(crate) fn surface_view_format(&self) -> TextureFormat {
+        view_format(&self.config)
+    }
+    // whether dispatch_to_surface can bind the surface itself instead of an intermediate
+    fn surface_storage(&self) -> bool {
+        self.window.is_some()
+            && self
+                .config
+                .usage
+                .contains(wgpu::TextureUsages::STORAGE_BINDING)
+    }
+    // the storage format binding 0 of a dispatch_to_surface shader has to be declared with, bgra8unorm when the
+    // surface is bound directly and rgba8unorm when it goes through an intermediate
+    pub fn surface_output_format(&self) -> TextureFormat {
+        match self.surface_storage() {
+            true => self.config.format,
+            false => TextureFormat::Rgba8Unorm,
+        }
+    }
+    // the texture bound at binding 0 by dispatch_to_surface. it is a full resolution intermediate when the surface
+    // can't be bound as storage texture, otherwise it only stands in for the surface in the bind group layout
+    pub(crate) fn surface_output(&mut self) -> Result<ResourceHandle> {
+        if let Some(handle) = &self.surface_output {
+            return Ok(handle.clone());
+        }
+        let res = match self.surface_storage() {
+            true => TextureRes::Custom(1, 1, 1),
+            false => TextureRes::FullRes,
+        };
+        let handle = self.texture("surface_output", res, self.surface_output_format());
+        self.resource_pool
+            .prepare_resources(&self.device, self.render_size())?;
+        self.surface_output = Some(handle.clone());
+        Ok(handle)
+    }
+
+    // returns a texture which contains the frame that was presented last, including the ui. it is copied when
+    // the draw encoder finishes, so during a frame it always holds the result of the previous frame
+    pub fn keep_previous_frame(&mut self) -> Result<ResourceHandle> {
+        if let Some(handle) = &self.previous_frame {
+            return Ok(handle.clone());
+        }
+        if !self
+            .device
+            .features()
+            .contains(Features::BGRA8UNORM_STORAGE)
+        {
+            bail!("the gpu doesn't support bgra8unorm storage textures, so the previous frame can't be kept");
+        }
+        // the surface isn't affected by the render scale, so this has to match the window instead
+        let handle = self.texture(
+            "previous_frame",
+            TextureRes::Custom(self.config.width, self.config.height, 1),
+            wgpu::TextureFormat::Bgra8Unorm,
+        );
+        self.config.usage |= wgpu::TextureUsages::COPY_SRC;
+        if let Some(window) = &self.window {
+            window.surface.configure(&self.device, &self.config);
+        }
+        self.previous_frame = Some(handle.clone());
+        Ok(handle)
+    }
+
+    // starts graphing the gpu time of the scopes with this label, like a pipeline name or "to_screen"
+    pub fn metric_from_timing(&mut self, label: &str) {
+        if !self
+            .timing_metrics
+            .iter()
+            .any(|metric| metric.label == label)
+        {
+            self.timing_metrics.push(TimingMetric {
+                label: label.to_string(),
+                times: VecDeque::with_capacity(FRAME_TIME_HISTORY),
+            });
+        }
+    }
+    pub(crate) fn update_timings(&mut self, timings: Vec<GpuTimerScopeResult>) {
+        for metric in &mut self.timing_metrics {
+            if metric.times.len() == FRAME_TIME_HISTORY {
+                metric.times.pop_front();
+            }
+            metric
+                .times
+                .push_back(scope_time(&timings, &metric.label) as f32);
+        }
+        self.frame_timings = timings;
+    }
+    // how many submitted encoders the gpu can lag behind before the cpu waits for it. lower values reduce
+    // input latency, higher values give more throughput
+    pub fn set_max_frames_in_flight(&mut self, frames: usize) {
+        self.max_frames_in_flight = frames.max(1);
+    }
+    pub(crate) fn limit_frames_in_flight(&mut self, submission: SubmissionIndex) {
+        puffin::profile_function!();
+        if self.device.poll(wgpu::Maintain::Poll) {
+            // everything submitted before has finished
+            self.submissions_in_flight.clear();
+        }
+        self.submissions_in_flight.push_back(submission);
+        while self.submissions_in_flight.len() > self.max_frames_in_flight {
+            let oldest = self.submissions_in_flight.pop_front().unwrap();
+            self.device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(oldest));
+        }
+    }
+    // renders frames images of a full orbit around a target and writes each to output_dir as frame_0000.png and up,
+    // then exits. the game has to use turntable_pose for its camera while capturing and call to_screen every frame,
+    // the to_screen texture has to be Rgba8Unorm
+    pub fn turntable_capture(&mut self, frames: u32, output_dir: &str, orbit: Orbit) -> Result<()> {
+        if frames == 0 {
+            bail!("a turntable needs at least one frame");
+        }
+        std::fs::create_dir_all(output_dir)
+            .with_context(|| format!("can't create turntable directory {}", output_dir))?;
+        self.turntable = Some(Turntable {
+            orbit,
+            frames,
+            frame: 0,
+            output_dir: PathBuf::from(output_dir),
+        });
+        Ok(())
+    }
+    // camera position and view direction of the frame being captured, None when no turntable is running
+    pub fn turntable_pose(&self) -> Option<(glam::Vec3, glam::Vec3)> {
+        self.turntable.as_ref().map(|turntable| {
+            let angle = turntable.frame as f32 / turntable.frames as f32 * std::f32::consts::TAU;
+            turntable.orbit.pose(angle)
+        })
+    }
+    // saves the frame that was just drawn, returns true once the last frame of the turntable was written
+    pub(crate) fn capture_turntable_frame(&mut self) -> Result<bool> {
+        let Some(turntable) = &self.turntable else {
+            return Ok(false);
+        };
+        let path = turntable
+            .output_dir
+            .join(format!("frame_{:04}.png", turntable.frame));
+        let texture = self
+            .ui_target
+            .clone()
+            .or_else(|| self.last_to_screen_texture_handle.clone())
+            .context("turntable_capture needs a to_screen every frame")?;
+        self.save_png(&texture, &path)?;
+        let turntable = self.turntable.as_mut().unwrap();
+        turntable.frame += 1;
+        if turntable.frame == turntable.frames {
+            info!(
+                "turntable of {} frames written to {}",
+                turntable.frames,
+                turntable.output_dir.display()
+            );
+            self.turntable = None;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+    // writes a 2d Rgba8Unorm texture to a png, waiting for all gpu work that writes it
+    pub fn save_png(&mut self, texture: &ResourceHandle, path: impl AsRef<Path>) -> Result<()> {
+        puffin::profile_function!();
+        let path = path.as_ref();
+        if !matches!(texture, ResourceHandle::Texture(_)) {
+            bail!("{} is not a texture", self.resource_pool.named(texture));
+        }
+        self.resource_pool
+            .prepare_resources(&self.device, self.render_size())?;
+        let pool_texture = self.resource_pool.grab_texture(texture);
+        if !matches!(
+            pool_texture.format,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+        ) || pool_texture.size.2 != 1
+        {
+            bail!(
+                "only 2d rgba8 textures can be saved as png, {} is {:?} with size {:?}",
+                pool_texture.name,
+                pool_texture.format,
+                pool_texture.size
+            );
+        }
+        let (width, height, _) = pool_texture.size;
+        // rows in the staging buffer are padded to the copy alignment
+        let row_bytes = width * 4;
+        let padded_row_bytes = wgpu::util::align_to(row_bytes, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("save_png_staging"),
+            size: (padded_row_bytes * height) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Save Png Encoder"),
+                });
+        command_encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: pool_texture
+                    .texture
+                    .as_ref()
+                    .context("texture was not allocated")?,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(std::iter::once(command_encoder.finish()));
+        let pixels = PendingRead::new(staging_buffer, move |bytes| {
+            bytes
+                .chunks_exact(padded_row_bytes as usize)
+                .flat_map(|row| row[..row_bytes as usize].iter().copied())
+                .collect::<Vec<u8>>()
+        })
+        .wait(self)?;
+
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("can't create {}", path.display()))?;
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&pixels)?;
+        Ok(())
+    }
+    // copies the current contents of a buffer or texture to the cpu, waiting for the work submitted so far. diff two
+    // snapshots to see which elements a pass changed
+    pub fn snapshot(&mut self, handle: &ResourceHandle) -> Result<Snapshot> {
+        puffin::profile_function!();
+        let frame = self.frame;
+        let mut encoder = self.get_encoder()?;
+        let (name, element_size, size, pending) = match handle {
+            ResourceHandle::Buffer(_) => {
+                let buffer = encoder.gpu_context.resource_pool.grab_buffer(handle);
+                let (name, element_size) = (buffer.name.clone(), buffer.element_size);
+                let elements = (buffer.size / element_size as u64) as u32;
+                let pending = encoder.read_buffer_bytes(handle)?;
+                (name, element_size, (elements, 1, 1), pending)
+            }
+            ResourceHandle::Texture(_) => {
+                let texture = encoder.gpu_context.resource_pool.grab_texture(handle);
+                let (name, size) = (texture.name.clone(), texture.size);
+                let element_size = texture
+                    .format
+                    .block_size(None)
+                    .context("could not get block size")?
+                    as usize;
+                let pending = encoder.read_texture_region::<u8>(handle, (0, 0, 0), size)?;
+                (name, element_size, size, pending)
+            }
+            ResourceHandle::TextureArray(_) => {
+                bail!("snapshot the textures of a texture array one by one")
+            }
+        };
+        drop(encoder);
+        Ok(Snapshot {
+            name,
+            frame,
+            element_size,
+            size,
+            data: pending.wait(self)?,
+        })
+    }
+    // blocks until all submitted gpu work has finished, useful before reading back results
+    pub fn wait_idle(&self) {
+        puffin::profile_function!();
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+
+    // video modes of the monitor the window is on, for picking one for set_video_mode
+    pub fn video_modes(&self) -> Vec<winit::monitor::VideoMode> {
+        self.window
+            .as_ref()
+            .and_then(|window| window.window.current_monitor())
+            .map(|monitor| monitor.video_modes().collect())
+            .unwrap_or_default()
+    }
+    pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+        self.window
+            .as_ref()?
+            .window
+            .current_monitor()?
+            .refresh_rate_millihertz()
+    }
+    // switches to exclusive fullscreen in the requested video mode, or borderless fullscreen for None
+    pub fn set_video_mode(&mut self, video_mode: Option<VideoModeRequest>) -> Result<()> {
+        let Some(window) = &self.window else {
+            bail!("a headless context has no window");
+        };
+        let monitor = match window.window.current_monitor() {
+            Some(monitor) => monitor,
+            None => bail!("the window isn't on a monitor"),
+        };
+        let size = match &video_mode {
+            Some(request) => (request.width, request.height),
+            None => (monitor.size().width, monitor.size().height),
+        };
+        let fullscreen = WindowConfig { video_mode }.fullscreen(monitor)?;
+        info!(fullscreen = ?fullscreen, "switching fullscreen mode");
+        window.window.set_fullscreen(Some(fullscreen));
+        self.config.width = size.0;
+        self.config.height = size.1;
+        window.surface.configure(&self.device, &self.config);
+        if let Some(previous_frame) = &self.previous_frame {
+            self.resource_pool.textures[previous_frame.get_index()].resolution =
+                TextureRes::Custom(size.0, size.1, 1);
+        }
+        Ok(())
+    }
+    // how fast the exposure of to_screen_auto_exposure follows the scene, higher is faster. at a speed of 1 the
+    // exposure covers about 63% of the way to its target in a second
+    pub fn set_auto_exposure_speed(&mut self, speed: f32) {
+        self.auto_exposure_speed = speed;
+    }
+    // the average luminance to_screen_auto_exposure exposes the scene to, 0.18 by default
+    pub fn set_auto_exposure_key(&mut self, key: f32) {
+        self.auto_exposure_key = key;
+    }
+    // checks the total workgroup count of every dispatch against a ceiling. dispatching pixel counts instead of
+    // workgroup counts gives millions of workgroups which can hang the gpu, this catches that before it's submitted
+    pub fn set_workgroup_ceiling(&mut self, ceiling: WorkgroupCeiling) {
+        self.workgroup_ceiling = ceiling;
+    }
+    // appends widgets to the built-in top bar, sections are drawn every frame after the built-in toggles in the order
+    // they were added
+    pub fn add_top_bar(&mut self, section: impl FnMut(&mut egui::Ui) + 'static) {
+        self.top_bar_sections.push(Box::new(section));
+    }
+    // also draws the ui into a texture, on top of what the texture contains, so it ends up in a saved or composited
+    // frame. the texture is created with CoGr::texture_render_target and the ui is scaled to its size. it gets its
+    // own egui renderer, so images from CoGr::egui_texture_from_handle only show up on the screen. a turntable
+    // capture saves the ui target instead of the to_screen texture while one is set
+    pub fn set_ui_target(&mut self, target: Option<&ResourceHandle>) -> Result<()> {
+        let Some(target) = target else {
+            self.ui_target = None;
+            self.ui_target_renderer = None;
+            return Ok(());
+        };
+        if !matches!(target, ResourceHandle::Texture(_)) {
+            bail!(
+                "the ui can only be drawn into a texture, not {}",
+                self.resource_pool.named(target)
+            );
+        }
+        let texture = self.resource_pool.grab_texture(target);
+        if !texture
+            .usage
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            bail!(
+                "the ui can't be drawn into {}, create it with CoGr::texture_render_target",
+                texture.name
+            );
+        }
+        if self
+            .ui_target_renderer
+            .as_ref()
+            .map_or(true, |(format, _)| *format != texture.format)
+        {
+            let mut renderer = egui_wgpu::Renderer::new(&self.device, texture.format, None, 1);
+            // later font changes arrive as texture deltas, the atlas so far has to be uploaded once
+            let font_image = self.context.fonts(|fonts| fonts.image());
+            renderer.update_texture(
+                &self.device,
+                &self.queue,
+                egui::TextureId::default(),
+                &egui::epaint::ImageDelta::full(font_image, egui::TextureOptions::LINEAR),
+            );
+            self.ui_target_renderer = Some((texture.format, renderer));
+        }
+        self.ui_target = Some(target.clone());
+        Ok(())
+    }
+    // whether draw_ui draws to the window, turn it off to only draw the ui into the target of set_ui_target
+    pub fn set_ui_on_screen(&mut self, on_screen: bool) {
+        self.ui_on_screen = on_screen;
+    }
+    // the limits of the device, like max_compute_workgroup_storage_size for var<workgroup> memory
+    pub fn limits(&self) -> wgpu::Limits {
+        self.device.limits()
+    }
+    // resolution the screen relative textures and buffers get, the window size times the render scale.
+    // to_screen scales the result up or down to the window
+    pub fn render_size(&self) -> (u32, u32) {
+        (
+            ((self.config.width as f32 * self.render_scale).round() as u32).max(1),
+            ((self.config.height as f32 * self.render_scale).round() as u32).max(1),
+        )
+    }
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+    // rendering at a lower scale like 0.75 trades resolution for speed in heavy shaders. screen relative
+    // resources are reallocated at the new size when the next encoder is created
+    pub fn set_render_scale(&mut self, render_scale: f32) {
+        self.render_scale = render_scale.max(f32::EPSILON);
+    }
+    // linear color the window is cleared to at the start of every get_encoder_for_draw, so frames that only draw ui
+    // or don't cover the whole window have a defined background. black by default
+    pub fn set_clear_color(&mut self, color: [f32; 4]) {
+        self.clear_color = Some(color);
+    }
+    // skips the clear, for apps that cover the whole window with to_screen every frame
+    pub fn disable_clear(&mut self) {
+        self.clear_color = None;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+    // seconds the game has been running, the sum of the dt of every on_render. it stands still while paused and
+    // advances by exactly the step dt when stepping, so passing it to shaders keeps animations reproducible
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+    // number of frames the clock advanced, paused frames aren't counted
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+    pub(crate) fn advance_clock(&mut self, dt: f32) {
+        self.time += dt;
+        self.frame += 1;
+    }
+    // groups textures into one binding, bound in wgsl as binding_array<texture_storage_2d<..>, N>.
+    // needs binding array support from the adapter
+    pub fn texture_array(&mut self, textures: &[&ResourceHandle]) -> Result<ResourceHandle> {
+        let required = Features::TEXTURE_BINDING_ARRAY | Features::STORAGE_RESOURCE_BINDING_ARRAY;
+        if !self.device.features().contains(required) {
+            bail!(
+                "texture arrays need {:?}, which this adapter doesn't support",
+                required
+            );
+        }
+        self.resource_pool.texture_array(textures)
+    }
+    // loads a png into a custom resolution Rgba8Unorm texture, images without alpha get an opaque alpha channel
+    pub fn texture_from_image(&mut self, name: &str, path: &str) -> Result<ResourceHandle> {
+        puffin::profile_function!();
+        let mut decoder = png::Decoder::new(std::fs::File::open(path)?);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut data)?;
+        data.truncate(frame.buffer_size());
+        let rgba = match frame.color_type {
+            png::ColorType::Rgba => data,
+            png::ColorType::Rgb => rgb_to_rgba(&data, 255),
+            png::ColorType::Grayscale => data.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+            png::ColorType::GrayscaleAlpha => data
+                .chunks_exact(2)
+                .flat_map(|v| [v[0], v[0], v[0], v[1]])
+                .collect(),
+            png::ColorType::Indexed => bail!("{} still has indexed colors after decoding", path),
+        };
+        info!(
+            path,
+            width = frame.width,
+            height = frame.height,
+            "loaded image"
+        );
+
+        let handle = self.texture(
+            name,
+            TextureRes::Custom(frame.width, frame.height, 1),
+            TextureFormat::Rgba8Unorm,
+        );
+        self.resource_pool
+            .prepare_resources(&self.device, self.render_size())?;
+        let texture = self.resource_pool.grab_texture(&handle);
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: texture.texture.as_ref().unwrap(),
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(frame.width * 4),
+                rows_per_image: Some(frame.height),
+            },
+            wgpu::Extent3d {
+                width: frame.width,
+                height: frame.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        Ok(handle)
+    }
+    // like texture, but it can also be the target of Encoder::to_texture
+    pub fn texture_render_target(
+        &mut self,
+        name: &str,
+        elements: TextureRes,
+        format: wgpu::TextureFormat,
+    ) -> ResourceHandle {
+        self.resource_pool
+            .texture_render_target(name.to_string(), elements, format)
+    }
+    // keeps a resource alive even when the game drops all its handles, so a resource that is only bound after a shader
+    // edit isn't freed in the meantime. a pinned texture array also keeps its textures alive
+    pub fn pin(&mut self, handle: &ResourceHandle) {
+        self.resource_pool.pin(handle);
+    }
+    // lets the resource be freed again once the game holds no handles to it
+    pub fn unpin(&mut self, handle: &ResourceHandle) {
+        self.resource_pool.unpin(handle);
+    }
+    pub fn is_pinned(&self, handle: &ResourceHandle) -> bool {
+        self.resource_pool.is_pinned(handle)
+    }
+    // like texture, but the texture can also be bound as texture_2d<f32> to read it with a sampler. storage only
+    // textures leave this usage out
+    pub fn texture_sampled(
+        &mut self,
+        name: &str,
+        elements: TextureRes,
+        format: wgpu::TextureFormat,
+    ) -> ResourceHandle {
+        self.resource_pool
+            .texture_sampled(name.to_string(), elements, format)
+    }
+    // sums the first count elements of a u32 or f32 buffer on the gpu, the result can be taken from the
+    // PendingRead right away since the reduction is submitted on its own
+    pub fn reduce_sum<T: ReduceElement>(
+        &mut self,
+        buffer: &ResourceHandle,
+        count: u32,
+    ) -> Result<PendingRead<T>> {
+        self.reduce(ReduceOp::Sum, buffer, count)
+    }
+    pub fn reduce_min<T: ReduceElement>(
+        &mut self,
+        buffer: &ResourceHandle,
+        count: u32,
+    ) -> Result<PendingRead<T>> {
+        self.reduce(ReduceOp::Min, buffer, count)
+    }
+    pub fn reduce_max<T: ReduceElement>(
+        &mut self,
+        buffer: &ResourceHandle,
+        count: u32,
+    ) -> Result<PendingRead<T>> {
+        self.reduce(ReduceOp::Max, buffer, count)
+    }
+    // tree reduction, every pass reduces REDUCE_ELEMENTS_PER_GROUP elements to one until a single one is left
+    fn reduce<T: ReduceElement>(
+        &mut self,
+        op: ReduceOp,
+        buffer: &ResourceHandle,
+        count: u32,
+    ) -> Result<PendingRead<T>> {
+        puffin::profile_function!();
+        if !matches!(buffer, ResourceHandle::Buffer(_)) {
+            bail!("{} is not a buffer", self.resource_pool.named(buffer));
+        }
+        self.resource_pool
+            .prepare_resources(&self.device, self.render_size())?;
+        let pool_buffer = self.resource_pool.grab_buffer(buffer);
+        if !pool_buffer.usage.contains(wgpu::BufferUsages::STORAGE) {
+            // the reduction reads it as storage buffer
+            bail!(
+                "{} is a uniform buffer, only storage buffers can be reduced",
+                pool_buffer.name
+            );
+        }
+        if pool_buffer.element_size != size_of::<T>() {
+            bail!(
+                "{} has elements of {} bytes, they can't be reduced as {}",
+                pool_buffer.name,
+                pool_buffer.element_size,
+                T::WGSL_TYPE
+            );
+        }
+        let element_count = self.resource_pool.element_count(buffer);
+        if count == 0 || count as u64 > element_count {
+            bail!(
+                "can't reduce {} elements of {} which has {} elements",
+                count,
+                pool_buffer.name,
+                element_count
+            );
+        }
+
+        let pipeline_index = match self
+            .reduce_pipelines
+            .iter()
+            .position(|pipeline| pipeline.op == op && pipeline.element_type == T::WGSL_TYPE)
+        {
+            Some(index) => index,
+            None => {
+                self.reduce_pipelines
+                    .push(ReducePipeline::new::<T>(&self.device, op));
+                self.reduce_pipelines.len() - 1
+            }
+        };
+        let reduce_pipeline = &self.reduce_pipelines[pipeline_index];
+        let pool_buffer = self.resource_pool.grab_buffer(buffer);
+        let max_work_groups = self.device.limits().max_compute_workgroups_per_dimension;
+
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Reduce Encoder"),
+                });
+        let mut partials: Vec<wgpu::Buffer> = Vec::new();
+        let mut count = count;
+        while partials.is_empty() || count > 1 {
+            let groups = reduce_groups(count);
+            let output = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("reduce_partials"),
+                size: wgpu::util::align_to(
+                    groups as u64 * size_of::<T>() as u64,
+                    wgpu::COPY_BUFFER_ALIGNMENT,
+                ),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let input = match partials.last() {
+                Some(partial) => partial,
+                None => pool_buffer
+                    .buffer
+                    .as_ref()
+                    .context("buffer was not allocated")?,
+            };
+            reduce_pipeline.record(
+                &self.device,
+                &mut command_encoder,
+                input,
+                &output,
+                count,
+                max_work_groups,
+            )?;
+            partials.push(output);
+            count = groups;
+        }
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("reduce_staging"),
+            size: size_of::<T>() as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        command_encoder.copy_buffer_to_buffer(
+            partials.last().unwrap(),
+            0,
+            &staging_buffer,
+            0,
+            size_of::<T>() as u64,
+        );
+        self.queue.submit(std::iter::once(command_encoder.finish()));
+        Ok(PendingRead::new(staging_buffer, |bytes| {
+            bytemuck::pod_read_unaligned(bytes)
+        }))
+    }
+    // counts the texels of a float texture with a nan or inf in any channel and logs a warning when there are any.
+    // it waits for the gpu, so it's only enabled in debug builds by default, otherwise it returns None
+    pub fn check_texture_finite(&mut self, texture: &ResourceHandle) -> Result<Option<u32>> {
+        puffin::profile_function!();
+        if !self.check_finite {
+            return Ok(None);
+        }
+        if !matches!(texture, ResourceHandle::Texture(_)) {
+            bail!("{} is not a texture", self.resource_pool.named(texture));
+        }
+        self.resource_pool
+            .prepare_resources(&self.device, self.render_size())?;
+        let pool_texture = self.resource_pool.grab_texture(texture);
+        let format = pool_texture.format;
+        if !matches!(
+            format,
+            TextureFormat::Rgba16Float
+                | TextureFormat::Rgba32Float
+                | TextureFormat::R32Float
+                | TextureFormat::Rg32Float
+        ) || pool_texture.size.2 != 1
+        {
+            bail!(
+                "only 2d float textures can be checked, {} is {:?} with size {:?}",
+                pool_texture.name,
+                format,
+                pool_texture.size
+            );
+        }
+
+        let pipeline_index = match self
+            .finite_check_pipelines
+            .iter()
+            .position(|pipeline| pipeline.format == format)
+        {
+            Some(index) => index,
+            None => {
+                self.finite_check_pipelines
+                    .push(FiniteCheckPipeline::new(&self.device, format));
+                self.finite_check_pipelines.len() - 1
+            }
+        };
+        let pool_texture = self.resource_pool.grab_texture(texture);
+        let counter = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("finite_check_counter"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("finite_check_staging"),
+            size: 4,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Finite Check Encoder"),
+                });
+        self.finite_check_pipelines[pipeline_index].record(
+            &self.device,
+            &mut command_encoder,
+            pool_texture
+                .texture_view
+                .as_ref()
+                .context("texture was not allocated")?,
+            (pool_texture.size.0, pool_texture.size.1),
+            &counter,
+        );
+        command_encoder.copy_buffer_to_buffer(&counter, 0, &staging_buffer, 0, 4);
+        self.queue.submit(std::iter::once(command_encoder.finish()));
+
+        let non_finite = PendingRead::new(staging_buffer, |bytes| {
+            bytemuck::pod_read_unaligned::<u32>(bytes)
+        })
+        .wait(self)?;
+        if non_finite > 0 {
+            warn!(
+                resource = %self.resource_pool.named(texture),
+                non_finite,
+                "texture contains nan or inf texels"
+            );
+        }
+        Ok(Some(non_finite))
+    }
+    // enables or disables check_texture_finite, by default it only runs in debug builds
+    pub fn set_check_finite(&mut self, check_finite: bool) {
+        self.check_finite = check_finite;
+    }
+    // registers a texture with the ui so it can be shown in an egui::Image, handy for looking at intermediate
+    // results. the texture gets the sampled usage added, when it was already allocated without it that
+    // reallocates the texture and its contents are lost. the format has to be filterable, like Rgba8Unorm
+    pub fn egui_texture_from_handle(&mut self, handle: &ResourceHandle) -> Result<egui::TextureId> {
+        if !matches!(handle, ResourceHandle::Texture(_)) {
+            bail!("{} is not a texture", self.resource_pool.named(handle));
+        }
+        let registered = self
+            .egui_textures
+            .iter()
+            .find(|texture| texture.handle.upgrade().is_some_and(|h| h.ptr_eq(handle)));
+        if let Some(texture) = registered {
+            return Ok(texture.id);
+        }
+        let texture = &mut self.resource_pool.textures[handle.get_index()];
+        if !texture.usage.contains(wgpu::TextureUsages::TEXTURE_BINDING) {
+            texture.usage |= wgpu::TextureUsages::TEXTURE_BINDING;
+            texture.texture = None;
+            texture.texture_view = None;
+        }
+        self.resource_pool
+            .prepare_resources(&self.device, self.render_size())?;
+        let texture = self.resource_pool.grab_texture(handle);
+        let id = self.renderer.register_native_texture(
+            &self.device,
+            texture.texture_view.as_ref().unwrap(),
+            wgpu::FilterMode::Linear,
+        );
+        self.egui_textures.push(EguiTexture {
+            handle: handle.downgrade(),
+            id,
+            generation: texture.generation,
+        });
+        Ok(id)
+    }
+    // points the ui textures at the current views of their pool textures and frees the ones that are gone
+    pub(crate) fn update_egui_textures(&mut self) {
+        puffin::profile_function!();
+        let device = &self.device;
+        let renderer = &mut self.renderer;
+        let resource_pool = &self.resource_pool;
+        self.egui_textures
+            .retain_mut(|egui_texture| match egui_texture.handle.upgrade() {
+                Some(handle) => {
+                    let texture = resource_pool.grab_texture(&handle);
+                    if texture.generation != egui_texture.generation {
+                        if let Some(view) = &texture.texture_view {
+                            renderer.update_egui_texture_from_wgpu_texture(
+                                device,
+                                view,
+                                wgpu::FilterMode::Linear,
+                                egui_texture.id,
+                            );
+                            egui_texture.generation = texture.generation;
+                        }
+                    }
+                    true
+                }
+                None => {
+                    renderer.free_texture(&egui_texture.id);
+                    false
+                }
+            });
+    }
+    // like texture, but the texture starts out as the given color instead of undefined contents. only works
+    // for 2d textures with a format that can be rendered to
+    pub fn texture_cleared(
+        &mut self,
+        name: &str,
+        elements: TextureRes,
+        format: wgpu::TextureFormat,
+        color: [f64; 4],
+    ) -> ResourceHandle {
+        let [r, g, b, a] = color;
+        self.resource_pool.texture_cleared(
+            name.to_string(),
+            elements,
+            format,
+            wgpu::Color { r, g, b, a },
+        )
+    }
+
     pub fn handle_window_event(&mut self, event: &WindowEvent) {
-        let _ = self.state.on_event(&self.context, event);
+        if let Some(window) = &mut self.window {
+            let _ = window.state.on_event(&self.context, event);
+        }
+        match event {
+            WindowEvent::Focused(focused) => self.focused = *focused,
+            WindowEvent::Occluded(occluded) => self.occluded = *occluded,
+            _ => {}
+        }
+    }
+    // whether the window has keyboard focus
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+    // whether the window is minimized or fully covered, not every platform reports this
+    pub fn is_occluded(&self) -> bool {
+        self.occluded
+    }
+    // parses, validates and reflects a shader without creating anything on the gpu, so a test can check every shader
+    // of a project compiles without a window or device. the bindings have to be numbered 0 up without gaps, since
+    // pipelines bind resources by their position
+    pub fn check_shader(shader_file: &str) -> Result<ReflectionInfo> {
+        let reflection = ReflectionInfo::from_file(shader_file)?;
+        let bindings = reflection.bindings();
+        if let Some((expected, binding)) = bindings
+            .iter()
+            .enumerate()
+            .find(|(expected, binding)| *expected as u32 != **binding)
+        {
+            bail!(
+                "{} has no binding {} but does declare binding {}",
+                shader_file,
+                expected,
+                binding
+            );
+        }
+        for entry_point in reflection.entry_points() {
+            if let Ok(size) = reflection.workgroup_size(entry_point) {
+                if size.iter().product::<u32>()
+                    > wgpu::Limits::default().max_compute_invocations_per_workgroup
+                {
+                    bail!(
+                        "entry point {} of {} has a workgroup size of {:?}, more invocations than devices have to support",
+                        entry_point,
+                        shader_file,
+                        size
+                    );
+                }
+            }
+        }
+        Ok(reflection)
     }
+    // compiles a compute pipeline. the resources are bound in order, the first one at @group(0) @binding(0) and the
+    // next ones at the following bindings, dispatches have to pass them in the same order. creating the pipeline
+    // fails when a resource lands on a binding of another kind or the entry point uses a binding without a resource.
+    // bindings_by_index puts resources given with their binding in this order
     pub fn pipeline(
         &mut self,
         shader_file: &str,
         entry_point: &str,
         bindings: &[&ResourceHandle],
     ) -> Result<Pipeline> {
-        Pipeline::new(self, shader_file, entry_point, bindings)
+        self.pipeline_with_options(
+            shader_file,
+            entry_point,
+            bindings,
+            &PipelineOptions::default(),
+        )
+    }
+    // starts a pipeline with more options than pipeline takes, like defines and read only bindings
+    pub fn pipeline_builder(&mut self, shader_file: &str) -> PipelineBuilder {
+        PipelineBuilder::new(self, shader_file)
+    }
+    pub(crate) fn pipeline_with_options(
+        &mut self,
+        shader_file: &str,
+        entry_point: &str,
+        bindings: &[&ResourceHandle],
+        options: &PipelineOptions,
+    ) -> Result<Pipeline> {
+        let pipeline = Pipeline::new(self, shader_file, entry_point, bindings, options);
+        self.record_pipeline_reload(shader_file, entry_point, pipeline.as_ref().err());
+        let pipeline = pipeline?;
+        if !self.virtual_shaders.contains_key(shader_file) {
+            self.shader_editor.add_shader_file(shader_file);
+        }
+        self.track_pipeline_bindings(
+            &format!("{}:{}", shader_file, entry_point),
+            bindings,
+            &pipeline,
+        );
+        Ok(pipeline)
+    }
+    // one pipeline per entry point of the shader, all bound to the same resources. the first entry point is
+    // selected initially and the entry point names are the mode names
+    pub fn pipeline_modes(
+        &mut self,
+        shader_file: &str,
+        entry_points: &[&str],
+        bindings: &[&ResourceHandle],
+    ) -> Result<PipelineModes> {
+        if entry_points.is_empty() {
+            bail!("{} needs at least one mode", shader_file);
+        }
+        let modes = entry_points
+            .iter()
+            .map(|entry_point| {
+                let pipeline = self.pipeline(shader_file, entry_point, bindings)?;
+                Ok((entry_point.to_string(), pipeline))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(PipelineModes::new(modes))
+    }
+    // like pipeline, but the wgsl code comes from a string, for example from include_str! so the shader is baked
+    // into the binary. the name is used in labels and errors, these pipelines aren't hot reloaded
+    pub fn pipeline_from_source(
+        &mut self,
+        name: &str,
+        source: &str,
+        entry_point: &str,
+        bindings: &[&ResourceHandle],
+    ) -> Result<Pipeline> {
+        let pipeline = Pipeline::from_source(self, name, source, entry_point, bindings);
+        self.record_pipeline_reload(name, entry_point, pipeline.as_ref().err());
+        let pipeline = pipeline?;
+        self.track_pipeline_bindings(&format!("{}:{}", name, entry_point), bindings, &pipeline);
+        Ok(pipeline)
+    }
+    // remembers the resources of a new pipeline for validate, pipelines whose resources were freed are forgotten
+    // so the list doesn't grow with every recreated pipeline
+    fn track_pipeline_bindings(
+        &mut self,
+        name: &str,
+        bindings: &[&ResourceHandle],
+        pipeline: &Pipeline,
+    ) {
+        self.pipeline_bindings.retain(PipelineBindings::alive);
+        self.pipeline_bindings.push(PipelineBindings {
+            name: name.to_string(),
+            bindings: bindings.iter().map(|handle| handle.downgrade()).collect(),
+            uniform_bindings: pipeline.uniform_bindings.clone(),
+            sampled_bindings: pipeline.sampled_bindings.clone(),
+        });
+    }
+    // checks whether every pipeline created so far can be dispatched with the resources it was created with,
+    // meant to be called at the end of on_init so mistakes show up before the first frame
+    pub fn validate(&mut self) -> Result<()> {
+        self.resource_pool
+            .prepare_resources(&self.device, self.render_size())?;
+        let limits = self.device.limits();
+        let mut errors = Vec::new();
+        // the resources were freed, so those pipelines can't be used with them anymore
+        self.pipeline_bindings.retain(PipelineBindings::alive);
+        for pipeline in &self.pipeline_bindings {
+            let bindings = pipeline
+                .bindings
+                .iter()
+                .filter_map(|handle| handle.upgrade())
+                .collect::<Vec<_>>();
+            // buffers declared as var<uniform>, or made with CoGr::uniform_buffer, count against the uniform limits
+            let (uniform_buffers, storage_buffers): (Vec<_>, Vec<_>) = bindings
+                .iter()
+                .enumerate()
+                .filter(|(_, handle)| matches!(handle, ResourceHandle::Buffer(_)))
+                .partition(|(index, handle)| {
+                    pipeline.uniform_bindings.contains(&(*index as u32))
+                        || !self
+                            .resource_pool
+                            .grab_buffer(handle)
+                            .usage
+                            .contains(wgpu::BufferUsages::STORAGE)
+                });
+            // sampled textures don't need the STORAGE_BINDING usage and have their own limit
+            let (sampled_textures, textures): (Vec<_>, Vec<_>) = bindings
+                .iter()
+                .enumerate()
+                .flat_map(|(index, handle)| {
+                    handle
+                        .textures()
+                        .into_iter()
+                        .map(move |texture| (index, texture))
+                })
+                .partition(|(index, _)| pipeline.sampled_bindings.contains(&(*index as u32)));
+            let texture_count = textures.len() as u32;
+            let sampled_texture_count = sampled_textures.len() as u32;
+            for (buffers, kind, max_count, max_size) in [
+                (
+                    &storage_buffers,
+                    "storage",
+                    limits.max_storage_buffers_per_shader_stage,
+                    limits.max_storage_buffer_binding_size,
+                ),
+                (
+                    &uniform_buffers,
+                    "uniform",
+                    limits.max_uniform_buffers_per_shader_stage,
+                    limits.max_uniform_buffer_binding_size,
+                ),
+            ] {
+                if buffers.len() as u32 > max_count {
+                    errors.push(format!(
+                        "{} binds {} {} buffers while the device supports {}",
+                        pipeline.name,
+                        buffers.len(),
+                        kind,
+                        max_count
+                    ));
+                }
+                for (_, handle) in buffers {
+                    let size = self.resource_pool.grab_buffer(handle).size;
+                    if size == 0 || size > max_size as u64 {
+                        errors.push(format!(
+                            "{} binds {} as {} buffer with a size of {} bytes, which has to be between 1 and {}",
+                            pipeline.name,
+                            self.resource_pool.named(handle),
+                            kind,
+                            size,
+                            max_size
+                        ));
+                    }
+                }
+            }
+            if texture_count > limits.max_storage_textures_per_shader_stage {
+                errors.push(format!(
+                    "{} binds {} textures while the device supports {}",
+                    pipeline.name, texture_count, limits.max_storage_textures_per_shader_stage
+                ));
+            }
+            if sampled_texture_count > limits.max_sampled_textures_per_shader_stage {
+                errors.push(format!(
+                    "{} binds {} sampled textures while the device supports {}",
+                    pipeline.name,
+                    sampled_texture_count,
+                    limits.max_sampled_textures_per_shader_stage
+                ));
+            }
+            for (_, handle) in &textures {
+                let format = self.resource_pool.grab_texture(handle).format;
+                if !format
+                    .guaranteed_format_features(self.device.features())
+                    .allowed_usages
+                    .contains(wgpu::TextureUsages::STORAGE_BINDING)
+                {
+                    errors.push(format!(
+                        "{} binds {} which has format {:?} that can't be used as storage texture",
+                        pipeline.name,
+                        self.resource_pool.named(handle),
+                        format
+                    ));
+                }
+            }
+        }
+        if !errors.is_empty() {
+            bail!("pipeline validation failed:\n{}", errors.join("\n"));
+        }
+        Ok(())
+    }
+    pub fn mesh_pipeline(
+        &mut self,
+        shader_file: &str,
+        vertex_attributes: &[wgpu::VertexFormat],
+        topology: wgpu::PrimitiveTopology,
+        bindings: &[&ResourceHandle],
+    ) -> Result<MeshPipeline> {
+        let pipeline = MeshPipeline::new(self, shader_file, vertex_attributes, topology, bindings);
+        self.record_pipeline_reload(shader_file, MESH_ENTRY_POINTS, pipeline.as_ref().err());
+        let pipeline = pipeline?;
+        if !self.virtual_shaders.contains_key(shader_file) {
+            self.shader_editor.add_shader_file(shader_file);
+        }
+        Ok(pipeline)
+    }
+    // every pipeline created so far with the result of its last (re)load
+    pub fn pipelines(&self) -> &[PipelineInfo] {
+        &self.pipeline_infos
+    }
+    // recompiles every pipeline the next time it's dispatched or drawn, even if its source didn't change
+    pub fn reload_pipelines(&mut self) {
+        self.reload_generation += 1;
+    }
+    // makes a shader or include available under a path without it existing on disk, so a binary can ship its
+    // shaders with include_str!. pipelines and includes look here before reading files, virtual shaders aren't hot
+    // reloaded but registering one again recompiles every pipeline
+    pub fn register_virtual_shader(&mut self, path: &str, source: &str) {
+        self.virtual_shaders
+            .insert(path.to_string(), source.to_string());
+        self.reload_generation += 1;
+    }
+    pub(crate) fn read_shader(&self, path: &str) -> Result<String> {
+        match self.virtual_shaders.get(path) {
+            Some(source) => Ok(source.clone()),
+            None => Ok(std::fs::read_to_string(path)?),
+        }
+    }
+    // the code of a shader with its includes
+    pub(crate) fn load_shader(&self, path: &str) -> Result<SourceMap> {
+        let mut source = SourceMap::default();
+        source.push_file(path, &|file| self.read_shader(file))?;
+        Ok(source)
+    }
+    // the files of the source that aren't virtual, for hot reloading
+    pub(crate) fn files_on_disk(&self, source: &SourceMap) -> Vec<String> {
+        source
+            .files()
+            .into_iter()
+            .filter(|file| !self.virtual_shaders.contains_key(*file))
+            .map(str::to_string)
+            .collect()
+    }
+    pub(crate) fn record_pipeline_reload(
+        &mut self,
+        source: &str,
+        entry_point: &str,
+        error: Option<&anyhow::Error>,
+    ) {
+        let index = match self
+            .pipeline_infos
+            .iter()
+            .position(|info| info.source == source && info.entry_point == entry_point)
+        {
+            Some(index) => index,
+            None => {
+                self.pipeline_infos.push(PipelineInfo {
+                    name: format!("{}:{}", source, entry_point),
+                    source: source.to_string(),
+                    entry_point: entry_point.to_string(),
+                    last_reload: SystemTime::now(),
+                    last_error: None,
+                });
+                self.pipeline_infos.len() - 1
+            }
+        };
+        let info = &mut self.pipeline_infos[index];
+        match error {
+            Some(error) => info.last_error = Some(error.to_string()),
+            None => {
+                info.last_reload = SystemTime::now();
+                info.last_error = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // a headless context for tests that need a gpu, None when there is no adapter so those tests are skipped
+    pub(crate) fn headless() -> Option<CoGr> {
+        match CoGr::headless(64, 64) {
+            Ok(gpu) => Some(gpu),
+            Err(err) => {
+                eprintln!("skipping test, no headless gpu: {:#}", err);
+                None
+            }
+        }
+    }
+
+    fn check_reductions(gpu: &mut CoGr, count: u32) -> Result<()> {
+        let integers = (0..count)
+            .map(|i| i.wrapping_mul(2654435761) % 1000)
+            .collect::<Vec<u32>>();
+        // small whole numbers, so every partial sum is exact in f32 and the order of the sum doesn't matter
+        let floats = (0..count)
+            .map(|i| (i % 17) as f32 - 8.0)
+            .collect::<Vec<f32>>();
+        let integer_buffer = gpu.buffer("integers", BufferSize::Custom(count as u64), 4);
+        let float_buffer = gpu.buffer("floats", BufferSize::Custom(count as u64), 4);
+        {
+            let mut encoder = gpu.get_encoder()?;
+            encoder.set_buffer_data_iter(&integer_buffer, integers.iter().copied())?;
+            encoder.set_buffer_data_iter(&float_buffer, floats.iter().copied())?;
+        }
+
+        let sum = gpu.reduce_sum::<u32>(&integer_buffer, count)?;
+        assert_eq!(
+            sum.wait(gpu)?,
+            integers.iter().sum::<u32>(),
+            "u32 sum of {}",
+            count
+        );
+        let min = gpu.reduce_min::<u32>(&integer_buffer, count)?;
+        assert_eq!(
+            min.wait(gpu)?,
+            *integers.iter().min().unwrap(),
+            "u32 min of {}",
+            count
+        );
+        let max = gpu.reduce_max::<u32>(&integer_buffer, count)?;
+        assert_eq!(
+            max.wait(gpu)?,
+            *integers.iter().max().unwrap(),
+            "u32 max of {}",
+            count
+        );
+
+        let sum = gpu.reduce_sum::<f32>(&float_buffer, count)?;
+        assert_eq!(
+            sum.wait(gpu)?,
+            floats.iter().sum::<f32>(),
+            "f32 sum of {}",
+            count
+        );
+        let min = gpu.reduce_min::<f32>(&float_buffer, count)?;
+        assert_eq!(
+            min.wait(gpu)?,
+            floats.iter().copied().fold(f32::INFINITY, f32::min),
+            "f32 min of {}",
+            count
+        );
+        let max = gpu.reduce_max::<f32>(&float_buffer, count)?;
+        assert_eq!(
+            max.wait(gpu)?,
+            floats.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+            "f32 max of {}",
+            count
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reductions_match_the_cpu() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        // around the 512 elements a workgroup reduces, and past 512 * 512 so it takes three passes
+        for count in [1, 511, 512, 513, 512 * 512 + 1] {
+            check_reductions(&mut gpu, count)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn reducing_a_uniform_buffer_fails() {
+        let Some(mut gpu) = headless() else {
+            return;
+        };
+        let uniform = gpu.uniform_buffer("uniform", 16);
+        let err = gpu
+            .reduce_sum::<u32>(&uniform, 1)
+            .err()
+            .expect("uniform buffers can't be reduced");
+        assert!(err.to_string().contains("only storage buffers"), "{}", err);
+    }
+
+    #[test]
+    fn pipelines_with_freed_resources_are_forgotten() -> Result<()> {
+        let Some(mut gpu) = headless() else {
+            return Ok(());
+        };
+        let source = "@group(0) @binding(0) var<storage, read_write> data: array<u32>;
+            @compute @workgroup_size(1) fn main() { data[0] = 1u; }";
+        let freed = gpu.buffer("freed", BufferSize::Custom(4), 4);
+        gpu.pipeline_from_source("first", source, "main", &[&freed])?;
+        drop(freed);
+        gpu.resource_pool.clean_up_resources();
+        let kept = gpu.buffer("kept", BufferSize::Custom(4), 4);
+        gpu.pipeline_from_source("second", source, "main", &[&kept])?;
+        assert_eq!(gpu.pipeline_bindings.len(), 1);
+        assert_eq!(gpu.pipeline_bindings[0].name, "second:main");
+        gpu.validate()
     }
 }
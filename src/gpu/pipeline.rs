@@ -1,23 +1,59 @@
+use std::num::NonZeroU32;
 use std::time::SystemTime;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use tracing::warn;
 
-use wgpu::{BindGroup, BindGroupLayout, BindGroupLayoutEntry, ComputePipeline, ShaderStages};
+use wgpu::{
+    BindGroup, BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry, ComputePipeline,
+    PushConstantRange, ShaderStages, TextureView,
+};
 
-use crate::{gpu::shader::Shader, hash_handles, ResourceHandle};
+use crate::{div_ceil, gpu::shader::Shader, hash_handles, ResourceHandle, ResourcePool, SourceMap};
 
 use super::CoGr;
 
+// the options of a pipeline made with CoGr::pipeline_builder, kept so hot reloads compile it the same way
+#[derive(Debug, Clone, Default)]
+pub struct PipelineOptions {
+    // wgsl prepended to the shader before the defines, like the struct of the push constants of a Sketch
+    pub prelude: Option<String>,
+    // prepended to the shader as `const name = value;`
+    pub defines: Vec<(String, String)>,
+    // bindings the shader isn't allowed to write to
+    pub read_only: Vec<u32>,
+}
+
 #[derive(Debug)]
 pub struct Pipeline {
     pub pipeline_name: String,
     pub entry_point: String,
     pub source: String,
+    // the code of pipelines created with CoGr::pipeline_from_source, those aren't hot reloaded
+    pub embedded_source: Option<String>,
+    // the files on disk the shader and its includes were read from, hot reload watches these. virtual shaders
+    // registered with CoGr::register_virtual_shader aren't in here
+    pub files: Vec<String>,
     pub last_update: SystemTime,
     pub pipeline: ComputePipeline,
+    pub workgroup_size: [u32; 3],
+    pub push_constant_size: u32,
+    // the names of the bindings in group 0 by binding index, for Encoder::dispatch_named
+    pub binding_names: Vec<Option<String>>,
+    // the bindings in group 0 the shader declares as var<uniform> or as sampled texture, used by CoGr::validate
+    pub uniform_bindings: Vec<u32>,
+    pub sampled_bindings: Vec<u32>,
+    pub options: PipelineOptions,
     pub bind_group_layout: BindGroupLayout,
+    pub bindings_hash: u64,
+    // CoGr::reload_pipelines forces a reload by bumping the generation of the context
+    pub reload_generation: u64,
     pub last_bind_group_hash: u64,
     pub last_bind_group: Option<BindGroup>,
+    // set once a dispatch that doesn't cover its target was reported, so it isn't reported every frame
+    pub coverage_warned: bool,
+    // same for dispatches over the ceiling of CoGr::set_workgroup_ceiling
+    pub ceiling_warned: bool,
 }
 
 impl Pipeline {
@@ -26,43 +62,84 @@ impl Pipeline {
         shader_file: &str,
         entry_point: &str,
         bindings: &[&ResourceHandle],
+        options: &PipelineOptions,
+    ) -> Result<Self> {
+        let mut source = SourceMap::default();
+        if let Some(prelude) = &options.prelude {
+            source.push("prelude", 1, prelude);
+        }
+        if !options.defines.is_empty() {
+            let defines = options
+                .defines
+                .iter()
+                .map(|(name, value)| format!("const {} = {};\n", name, value))
+                .collect::<String>();
+            source.push("defines", 1, &defines);
+        }
+        source.push_file(shader_file, &|file| gpu_context.read_shader(file))?;
+        let shader = Shader::compile_mapped(gpu_context, shader_file, &source)?;
+        let mut pipeline = Self::from_shader(gpu_context, shader, entry_point, bindings, options)?;
+        pipeline.files = gpu_context.files_on_disk(&source);
+        pipeline.last_update = last_modified(&pipeline.files);
+        Ok(pipeline)
+    }
+
+    pub(crate) fn from_source(
+        gpu_context: &CoGr,
+        name: &str,
+        source: &str,
+        entry_point: &str,
+        bindings: &[&ResourceHandle],
     ) -> Result<Self> {
-        let shader = Shader::compile_shader(gpu_context, shader_file)?;
-        let code = std::fs::read_to_string(shader_file)?;
+        let shader = Shader::compile_source(gpu_context, name, source.to_string())?;
+        let options = PipelineOptions::default();
+        let mut pipeline = Self::from_shader(gpu_context, shader, entry_point, bindings, &options)?;
+        pipeline.embedded_source = Some(source.to_string());
+        Ok(pipeline)
+    }
+
+    fn from_shader(
+        gpu_context: &CoGr,
+        shader: Shader,
+        entry_point: &str,
+        bindings: &[&ResourceHandle],
+        options: &PipelineOptions,
+    ) -> Result<Self> {
+        let shader_file = shader.file.as_str();
+        check_bindings(
+            &gpu_context.resource_pool,
+            &shader,
+            &[entry_point],
+            bindings,
+        )?;
+        let uniform_bindings = shader.uniform_bindings();
+        for &binding in &options.read_only {
+            let read_only = shader.is_sampled(binding)
+                || uniform_bindings.contains(&binding)
+                || shader.binding_access(binding) == wgpu::StorageTextureAccess::ReadOnly;
+            if !read_only {
+                bail!(
+                    "binding {} of {} is marked read only, but the shader declares it with write access",
+                    binding,
+                    shader_file
+                );
+            }
+        }
+        let workgroup_size = shader.workgroup_size(entry_point)?;
+        let workgroup_memory_size = shader.workgroup_memory_size(entry_point)?;
+        let max_workgroup_memory_size = gpu_context.limits().max_compute_workgroup_storage_size;
+        if workgroup_memory_size > max_workgroup_memory_size {
+            bail!(
+                "{}:{} uses {} bytes of workgroup memory while the device supports {}",
+                shader_file,
+                entry_point,
+                workgroup_memory_size,
+                max_workgroup_memory_size
+            );
+        }
         println!("compiled shader");
 
-        let bind_group_layout_entries: Vec<BindGroupLayoutEntry> = bindings
-            .iter()
-            .enumerate()
-            .map(|(index, val)| match val {
-                ResourceHandle::Texture(_) => {
-                    let texture = gpu_context.resource_pool.grab_texture(val);
-                    BindGroupLayoutEntry {
-                        visibility: ShaderStages::all(),
-                        ty: wgpu::BindingType::StorageTexture {
-                            access: wgpu::StorageTextureAccess::ReadWrite,
-                            format: texture.format,
-                            view_dimension: texture.view_dims,
-                        },
-                        count: None,
-                        binding: index as u32,
-                    }
-                }
-                ResourceHandle::Buffer(_) => {
-                    let texture = gpu_context.resource_pool.grab_buffer(val);
-                    BindGroupLayoutEntry {
-                        visibility: ShaderStages::all(),
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Storage { read_only: false },
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                        binding: index as u32,
-                    }
-                }
-            })
-            .collect::<Vec<_>>();
+        let bind_group_layout_entries = bind_group_layout_entries(gpu_context, bindings, &shader);
 
         let bind_group_layout =
             gpu_context
@@ -72,13 +149,33 @@ impl Pipeline {
                     entries: bind_group_layout_entries.as_slice(),
                 });
 
+        let push_constant_size = shader.push_constant_size();
+        let push_constant_ranges = match push_constant_size {
+            0 => Vec::new(),
+            size => {
+                if !gpu_context
+                    .device
+                    .features()
+                    .contains(wgpu::Features::PUSH_CONSTANTS)
+                {
+                    bail!(
+                        "{} uses push constants, which this adapter doesn't support",
+                        shader_file
+                    );
+                }
+                vec![PushConstantRange {
+                    stages: ShaderStages::COMPUTE,
+                    range: 0..size,
+                }]
+            }
+        };
         let pipeline_layout =
             gpu_context
                 .device
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                     label: Some(&(shader_file.to_owned() + "_layout")),
                     bind_group_layouts: &[&bind_group_layout],
-                    push_constant_ranges: &[],
+                    push_constant_ranges: &push_constant_ranges,
                 });
 
         let pipeline =
@@ -94,26 +191,376 @@ impl Pipeline {
         Ok(Pipeline {
             pipeline_name: shader_file.to_string(),
             pipeline,
+            workgroup_size,
+            push_constant_size,
+            binding_names: (0..shader.bindings().last().map_or(0, |last| last + 1))
+                .map(|binding| shader.binding_name(binding).map(str::to_string))
+                .collect(),
+            uniform_bindings,
+            sampled_bindings: shader
+                .bindings()
+                .into_iter()
+                .filter(|&binding| shader.is_sampled(binding))
+                .collect(),
+            options: options.clone(),
             source: shader_file.to_string(),
+            embedded_source: None,
+            files: Vec::new(),
             entry_point: entry_point.to_string(),
-            last_update: std::fs::metadata(shader_file).unwrap().modified().unwrap(),
+            last_update: SystemTime::now(),
             bind_group_layout,
-            last_bind_group_hash: hash_handles(bindings),
+            bindings_hash: hash_handles(bindings),
+            reload_generation: gpu_context.reload_generation,
+            last_bind_group_hash: 0,
             last_bind_group: None,
+            coverage_warned: false,
+            ceiling_warned: false,
         })
     }
 
-    pub fn check_hot_reload(&mut self, gpu_context: &CoGr, bindings: &[&ResourceHandle]) {
-        if hash_handles(bindings) != self.last_bind_group_hash
-            || self.last_update < std::fs::metadata(&self.source).unwrap().modified().unwrap()
+    // the number of workgroups needed for one invocation per cell of a volume, rounded up per dimension
+    pub fn work_groups(&self, size: (u32, u32, u32)) -> (u32, u32, u32) {
+        (
+            div_ceil(size.0, self.workgroup_size[0]),
+            div_ceil(size.1, self.workgroup_size[1]),
+            div_ceil(size.2, self.workgroup_size[2]),
+        )
+    }
+
+    // puts resources given with the name of their binding in binding order
+    pub fn named_bindings<'r>(
+        &self,
+        bindings: &[(&str, &'r ResourceHandle)],
+    ) -> Result<Vec<&'r ResourceHandle>> {
+        let indexed = bindings
+            .iter()
+            .map(|&(name, handle)| {
+                match self
+                    .binding_names
+                    .iter()
+                    .position(|binding_name| binding_name.as_deref() == Some(name))
+                {
+                    Some(binding) => Ok((binding as u32, handle)),
+                    None => bail!(
+                        "{} has no binding named {}, it has {}",
+                        self.pipeline_name,
+                        name,
+                        self.binding_names
+                            .iter()
+                            .flatten()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        bindings_by_index(&indexed)
+    }
+
+    pub fn check_hot_reload(&mut self, gpu_context: &mut CoGr, bindings: &[&ResourceHandle]) {
+        let source_changed = self.last_update < last_modified(&self.files);
+        if hash_handles(bindings) != self.bindings_hash
+            || source_changed
+            || self.reload_generation != gpu_context.reload_generation
         {
-            match Pipeline::new(gpu_context, &self.source, &self.entry_point, bindings) {
+            let new_pipe = match &self.embedded_source {
+                Some(source) => Pipeline::from_source(
+                    gpu_context,
+                    &self.source,
+                    source,
+                    &self.entry_point,
+                    bindings,
+                ),
+                None => Pipeline::new(
+                    gpu_context,
+                    &self.source,
+                    &self.entry_point,
+                    bindings,
+                    &self.options,
+                ),
+            };
+            gpu_context.record_pipeline_reload(
+                &self.source,
+                &self.entry_point,
+                new_pipe.as_ref().err(),
+            );
+            match new_pipe {
                 Ok(new_pipe) => *self = new_pipe,
                 Err(err) => {
                     println!("{}", err);
-                    self.last_update = std::fs::metadata(&self.source).unwrap().modified().unwrap();
+                    self.reload_generation = gpu_context.reload_generation;
+                    self.last_update = last_modified(&self.files);
+                }
+            }
+        }
+    }
+}
+
+// the latest modification time of the files, files that can't be read are skipped
+pub(crate) fn last_modified(files: &[String]) -> SystemTime {
+    files
+        .iter()
+        .filter_map(|file| {
+            std::fs::metadata(file)
+                .and_then(|meta| meta.modified())
+                .ok()
+        })
+        .max()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+pub(crate) fn bind_group_layout_entries(
+    gpu_context: &CoGr,
+    bindings: &[&ResourceHandle],
+    shader: &Shader,
+) -> Vec<BindGroupLayoutEntry> {
+    let uniform_bindings = shader.uniform_bindings();
+    bindings
+        .iter()
+        .enumerate()
+        .map(|(index, val)| match val {
+            ResourceHandle::Texture(_) if shader.is_sampled(index as u32) => {
+                // needs a texture created with CoGr::texture_sampled
+                let texture = gpu_context.resource_pool.grab_texture(val);
+                BindGroupLayoutEntry {
+                    visibility: ShaderStages::all(),
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: texture
+                            .format
+                            .sample_type(None)
+                            .unwrap_or(wgpu::TextureSampleType::Float { filterable: true }),
+                        view_dimension: texture.view_dims,
+                        multisampled: false,
+                    },
+                    count: None,
+                    binding: index as u32,
+                }
+            }
+            ResourceHandle::Texture(_) => {
+                let texture = gpu_context.resource_pool.grab_texture(val);
+                BindGroupLayoutEntry {
+                    visibility: ShaderStages::all(),
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: shader.binding_access(index as u32),
+                        format: texture.format,
+                        view_dimension: texture.view_dims,
+                    },
+                    count: None,
+                    binding: index as u32,
+                }
+            }
+            ResourceHandle::Buffer(_) => {
+                // a struct that differs from the shader layout shifts every field after the first element
+                let buffer = gpu_context.resource_pool.grab_buffer(val);
+                if let Some(stride) = shader.binding_element_size(index as u32) {
+                    if stride as usize != buffer.element_size {
+                        warn!(
+                            "{} is bound at binding {} of {} with elements of {} bytes, but the shader reads elements of {} bytes",
+                            buffer.name, index, shader.file, buffer.element_size, stride
+                        );
+                    }
+                }
+                BindGroupLayoutEntry {
+                    visibility: ShaderStages::all(),
+                    ty: wgpu::BindingType::Buffer {
+                        ty: match uniform_bindings.contains(&(index as u32)) {
+                            true => wgpu::BufferBindingType::Uniform,
+                            false => wgpu::BufferBindingType::Storage {
+                                read_only: shader.binding_access(index as u32)
+                                    == wgpu::StorageTextureAccess::ReadOnly,
+                            },
+                        },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                    binding: index as u32,
+                }
+            }
+            ResourceHandle::TextureArray(textures) => {
+                // all textures in an array share format and dimensions, so the first one describes them all
+                let texture = gpu_context.resource_pool.grab_texture(&textures[0]);
+                BindGroupLayoutEntry {
+                    visibility: ShaderStages::all(),
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: shader.binding_access(index as u32),
+                        format: texture.format,
+                        view_dimension: texture.view_dims,
+                    },
+                    count: NonZeroU32::new(textures.len() as u32),
+                    binding: index as u32,
                 }
             }
+        })
+        .collect::<Vec<_>>()
+}
+
+// resources are bound in order, the first one at @group(0) @binding(0), the next at binding 1 and so on. this
+// catches resources that end up at a binding that doesn't exist or is of another kind, and bindings the entry points
+// use without a resource for them
+pub(crate) fn check_bindings(
+    resource_pool: &ResourcePool,
+    shader: &Shader,
+    entry_points: &[&str],
+    bindings: &[&ResourceHandle],
+) -> Result<()> {
+    let expected = || {
+        shader
+            .bindings()
+            .iter()
+            .map(|&binding| {
+                format!(
+                    "\n  binding {}: {}",
+                    binding,
+                    shader.binding_name(binding).unwrap_or("_")
+                )
+            })
+            .collect::<String>()
+    };
+    for (index, handle) in bindings.iter().enumerate() {
+        let binding = index as u32;
+        let Some(var) = shader.global_binding(binding) else {
+            bail!(
+                "{} is resource {}, but {} declares no binding {}. resources are bound in order from binding 0, the shader expects:{}",
+                resource_pool.named(handle),
+                index,
+                shader.file,
+                binding,
+                expected()
+            );
+        };
+        let is_texture = matches!(
+            shader.module.types[var.ty].inner,
+            naga::TypeInner::Image { .. } | naga::TypeInner::BindingArray { .. }
+        );
+        let handle_is_texture = !matches!(handle, ResourceHandle::Buffer(_));
+        if is_texture != handle_is_texture {
+            bail!(
+                "{} is resource {}, but binding {} of {} is {}, which is a {}. the shader expects:{}",
+                resource_pool.named(handle),
+                index,
+                binding,
+                shader.file,
+                shader.binding_name(binding).unwrap_or("_"),
+                if is_texture { "texture" } else { "buffer" },
+                expected()
+            );
+        }
+        if matches!(handle, ResourceHandle::Buffer(_))
+            && !resource_pool
+                .grab_buffer(handle)
+                .usage
+                .contains(wgpu::BufferUsages::STORAGE)
+            && var.space != naga::AddressSpace::Uniform
+        {
+            bail!(
+                "{} is a uniform buffer, but binding {} of {} is {}, which isn't a var<uniform>",
+                resource_pool.named(handle),
+                binding,
+                shader.file,
+                shader.binding_name(binding).unwrap_or("_")
+            );
+        }
+    }
+    for entry_point in entry_points {
+        if let Some(&missing) = shader
+            .used_bindings(entry_point)?
+            .iter()
+            .find(|&&binding| binding as usize >= bindings.len())
+        {
+            bail!(
+                "{}:{} uses binding {} ({}), but only {} resources are given. the shader expects:{}",
+                shader.file,
+                entry_point,
+                missing,
+                shader.binding_name(missing).unwrap_or("_"),
+                bindings.len(),
+                expected()
+            );
         }
     }
+    Ok(())
+}
+
+// puts resources given together with their binding in binding order, for passing them to pipeline and the
+// dispatches without keeping the order in mind. every binding from 0 up to the highest one needs a resource
+pub fn bindings_by_index<'r>(
+    bindings: &[(u32, &'r ResourceHandle)],
+) -> Result<Vec<&'r ResourceHandle>> {
+    let mut ordered: Vec<Option<&ResourceHandle>> = Vec::new();
+    for &(binding, handle) in bindings {
+        let index = binding as usize;
+        if ordered.len() <= index {
+            ordered.resize(index + 1, None);
+        }
+        if ordered[index].replace(handle).is_some() {
+            bail!("binding {} is given more than once", binding);
+        }
+    }
+    ordered
+        .into_iter()
+        .enumerate()
+        .map(|(binding, handle)| {
+            handle.ok_or_else(|| anyhow::anyhow!("binding {} has no resource", binding))
+        })
+        .collect()
+}
+
+// the views of every texture array in the resources, the bind group entries borrow from these
+pub(crate) fn texture_array_views<'a>(
+    resource_pool: &'a ResourcePool,
+    resources: &[&ResourceHandle],
+) -> Vec<Vec<&'a TextureView>> {
+    resources
+        .iter()
+        .filter_map(|val| match val {
+            ResourceHandle::TextureArray(textures) => Some(
+                textures
+                    .iter()
+                    .map(|handle| {
+                        resource_pool
+                            .grab_texture(handle)
+                            .texture_view
+                            .as_ref()
+                            .unwrap()
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(crate) fn bind_group_entries<'a>(
+    resource_pool: &'a ResourcePool,
+    resources: &[&ResourceHandle],
+    texture_array_views: &'a [Vec<&'a TextureView>],
+) -> Vec<BindGroupEntry<'a>> {
+    let mut texture_array_views = texture_array_views.iter();
+    resources
+        .iter()
+        .enumerate()
+        .map(|(i, val)| BindGroupEntry {
+            binding: i as u32,
+            resource: match val {
+                ResourceHandle::Texture(_) => wgpu::BindingResource::TextureView(
+                    resource_pool
+                        .grab_texture(val)
+                        .texture_view
+                        .as_ref()
+                        .unwrap(),
+                ),
+                ResourceHandle::Buffer(_) => resource_pool
+                    .grab_buffer(val)
+                    .buffer
+                    .as_ref()
+                    .unwrap()
+                    .as_entire_binding(),
+                ResourceHandle::TextureArray(_) => {
+                    wgpu::BindingResource::TextureViewArray(texture_array_views.next().unwrap())
+                }
+            },
+        })
+        .collect::<Vec<BindGroupEntry>>()
 }
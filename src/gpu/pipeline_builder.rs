@@ -0,0 +1,88 @@
+use std::fmt::Display;
+
+use anyhow::{bail, Result};
+
+use super::{CoGr, Pipeline, PipelineOptions, ResourceHandle};
+
+// collects the options of a compute pipeline, made with CoGr::pipeline_builder:
+// gpu.pipeline_builder(file).entry("main").bindings(&[&a, &b]).define("SIZE", 64).read_only(&a).build()?
+pub struct PipelineBuilder<'a> {
+    gpu_context: &'a mut CoGr,
+    shader_file: String,
+    entry_point: String,
+    bindings: Vec<ResourceHandle>,
+    prelude: Option<String>,
+    defines: Vec<(String, String)>,
+    read_only: Vec<ResourceHandle>,
+}
+
+impl<'a> PipelineBuilder<'a> {
+    pub(crate) fn new(gpu_context: &'a mut CoGr, shader_file: &str) -> Self {
+        Self {
+            gpu_context,
+            shader_file: shader_file.to_string(),
+            entry_point: "main".to_string(),
+            bindings: Vec::new(),
+            prelude: None,
+            defines: Vec::new(),
+            read_only: Vec::new(),
+        }
+    }
+    // the entry point to run, main by default
+    pub fn entry(mut self, entry_point: &str) -> Self {
+        self.entry_point = entry_point.to_string();
+        self
+    }
+    // the resources bound at binding 0 and up, in order
+    pub fn bindings(mut self, bindings: &[&ResourceHandle]) -> Self {
+        self.bindings = bindings.iter().map(|&handle| handle.clone()).collect();
+        self
+    }
+    // wgsl code put in front of the shader, for declarations every shader of a kind shares
+    pub fn prelude(mut self, code: &str) -> Self {
+        self.prelude = Some(code.to_string());
+        self
+    }
+    // adds `const name = value;` in front of the shader, so the value can be used in array sizes and workgroup sizes
+    pub fn define(mut self, name: &str, value: impl Display) -> Self {
+        self.defines.push((name.to_string(), value.to_string()));
+        self
+    }
+    // fails the build when the shader can write to the resource, for inputs a pass should never modify
+    pub fn read_only(mut self, handle: &ResourceHandle) -> Self {
+        self.read_only.push(handle.clone());
+        self
+    }
+    pub fn build(self) -> Result<Pipeline> {
+        let mut read_only = Vec::new();
+        for handle in &self.read_only {
+            let before = read_only.len();
+            read_only.extend(
+                self.bindings
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, binding)| binding.ptr_eq(handle))
+                    .map(|(index, _)| index as u32),
+            );
+            if read_only.len() == before {
+                bail!(
+                    "{} is marked read only but isn't bound to {}",
+                    self.gpu_context.resource_pool.named(handle),
+                    self.shader_file
+                );
+            }
+        }
+        let options = PipelineOptions {
+            prelude: self.prelude,
+            defines: self.defines,
+            read_only,
+        };
+        let bindings = self.bindings.iter().collect::<Vec<_>>();
+        self.gpu_context.pipeline_with_options(
+            &self.shader_file,
+            &self.entry_point,
+            &bindings,
+            &options,
+        )
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{anyhow, Result};
+
+use super::Pipeline;
+
+// pipelines over the same resources of which one is dispatched per frame, like the render modes of a tracer.
+// every mode keeps its own pipeline and cached bind group, so switching modes doesn't compile or create
+// anything. a uniform driven branch in a single shader avoids the extra pipelines, but every mode then pays
+// for the registers and code size of the most expensive one
+#[derive(Debug)]
+pub struct PipelineModes {
+    modes: Vec<(String, Pipeline)>,
+    selected: usize,
+}
+
+impl PipelineModes {
+    pub(crate) fn new(modes: Vec<(String, Pipeline)>) -> Self {
+        Self { modes, selected: 0 }
+    }
+    pub fn select(&mut self, mode: &str) -> Result<()> {
+        self.selected = self
+            .modes
+            .iter()
+            .position(|(name, _)| name == mode)
+            .ok_or_else(|| anyhow!("there is no mode {}", mode))?;
+        Ok(())
+    }
+    pub fn selected_mode(&self) -> &str {
+        &self.modes[self.selected].0
+    }
+    // the pipeline of the selected mode, to pass to a dispatch
+    pub fn selected(&mut self) -> &mut Pipeline {
+        &mut self.modes[self.selected].1
+    }
+    pub fn modes(&self) -> impl Iterator<Item = &str> {
+        self.modes.iter().map(|(name, _)| name.as_str())
+    }
+    // a combo box to pick the mode from the user ui
+    pub fn draw_selector(&mut self, ui: &mut egui::Ui, label: &str) {
+        let mut selected = self.selected;
+        egui::ComboBox::from_label(label)
+            .selected_text(self.selected_mode())
+            .show_ui(ui, |ui| {
+                for (i, (name, _)) in self.modes.iter().enumerate() {
+                    ui.selectable_value(&mut selected, i, name);
+                }
+            });
+        self.selected = selected;
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::mpsc::channel;
+
+use anyhow::Result;
+
+use crate::CoGr;
+
+// gpu data copied into a staging buffer by an encoder, it can be read once that encoder is submitted
+pub struct PendingRead<T> {
+    staging_buffer: wgpu::Buffer,
+    convert: Box<dyn FnOnce(&[u8]) -> T>,
+}
+
+impl<T> PendingRead<T> {
+    pub(crate) fn new(
+        staging_buffer: wgpu::Buffer,
+        convert: impl FnOnce(&[u8]) -> T + 'static,
+    ) -> Self {
+        Self {
+            staging_buffer,
+            convert: Box::new(convert),
+        }
+    }
+    // blocks until the gpu finished the copy, the encoder that recorded it has to be dropped first
+    pub fn wait(self, gpu_context: &CoGr) -> Result<T> {
+        puffin::profile_function!();
+        let slice = self.staging_buffer.slice(..);
+        let (sender, receiver) = channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        gpu_context.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+        let data = {
+            let bytes = slice.get_mapped_range();
+            (self.convert)(&bytes)
+        };
+        self.staging_buffer.unmap();
+        Ok(data)
+    }
+}
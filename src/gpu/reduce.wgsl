@@ -0,0 +1,50 @@
+// ELEMENT, IDENTITY and COMBINE are replaced with the element type and operation of the reduction
+struct Params {
+    count: u32,
+}
+
+@group(0) @binding(0)
+var<storage, read> input: array<ELEMENT>;
+@group(0) @binding(1)
+var<storage, read_write> output: array<ELEMENT>;
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+var<workgroup> partial: array<ELEMENT, 256>;
+
+fn combine(a: ELEMENT, b: ELEMENT) -> ELEMENT {
+    return COMBINE;
+}
+
+// every workgroup reduces 512 elements to one, the workgroups can be split over y and z for large inputs
+@compute @workgroup_size(256)
+fn main(
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+    @builtin(workgroup_id) group_id: vec3<u32>,
+    @builtin(num_workgroups) num_groups: vec3<u32>,
+) {
+    let group = group_id.x + (group_id.y + group_id.z * num_groups.y) * num_groups.x;
+    // splitting can launch more workgroups than needed, those have nothing to reduce
+    if group * 512u >= params.count {
+        return;
+    }
+    let i = group * 512u + local_id.x;
+    var value = IDENTITY;
+    if i < params.count {
+        value = input[i];
+    }
+    if i + 256u < params.count {
+        value = combine(value, input[i + 256u]);
+    }
+    partial[local_id.x] = value;
+    workgroupBarrier();
+    for (var stride = 128u; stride > 0u; stride >>= 1u) {
+        if local_id.x < stride {
+            partial[local_id.x] = combine(partial[local_id.x], partial[local_id.x + stride]);
+        }
+        workgroupBarrier();
+    }
+    if local_id.x == 0u {
+        output[group] = partial[0];
+    }
+}
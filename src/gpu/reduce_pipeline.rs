@@ -0,0 +1,154 @@
+use anyhow::Result;
+use bytemuck::Pod;
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferUsages, CommandEncoder,
+    ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Device,
+    PipelineLayoutDescriptor, ShaderModuleDescriptor, ShaderSource, ShaderStages,
+};
+
+use crate::{div_ceil, split_work_groups};
+
+// number of elements a single workgroup of reduce.wgsl reduces to one
+pub(crate) const REDUCE_ELEMENTS_PER_GROUP: u32 = 512;
+
+// the number of workgroups, and so of output elements, of a pass over count elements
+pub(crate) fn reduce_groups(count: u32) -> u32 {
+    div_ceil(count, REDUCE_ELEMENTS_PER_GROUP)
+}
+
+// element types the bundled reductions work on
+pub trait ReduceElement: Pod {
+    const WGSL_TYPE: &'static str;
+    const ZERO: &'static str;
+    const LOWEST: &'static str;
+    const HIGHEST: &'static str;
+}
+
+impl ReduceElement for u32 {
+    const WGSL_TYPE: &'static str = "u32";
+    const ZERO: &'static str = "0u";
+    const LOWEST: &'static str = "0u";
+    const HIGHEST: &'static str = "4294967295u";
+}
+
+impl ReduceElement for f32 {
+    const WGSL_TYPE: &'static str = "f32";
+    const ZERO: &'static str = "0.0";
+    const LOWEST: &'static str = "-3.40282347e+38";
+    const HIGHEST: &'static str = "3.40282347e+38";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReduceOp {
+    Sum,
+    Min,
+    Max,
+}
+
+#[derive(Debug)]
+pub(crate) struct ReducePipeline {
+    pub(crate) op: ReduceOp,
+    pub(crate) element_type: &'static str,
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+}
+
+impl ReducePipeline {
+    pub(crate) fn new<T: ReduceElement>(device: &Device, op: ReduceOp) -> Self {
+        let (identity, combine) = match op {
+            ReduceOp::Sum => (T::ZERO, "a + b"),
+            ReduceOp::Min => (T::HIGHEST, "min(a, b)"),
+            ReduceOp::Max => (T::LOWEST, "max(a, b)"),
+        };
+        let source = include_str!("reduce.wgsl")
+            .replace("ELEMENT", T::WGSL_TYPE)
+            .replace("IDENTITY", identity)
+            .replace("COMBINE", combine);
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("reduce.wgsl"),
+            source: ShaderSource::Wgsl(source.into()),
+        });
+
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("reduce_bindgroup_layout"),
+            entries: &[
+                buffer_entry(0, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(1, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(2, BufferBindingType::Uniform),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("reduce_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("reduce.wgsl"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        ReducePipeline {
+            op,
+            element_type: T::WGSL_TYPE,
+            pipeline,
+            bind_group_layout,
+        }
+    }
+    // records one pass reducing count elements of input to count / REDUCE_ELEMENTS_PER_GROUP (rounded up)
+    // elements of output
+    pub(crate) fn record(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        input: &Buffer,
+        output: &Buffer,
+        count: u32,
+        max_work_groups: u32,
+    ) -> Result<()> {
+        let work_groups = split_work_groups(reduce_groups(count), max_work_groups)?;
+        let params = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("reduce_params"),
+            contents: bytemuck::bytes_of(&count),
+            usage: BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("reduce_bindgroup"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: input.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: output.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        });
+        let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("reduce"),
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(work_groups.0, work_groups.1, work_groups.2);
+        Ok(())
+    }
+}
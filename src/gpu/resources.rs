@@ -2,13 +2,19 @@ use std::{
     cell::RefCell,
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
-    ops::SubAssign,
-    rc::Rc,
+    ops::{Deref, SubAssign},
+    rc::{Rc, Weak},
 };
 
-use std::fmt::Debug;
-use tracing::info;
-use wgpu::{TextureFormat, TextureViewDimension};
+use anyhow::{anyhow, Result};
+use std::fmt::{Debug, Display};
+use tracing::{debug, info};
+use wgpu::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::div_ceil;
 
 #[derive(Debug)]
 pub enum TextureRes {
@@ -20,17 +26,19 @@ pub enum TextureRes {
     ThirtySecondRes,
     Custom(u32, u32, u32),
 }
-fn match_resolution(
-    config: &wgpu::SurfaceConfiguration,
-    texture_resolution: &TextureRes,
-) -> (u32, u32, u32) {
+// the screen relative resolutions are relative to the render size, which is the window size times the render scale.
+// fractions are rounded up, so at a width of 1281 a HalfRes texture is 641 wide and every full res pixel has a
+// half res pixel covering it. dispatches over a reduced resolution should round up the same way
+fn match_resolution(render_size: (u32, u32), texture_resolution: &TextureRes) -> (u32, u32, u32) {
+    let (width, height) = render_size;
+    let fraction = |divisor: u32| (div_ceil(width, divisor), div_ceil(height, divisor), 1);
     match texture_resolution {
-        TextureRes::FullRes => (config.width, config.height, 1),
-        TextureRes::HalfRes => (config.width / 2, config.height / 2, 1),
-        TextureRes::QuarterRes => (config.width / 4, config.height / 4, 1),
-        TextureRes::EightRes => (config.width / 8, config.height / 8, 1),
-        TextureRes::SixteenthRes => (config.width / 16, config.height / 16, 1),
-        TextureRes::ThirtySecondRes => (config.width / 32, config.height / 32, 1),
+        TextureRes::FullRes => (width, height, 1),
+        TextureRes::HalfRes => fraction(2),
+        TextureRes::QuarterRes => fraction(4),
+        TextureRes::EightRes => fraction(8),
+        TextureRes::SixteenthRes => fraction(16),
+        TextureRes::ThirtySecondRes => fraction(32),
         TextureRes::Custom(x, y, z) => (*x, *y, *z),
     }
 }
@@ -44,6 +52,8 @@ pub enum BufferSize {
     SixteenthRes,
     ThirtySecondRes,
     Custom(u64),
+    // as many elements as the referenced buffer or texture has, follows it when it gets resized
+    PerElementOf(ResourceHandle),
 }
 
 impl From<u64> for BufferSize {
@@ -63,12 +73,13 @@ impl From<i32> for BufferSize {
 }
 
 fn match_buffer_size(
-    config: &wgpu::SurfaceConfiguration,
+    pool: &ResourcePool,
+    render_size: (u32, u32),
     elements: &BufferSize,
     element_size: usize,
 ) -> u64 {
-    let width = config.width as u64;
-    let height = config.height as u64;
+    let width = render_size.0 as u64;
+    let height = render_size.1 as u64;
     match elements {
         BufferSize::FullRes => width * height * element_size as u64,
         BufferSize::HalfRes => width * height * element_size as u64 / 2,
@@ -77,31 +88,45 @@ fn match_buffer_size(
         BufferSize::SixteenthRes => width * height * element_size as u64 / 16,
         BufferSize::ThirtySecondRes => width * height * element_size as u64 / 32,
         BufferSize::Custom(x) => x * element_size as u64,
+        BufferSize::PerElementOf(handle) => pool.element_count(handle) * element_size as u64,
     }
 }
 
 #[derive(Debug)]
 pub struct Texture {
     pub name: String,
+    pub resolution: TextureRes,
+    pub size: (u32, u32, u32),
     pub format: TextureFormat,
     pub view_dims: TextureViewDimension,
-    pub texture: wgpu::Texture,
-    pub texture_view: wgpu::TextureView,
+    // usages on top of the storage and copy usages every texture has
+    pub usage: TextureUsages,
+    pub clear_color: Option<wgpu::Color>,
+    pub(crate) needs_clear: bool,
+    // bumped every time the wgpu texture is (re)allocated
+    pub generation: u64,
+    pub texture: Option<wgpu::Texture>,
+    pub texture_view: Option<wgpu::TextureView>,
 }
 
 impl Texture {
-    fn new(
-        name: String,
-        view_dims: TextureViewDimension,
-        texture: wgpu::Texture,
-        texture_view: wgpu::TextureView,
-    ) -> Self {
+    fn new(name: String, resolution: TextureRes, format: TextureFormat) -> Self {
+        let view_dims = match resolution {
+            TextureRes::Custom(_, _, z) if z > 1 => TextureViewDimension::D3,
+            _ => TextureViewDimension::D2,
+        };
         Self {
             name,
-            format: texture.format(),
-            view_dims: view_dims,
-            texture,
-            texture_view,
+            resolution,
+            size: (0, 0, 0),
+            format,
+            view_dims,
+            usage: TextureUsages::empty(),
+            clear_color: None,
+            needs_clear: false,
+            generation: 0,
+            texture: None,
+            texture_view: None,
         }
     }
 }
@@ -109,19 +134,88 @@ impl Texture {
 #[derive(Debug)]
 pub struct Buffer {
     pub name: String,
-    pub buffer: wgpu::Buffer,
+    pub elements: BufferSize,
+    pub element_size: usize,
+    pub size: u64,
+    // STORAGE_BUFFER_USAGE, or UNIFORM_BUFFER_USAGE for buffers made with CoGr::uniform_buffer
+    pub usage: wgpu::BufferUsages,
+    // bumped every time the wgpu buffer is (re)allocated
+    pub generation: u64,
+    pub buffer: Option<wgpu::Buffer>,
 }
 
+// buffers can be bound as storage or uniform buffer and used as vertex, index and indirect buffer
+pub(crate) const STORAGE_BUFFER_USAGE: wgpu::BufferUsages = wgpu::BufferUsages::COPY_DST
+    .union(wgpu::BufferUsages::COPY_SRC)
+    .union(wgpu::BufferUsages::UNIFORM)
+    .union(wgpu::BufferUsages::STORAGE)
+    .union(wgpu::BufferUsages::VERTEX)
+    .union(wgpu::BufferUsages::INDEX)
+    .union(wgpu::BufferUsages::INDIRECT);
+// uniform buffers can only be bound as var<uniform>, which the driver can keep in faster constant memory
+pub(crate) const UNIFORM_BUFFER_USAGE: wgpu::BufferUsages = wgpu::BufferUsages::COPY_DST
+    .union(wgpu::BufferUsages::COPY_SRC)
+    .union(wgpu::BufferUsages::UNIFORM);
+
 impl Buffer {
-    pub fn new(name: String, buffer: wgpu::Buffer) -> Self {
-        Self { name, buffer }
+    pub fn new(name: String, elements: BufferSize, element_size: usize) -> Self {
+        Self {
+            name,
+            elements,
+            element_size,
+            size: 0,
+            usage: STORAGE_BUFFER_USAGE,
+            generation: 0,
+            buffer: None,
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum ResourceHandle {
-    Texture(Rc<RefCell<usize>>),
-    Buffer(Rc<RefCell<usize>>),
+    Texture(Rc<PoolSlot>),
+    Buffer(Rc<PoolSlot>),
+    // a group of textures bound to a single binding array, the textures themselves live in the pool
+    TextureArray(Rc<TextureArraySlot>),
+}
+
+// where a resource lives in the pool. the index shifts down when resources before it are cleaned up, the id it got
+// at creation never changes
+#[derive(Debug)]
+pub struct PoolSlot {
+    index: RefCell<usize>,
+    id: u64,
+}
+
+#[derive(Debug)]
+pub struct TextureArraySlot {
+    textures: Vec<ResourceHandle>,
+    id: u64,
+}
+
+impl Deref for TextureArraySlot {
+    type Target = [ResourceHandle];
+
+    fn deref(&self) -> &Self::Target {
+        &self.textures
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum WeakResourceHandle {
+    Texture(Weak<PoolSlot>),
+    Buffer(Weak<PoolSlot>),
+    TextureArray(Weak<TextureArraySlot>),
+}
+
+impl WeakResourceHandle {
+    pub(crate) fn upgrade(&self) -> Option<ResourceHandle> {
+        match self {
+            WeakResourceHandle::Texture(t) => t.upgrade().map(ResourceHandle::Texture),
+            WeakResourceHandle::Buffer(b) => b.upgrade().map(ResourceHandle::Buffer),
+            WeakResourceHandle::TextureArray(a) => a.upgrade().map(ResourceHandle::TextureArray),
+        }
+    }
 }
 
 pub fn hash_handles(handles: &[&ResourceHandle]) -> u64 {
@@ -135,47 +229,114 @@ pub fn hash_handles(handles: &[&ResourceHandle]) -> u64 {
 impl ResourceHandle {
     pub fn get_index(&self) -> usize {
         match self {
-            ResourceHandle::Texture(t) => *t.borrow(),
-            ResourceHandle::Buffer(b) => *b.borrow(),
+            ResourceHandle::Texture(t) => *t.index.borrow(),
+            ResourceHandle::Buffer(b) => *b.index.borrow(),
+            ResourceHandle::TextureArray(_) => {
+                panic!("texture arrays aren't stored in the resource pool, only their textures are")
+            }
+        }
+    }
+    // unique for every resource the pool creates and stable for its whole life, so it can key user side maps.
+    // ids aren't reused after a resource is cleaned up
+    pub fn id(&self) -> u64 {
+        match self {
+            ResourceHandle::Texture(t) => t.id,
+            ResourceHandle::Buffer(b) => b.id,
+            ResourceHandle::TextureArray(a) => a.id,
         }
     }
-    pub fn new_t(index: usize) -> Self {
-        ResourceHandle::Texture(Rc::new(RefCell::new(index)))
+    pub fn new_t(index: usize, id: u64) -> Self {
+        ResourceHandle::Texture(Rc::new(PoolSlot {
+            index: RefCell::new(index),
+            id,
+        }))
     }
-    pub fn new_b(index: usize) -> Self {
-        ResourceHandle::Buffer(Rc::new(RefCell::new(index)))
+    pub fn new_b(index: usize, id: u64) -> Self {
+        ResourceHandle::Buffer(Rc::new(PoolSlot {
+            index: RefCell::new(index),
+            id,
+        }))
     }
+    // weak handles don't keep a resource alive, so they aren't counted
     pub fn reference_count(&self) -> usize {
         match self {
-            ResourceHandle::Texture(t) => Rc::strong_count(t) + Rc::weak_count(t),
-            ResourceHandle::Buffer(b) => Rc::strong_count(b) + Rc::weak_count(b),
+            ResourceHandle::Texture(t) => Rc::strong_count(t),
+            ResourceHandle::Buffer(b) => Rc::strong_count(b),
+            ResourceHandle::TextureArray(a) => Rc::strong_count(a),
+        }
+    }
+    pub(crate) fn downgrade(&self) -> WeakResourceHandle {
+        match self {
+            ResourceHandle::Texture(t) => WeakResourceHandle::Texture(Rc::downgrade(t)),
+            ResourceHandle::Buffer(b) => WeakResourceHandle::Buffer(Rc::downgrade(b)),
+            ResourceHandle::TextureArray(a) => WeakResourceHandle::TextureArray(Rc::downgrade(a)),
         }
     }
     pub fn decrement(&mut self) {
         match self {
-            ResourceHandle::Texture(t) => t.borrow_mut().sub_assign(1),
-            ResourceHandle::Buffer(b) => b.borrow_mut().sub_assign(1),
+            ResourceHandle::Texture(t) => t.index.borrow_mut().sub_assign(1),
+            ResourceHandle::Buffer(b) => b.index.borrow_mut().sub_assign(1),
+            // not indexed into the pool, its textures get decremented by the pool itself
+            ResourceHandle::TextureArray(_) => (),
         };
     }
     pub fn ptr_eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (ResourceHandle::Texture(h1), ResourceHandle::Texture(h2)) => Rc::ptr_eq(h1, h2),
-            (ResourceHandle::Texture(h1), ResourceHandle::Buffer(h2)) => Rc::ptr_eq(h1, h2),
-            (ResourceHandle::Buffer(h1), ResourceHandle::Texture(h2)) => Rc::ptr_eq(h1, h2),
-            (ResourceHandle::Buffer(h1), ResourceHandle::Buffer(h2)) => Rc::ptr_eq(h1, h2),
+        self.as_ptr() == other.as_ptr()
+    }
+    // the textures of a texture array, or the texture itself
+    pub fn textures(&self) -> Vec<ResourceHandle> {
+        match self {
+            ResourceHandle::Texture(_) => vec![self.clone()],
+            ResourceHandle::Buffer(_) => Vec::new(),
+            ResourceHandle::TextureArray(a) => a.to_vec(),
+        }
+    }
+    fn as_ptr(&self) -> *const () {
+        match self {
+            ResourceHandle::Texture(t) => Rc::as_ptr(t) as *const (),
+            ResourceHandle::Buffer(b) => Rc::as_ptr(b) as *const (),
+            ResourceHandle::TextureArray(a) => Rc::as_ptr(a) as *const (),
         }
     }
 }
 
 impl Hash for ResourceHandle {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        match self {
-            ResourceHandle::Texture(t) => t.as_ptr().hash(state),
-            ResourceHandle::Buffer(b) => b.as_ptr().hash(state),
+        self.as_ptr().hash(state)
+    }
+}
+
+pub struct NamedHandle<'a> {
+    pool: &'a ResourcePool,
+    handle: &'a ResourceHandle,
+}
+
+impl Debug for NamedHandle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.handle {
+            ResourceHandle::Texture(_) => {
+                write!(f, "Texture({:?})", self.pool.grab_texture(self.handle).name)
+            }
+            ResourceHandle::Buffer(_) => {
+                write!(f, "Buffer({:?})", self.pool.grab_buffer(self.handle).name)
+            }
+            ResourceHandle::TextureArray(a) => {
+                let names = a
+                    .iter()
+                    .map(|handle| &self.pool.grab_texture(handle).name)
+                    .collect::<Vec<_>>();
+                write!(f, "TextureArray({:?})", names)
+            }
         }
     }
 }
 
+impl Display for NamedHandle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct ResourcePool {
     pub(crate) recreate_resources: bool,
@@ -183,61 +344,213 @@ pub struct ResourcePool {
     pub(crate) textures: Vec<Texture>,
     pub(crate) buffer_handles: Vec<ResourceHandle>,
     pub(crate) texture_handles: Vec<ResourceHandle>,
+    // extra references to pinned resources, which keep them from being cleaned up
+    pinned: Vec<ResourceHandle>,
+    // id of the next created resource
+    next_id: u64,
 }
 
 impl ResourcePool {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
     pub fn grab_texture(&self, handle: &ResourceHandle) -> &Texture {
         &self.textures[handle.get_index()]
     }
+    pub fn generation(&self, handle: &ResourceHandle) -> u64 {
+        match handle {
+            ResourceHandle::Texture(_) => self.grab_texture(handle).generation,
+            ResourceHandle::Buffer(_) => self.grab_buffer(handle).generation,
+            ResourceHandle::TextureArray(a) => a.iter().map(|t| self.generation(t)).sum(),
+        }
+    }
+    // a reallocated resource keeps its handle, so the generations are part of the hash
+    pub fn hash_bind_group(&self, handles: &[&ResourceHandle]) -> u64 {
+        let mut hasher = DefaultHasher::default();
+        for handle in handles {
+            handle.hash(&mut hasher);
+            self.generation(handle).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
     pub fn grab_buffer(&self, handle: &ResourceHandle) -> &Buffer {
         &self.buffers[handle.get_index()]
     }
+    // wraps a handle so it's printed with the name of the resource it points to, like Buffer("triangles")
+    pub fn named<'a>(&'a self, handle: &'a ResourceHandle) -> NamedHandle<'a> {
+        NamedHandle { pool: self, handle }
+    }
+    // number of elements in a buffer, or the number of texels in a texture
+    pub fn element_count(&self, handle: &ResourceHandle) -> u64 {
+        match handle {
+            ResourceHandle::Texture(_) => {
+                let (x, y, z) = self.grab_texture(handle).size;
+                x as u64 * y as u64 * z as u64
+            }
+            ResourceHandle::Buffer(_) => {
+                let buffer = self.grab_buffer(handle);
+                buffer.size / buffer.element_size as u64
+            }
+            ResourceHandle::TextureArray(a) => a.iter().map(|t| self.element_count(t)).sum(),
+        }
+    }
+
+    // groups textures so they can be bound as one binding array, they need the same format and dimensions
+    pub(crate) fn texture_array(&mut self, textures: &[&ResourceHandle]) -> Result<ResourceHandle> {
+        let first = match textures.first() {
+            Some(first @ ResourceHandle::Texture(_)) => self.grab_texture(first),
+            Some(_) => Err(anyhow!("texture arrays can only contain textures"))?,
+            None => Err(anyhow!("texture arrays need at least one texture"))?,
+        };
+        for handle in textures {
+            if !matches!(handle, ResourceHandle::Texture(_)) {
+                Err(anyhow!("texture arrays can only contain textures"))?
+            }
+            let texture = self.grab_texture(handle);
+            if texture.format != first.format || texture.view_dims != first.view_dims {
+                Err(anyhow!(
+                    "texture {} is {:?} {:?} while the texture array is {:?} {:?}",
+                    texture.name,
+                    texture.format,
+                    texture.view_dims,
+                    first.format,
+                    first.view_dims
+                ))?
+            }
+        }
+        Ok(ResourceHandle::TextureArray(Rc::new(TextureArraySlot {
+            textures: textures.iter().map(|handle| (*handle).clone()).collect(),
+            id: self.next_id(),
+        })))
+    }
 
     pub(crate) fn texture(
         &mut self,
         name: String,
-        view_dims: TextureViewDimension,
-        texture: wgpu::Texture,
-        texture_view: wgpu::TextureView,
+        resolution: TextureRes,
+        format: TextureFormat,
     ) -> ResourceHandle {
         puffin::profile_function!();
         info!(
-            "creating texture {} with {:?} and view {:?}",
-            name, texture, texture_view
+            name = %name,
+            resolution = ?resolution,
+            format = ?format,
+            "creating texture"
         );
-        let texture = Texture::new(name, view_dims, texture, texture_view);
-        let handle = ResourceHandle::new_t(self.textures.len());
+        let texture = Texture::new(name, resolution, format);
+        let handle = ResourceHandle::new_t(self.textures.len(), self.next_id());
         self.textures.push(texture);
         self.texture_handles.push(handle.clone());
         handle
     }
 
-    pub(crate) fn buffer(&mut self, name: String, buffer: wgpu::Buffer) -> ResourceHandle {
+    // the texture is cleared to the given color every time it gets (re)allocated
+    pub(crate) fn texture_cleared(
+        &mut self,
+        name: String,
+        resolution: TextureRes,
+        format: TextureFormat,
+        color: wgpu::Color,
+    ) -> ResourceHandle {
+        let handle = self.texture(name, resolution, format);
+        let texture = &mut self.textures[handle.get_index()];
+        texture.clear_color = Some(color);
+        texture.usage |= TextureUsages::RENDER_ATTACHMENT;
+        handle
+    }
+
+    pub(crate) fn texture_render_target(
+        &mut self,
+        name: String,
+        resolution: TextureRes,
+        format: TextureFormat,
+    ) -> ResourceHandle {
+        let handle = self.texture(name, resolution, format);
+        self.textures[handle.get_index()].usage |= TextureUsages::RENDER_ATTACHMENT;
+        handle
+    }
+
+    pub(crate) fn texture_sampled(
+        &mut self,
+        name: String,
+        resolution: TextureRes,
+        format: TextureFormat,
+    ) -> ResourceHandle {
+        let handle = self.texture(name, resolution, format);
+        self.textures[handle.get_index()].usage |= TextureUsages::TEXTURE_BINDING;
+        handle
+    }
+
+    pub(crate) fn buffer(
+        &mut self,
+        name: String,
+        elements: BufferSize,
+        element_size: usize,
+    ) -> ResourceHandle {
         puffin::profile_function!();
-        info!("creating buffer {} with {:?}", name, buffer);
-        let buffer = Buffer::new(name, buffer);
-        let handle = ResourceHandle::new_b(self.buffers.len());
+        // element_count and the sizes of PerElementOf buffers divide by it
+        assert!(
+            element_size != 0,
+            "buffer {} has elements of 0 bytes, they need at least one",
+            name
+        );
+        info!(
+            name = %name,
+            elements = ?elements,
+            element_size,
+            "creating buffer"
+        );
+        let buffer = Buffer::new(name, elements, element_size);
+        let handle = ResourceHandle::new_b(self.buffers.len(), self.next_id());
         self.buffers.push(buffer);
         self.buffer_handles.push(handle.clone());
         handle
     }
 
+    pub(crate) fn uniform_buffer(&mut self, name: String, size: usize) -> ResourceHandle {
+        let handle = self.buffer(name, BufferSize::Custom(1), size);
+        self.buffers[handle.get_index()].usage = UNIFORM_BUFFER_USAGE;
+        handle
+    }
+
+    pub(crate) fn pin(&mut self, handle: &ResourceHandle) {
+        if !self.is_pinned(handle) {
+            self.pinned.push(handle.clone());
+        }
+    }
+    pub(crate) fn unpin(&mut self, handle: &ResourceHandle) {
+        self.pinned.retain(|pinned| !pinned.ptr_eq(handle));
+    }
+    pub(crate) fn is_pinned(&self, handle: &ResourceHandle) -> bool {
+        self.pinned.iter().any(|pinned| pinned.ptr_eq(handle))
+    }
+
     pub(crate) fn clean_up_resources(&mut self) {
         puffin::profile_function!();
-        info!("{:?}", self.buffer_handles);
+        debug!(
+            buffers = ?self
+                .buffer_handles
+                .iter()
+                .map(|handle| self.named(handle))
+                .collect::<Vec<_>>(),
+            "live buffers"
+        );
         // remove all resources which are only referenced by resource pool
         let mut i = 0;
         while i < self.buffer_handles.len() {
             let handle = &self.buffer_handles[i];
             if handle.reference_count() == 1 {
                 info!(
-                    "removing buffer at index {}, {} buffer(s) left",
-                    i,
-                    self.buffers.len() - 1
+                    resource = %self.named(handle),
+                    index = i,
+                    remaining = self.buffers.len() - 1,
+                    "freeing buffer"
                 );
                 self.buffers.remove(i);
                 self.buffer_handles.remove(i);
-                self.buffer_handles.iter_mut().for_each(|handle| {
+                // only the resources after the removed one move down
+                self.buffer_handles[i..].iter_mut().for_each(|handle| {
                     handle.decrement();
                 });
                 continue;
@@ -249,37 +562,104 @@ impl ResourcePool {
             let handle = &self.texture_handles[i];
             if handle.reference_count() == 1 {
                 info!(
-                    "removing texture at index {}, {} texture(s) left",
-                    i,
-                    self.textures.len() - 1
+                    resource = %self.named(handle),
+                    index = i,
+                    remaining = self.textures.len() - 1,
+                    "freeing texture"
                 );
                 self.textures.remove(i);
                 self.texture_handles.remove(i);
-                self.texture_handles.iter_mut().for_each(|handle| {
+                // only the resources after the removed one move down
+                self.texture_handles[i..].iter_mut().for_each(|handle| {
                     handle.decrement();
                 });
                 continue;
             }
             i += 1;
         }
-        info!("{:?}", self.buffer_handles);
+        debug!(
+            buffers = ?self
+                .buffer_handles
+                .iter()
+                .map(|handle| self.named(handle))
+                .collect::<Vec<_>>(),
+            "live buffers"
+        );
+    }
+
+    // records a clear for every texture with a clear color which got allocated since the last call
+    pub(crate) fn clear_textures(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        puffin::profile_function!();
+        for texture in self
+            .textures
+            .iter_mut()
+            .filter(|texture| texture.needs_clear)
+        {
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("clear texture"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: texture.texture_view.as_ref().unwrap(),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(texture.clear_color.unwrap()),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            texture.needs_clear = false;
+        }
     }
 
+    // (re)allocates every resource which has not been allocated yet or whose size has changed
     pub(crate) fn prepare_resources(
         &mut self,
         device: &wgpu::Device,
-        config: &wgpu::SurfaceConfiguration,
-    ) {
+        render_size: (u32, u32),
+    ) -> Result<()> {
         puffin::profile_function!();
         self.clean_up_resources();
+
+        for texture in self.textures.iter_mut() {
+            let size = match_resolution(render_size, &texture.resolution);
+            if texture.texture.is_none() || texture.size != size {
+                info!(name = %texture.name, size = ?size, "allocating texture");
+                let (wgpu_texture, texture_view) =
+                    init_texture(device, &texture.name, size, texture.format, texture.usage)?;
+                texture.size = size;
+                texture.needs_clear = texture.clear_color.is_some();
+                texture.texture = Some(wgpu_texture);
+                texture.texture_view = Some(texture_view);
+                texture.generation += 1;
+            }
+        }
+        // buffers can be sized after other resources, those are always created before the buffers depending on them
+        for i in 0..self.buffers.len() {
+            let buffer = &self.buffers[i];
+            let mut size =
+                match_buffer_size(self, render_size, &buffer.elements, buffer.element_size);
+            if !buffer.usage.contains(wgpu::BufferUsages::STORAGE) {
+                // wgsl rounds the size of uniform structs up to 16 bytes
+                size = wgpu::util::align_to(size, 16);
+            }
+            let buffer = &mut self.buffers[i];
+            if buffer.buffer.is_none() || buffer.size != size {
+                info!(name = %buffer.name, bytes = size, "allocating buffer");
+                buffer.size = size;
+                buffer.buffer = Some(init_buffer(device, &buffer.name, size, buffer.usage));
+                buffer.generation += 1;
+            }
+        }
+        Ok(())
     }
 }
-/*
+
 pub(crate) fn init_texture(
     device: &wgpu::Device,
     texture_name: &str,
     dims: (u32, u32, u32),
     format: wgpu::TextureFormat,
+    usage: TextureUsages,
 ) -> Result<(wgpu::Texture, wgpu::TextureView)> {
     if dims.0 == 0 || dims.1 == 0 || dims.2 == 0 {
         Err(anyhow!(
@@ -313,7 +693,7 @@ pub(crate) fn init_texture(
         usage: TextureUsages::STORAGE_BINDING
             | TextureUsages::COPY_DST
             | TextureUsages::COPY_SRC
-            | TextureUsages::TEXTURE_BINDING,
+            | usage,
         view_formats: &[format],
     });
 
@@ -329,7 +709,7 @@ pub(crate) fn init_texture(
     });
     Ok((texture, texture_view))
 }
-
+/*
 pub(crate) fn init_texture_with_data(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
@@ -388,20 +768,116 @@ pub(crate) fn init_texture_with_data(
         array_layer_count: None,
     });
     Ok((texture, texture_view))
-}
+}*/
 
-pub(crate) fn init_storage_buffer(
+pub(crate) fn init_buffer(
     device: &wgpu::Device,
     buffer_name: &str,
     size: u64,
+    usage: wgpu::BufferUsages,
 ) -> wgpu::Buffer {
     device.create_buffer(&wgpu::BufferDescriptor {
         label: Some(buffer_name),
         size,
-        usage: wgpu::BufferUsages::COPY_DST
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::UNIFORM
-            | wgpu::BufferUsages::STORAGE,
+        usage,
         mapped_at_creation: false,
     })
-}*/
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_resolution_rounds_up() {
+        let expected = [
+            (TextureRes::FullRes, (1281, 721, 1)),
+            (TextureRes::HalfRes, (641, 361, 1)),
+            (TextureRes::QuarterRes, (321, 181, 1)),
+            (TextureRes::EightRes, (161, 91, 1)),
+            (TextureRes::SixteenthRes, (81, 46, 1)),
+            (TextureRes::ThirtySecondRes, (41, 23, 1)),
+            (TextureRes::Custom(3, 5, 7), (3, 5, 7)),
+        ];
+        for (resolution, size) in expected {
+            assert_eq!(
+                match_resolution((1281, 721), &resolution),
+                size,
+                "{:?}",
+                resolution
+            );
+        }
+    }
+
+    #[test]
+    fn match_resolution_never_hits_zero() {
+        for resolution in [
+            TextureRes::FullRes,
+            TextureRes::HalfRes,
+            TextureRes::QuarterRes,
+            TextureRes::EightRes,
+            TextureRes::SixteenthRes,
+            TextureRes::ThirtySecondRes,
+        ] {
+            assert_eq!(
+                match_resolution((1, 1), &resolution),
+                (1, 1, 1),
+                "{:?}",
+                resolution
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "elements of 0 bytes")]
+    fn buffers_with_empty_elements_are_rejected() {
+        ResourcePool::default().buffer("empty".to_string(), BufferSize::Custom(1), 0);
+    }
+
+    #[test]
+    fn clean_up_keeps_indices_of_surviving_resources() {
+        let mut pool = ResourcePool::default();
+        let texture = |pool: &mut ResourcePool, name: &str| {
+            pool.texture(
+                name.to_string(),
+                TextureRes::Custom(4, 4, 1),
+                TextureFormat::Rgba8Unorm,
+            )
+        };
+        // buffers and textures interleaved, so a mixup between the two index spaces shows up
+        let buffer_0 = pool.buffer("buffer_0".to_string(), BufferSize::Custom(1), 4);
+        let texture_0 = texture(&mut pool, "texture_0");
+        let buffer_1 = pool.buffer("buffer_1".to_string(), BufferSize::Custom(1), 4);
+        let texture_1 = texture(&mut pool, "texture_1");
+        let buffer_2 = pool.buffer("buffer_2".to_string(), BufferSize::Custom(1), 4);
+        let texture_2 = texture(&mut pool, "texture_2");
+        let texture_3 = texture(&mut pool, "texture_3");
+
+        drop(texture_1);
+        drop(buffer_1);
+        pool.clean_up_resources();
+
+        assert_eq!(pool.textures.len(), 3);
+        assert_eq!(pool.buffers.len(), 2);
+        for (handle, index, name) in [
+            (&texture_0, 0, "texture_0"),
+            (&texture_2, 1, "texture_2"),
+            (&texture_3, 2, "texture_3"),
+        ] {
+            assert_eq!(handle.get_index(), index);
+            assert_eq!(pool.grab_texture(handle).name, name);
+        }
+        for (handle, index, name) in [(&buffer_0, 0, "buffer_0"), (&buffer_2, 1, "buffer_2")] {
+            assert_eq!(handle.get_index(), index);
+            assert_eq!(pool.grab_buffer(handle).name, name);
+        }
+
+        // freeing the first one shifts everything after it
+        drop(texture_0);
+        pool.clean_up_resources();
+        assert_eq!(texture_2.get_index(), 0);
+        assert_eq!(texture_3.get_index(), 1);
+        assert_eq!(pool.grab_texture(&texture_3).name, "texture_3");
+        assert_eq!(buffer_2.get_index(), 1);
+    }
+}
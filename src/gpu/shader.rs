@@ -1,31 +1,291 @@
 use std::borrow::Cow;
+use std::ops::Deref;
 
-use anyhow::Result;
-use wgpu::{ShaderModule, ShaderModuleDescriptor};
+use anyhow::{anyhow, Result};
+use wgpu::{ShaderModule, ShaderModuleDescriptor, StorageTextureAccess};
 
-use crate::CoGr;
+use crate::{CoGr, SourceMap};
 
 pub struct Shader {
-    pub file: String,
     pub shader: String,
     pub shader_module: ShaderModule,
+    pub reflection: ReflectionInfo,
+}
+
+// everything known about a shader without a device, what it declares and how it is laid out
+pub struct ReflectionInfo {
+    pub file: String,
+    pub module: naga::Module,
+    // what every function and entry point uses, from validation
+    pub info: naga::valid::ModuleInfo,
+}
+
+impl Deref for Shader {
+    type Target = ReflectionInfo;
+
+    fn deref(&self) -> &Self::Target {
+        &self.reflection
+    }
 }
 
 impl Shader {
     pub fn compile_shader(gpu_context: &CoGr, shader_file: &str) -> Result<Shader> {
-        let code = std::fs::read_to_string(shader_file)?;
+        let source = gpu_context.load_shader(shader_file)?;
+        Self::compile_mapped(gpu_context, shader_file, &source)
+    }
+    // compiles wgsl code that doesn't come from a file, the name is used for labels and errors
+    pub fn compile_source(gpu_context: &CoGr, name: &str, code: String) -> Result<Shader> {
+        Self::compile_mapped(gpu_context, name, &SourceMap::from_file(name, &code))
+    }
+    // compiles code assembled from several parts, errors point at the part and line they come from
+    pub fn compile_mapped(gpu_context: &CoGr, name: &str, source: &SourceMap) -> Result<Shader> {
+        let code = source.code().to_string();
+        let reflection = ReflectionInfo::parse(name, source)?;
 
         let shader_module = gpu_context
             .device
             .create_shader_module(ShaderModuleDescriptor {
-                label: Some(shader_file),
+                label: Some(name),
                 source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&code)),
             });
 
         Ok(Shader {
-            file: shader_file.to_string(),
             shader: code,
             shader_module,
+            reflection,
         })
     }
 }
+
+impl ReflectionInfo {
+    // parses and validates a wgsl file, this doesn't need a device so it also works in tests and linters
+    pub fn from_file(shader_file: &str) -> Result<Self> {
+        let mut source = SourceMap::default();
+        source.push_file(shader_file, &|file| Ok(std::fs::read_to_string(file)?))?;
+        Self::parse(shader_file, &source)
+    }
+    pub(crate) fn parse(name: &str, source: &SourceMap) -> Result<Self> {
+        let module = naga::front::wgsl::parse_str(source.code())
+            .map_err(|err| anyhow!(source.format_parse_error(&err)))?;
+        // validating here gives errors with source lines, wgpu would only report them when creating the pipeline
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .map_err(|err| anyhow!(source.format_validation_error(&err)))?;
+        Ok(Self {
+            file: name.to_string(),
+            module,
+            info,
+        })
+    }
+    // names of all entry points in the shader
+    pub fn entry_points(&self) -> Vec<&str> {
+        self.module
+            .entry_points
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+    // sorted binding indices declared in group 0, a pipeline needs a resource for each of them
+    pub fn bindings(&self) -> Vec<u32> {
+        let mut bindings = self
+            .module
+            .global_variables
+            .iter()
+            .filter_map(|(_, var)| var.binding.as_ref())
+            .filter(|binding| binding.group == 0)
+            .map(|binding| binding.binding)
+            .collect::<Vec<_>>();
+        bindings.sort();
+        bindings.dedup();
+        bindings
+    }
+    // sorted binding indices in group 0 that an entry point reads or writes, these need a resource
+    pub fn used_bindings(&self, entry_point: &str) -> Result<Vec<u32>> {
+        let index = self
+            .module
+            .entry_points
+            .iter()
+            .position(|entry| entry.name == entry_point)
+            .ok_or_else(|| anyhow!("{} has no entry point {}", self.file, entry_point))?;
+        let function_info = self.info.get_entry_point(index);
+        let mut bindings = self
+            .module
+            .global_variables
+            .iter()
+            .filter(|(handle, _)| !function_info[*handle].is_empty())
+            .filter_map(|(_, var)| var.binding.as_ref())
+            .filter(|binding| binding.group == 0)
+            .map(|binding| binding.binding)
+            .collect::<Vec<_>>();
+        bindings.sort();
+        bindings.dedup();
+        Ok(bindings)
+    }
+    // the name a binding in group 0 is declared with
+    pub fn binding_name(&self, binding: u32) -> Option<&str> {
+        self.global_binding(binding)?.name.as_deref()
+    }
+    // reads the @workgroup_size of a compute entry point from the shader source
+    pub fn workgroup_size(&self, entry_point: &str) -> Result<[u32; 3]> {
+        self.module
+            .entry_points
+            .iter()
+            .find(|entry| entry.name == entry_point)
+            .map(|entry| entry.workgroup_size)
+            .ok_or_else(|| anyhow!("{} has no entry point {}", self.file, entry_point))
+    }
+    // bytes of var<workgroup> memory a compute entry point uses, counting only the variables it or the functions
+    // it calls touch, each placed at its wgsl alignment
+    pub fn workgroup_memory_size(&self, entry_point: &str) -> Result<u32> {
+        let index = self
+            .module
+            .entry_points
+            .iter()
+            .position(|entry| entry.name == entry_point)
+            .ok_or_else(|| anyhow!("{} has no entry point {}", self.file, entry_point))?;
+        let function_info = self.info.get_entry_point(index);
+        let mut layouter = naga::proc::Layouter::default();
+        layouter.update(self.module.to_ctx())?;
+        Ok(self
+            .module
+            .global_variables
+            .iter()
+            .filter(|(handle, var)| {
+                var.space == naga::AddressSpace::WorkGroup && !function_info[*handle].is_empty()
+            })
+            .fold(0, |offset, (_, var)| {
+                let layout = &layouter[var.ty];
+                layout.alignment.round_up(offset) + layout.size
+            }))
+    }
+    // size in bytes of the var<push_constant> in the shader, 0 if there is none
+    pub fn push_constant_size(&self) -> u32 {
+        self.module
+            .global_variables
+            .iter()
+            .filter(|(_, var)| var.space == naga::AddressSpace::PushConstant)
+            .map(|(_, var)| self.module.types[var.ty].inner.size(self.module.to_ctx()))
+            .max()
+            .unwrap_or(0)
+    }
+    // how a binding in group 0 is accessed according to its declaration, wgpu requires the layout to match it
+    // exactly. for buffers ReadOnly means var<storage, read>. bindings that aren't found are ReadWrite
+    pub fn binding_access(&self, binding: u32) -> StorageTextureAccess {
+        let access = match self.global_binding(binding) {
+            Some(var) => match (var.space, &self.module.types[var.ty].inner) {
+                (naga::AddressSpace::Storage { access }, _) => access,
+                (
+                    _,
+                    naga::TypeInner::Image {
+                        class: naga::ImageClass::Storage { access, .. },
+                        ..
+                    },
+                ) => *access,
+                (_, naga::TypeInner::BindingArray { base, .. }) => {
+                    match &self.module.types[*base].inner {
+                        naga::TypeInner::Image {
+                            class: naga::ImageClass::Storage { access, .. },
+                            ..
+                        } => *access,
+                        _ => naga::StorageAccess::all(),
+                    }
+                }
+                _ => naga::StorageAccess::all(),
+            },
+            None => naga::StorageAccess::all(),
+        };
+        match (
+            access.contains(naga::StorageAccess::LOAD),
+            access.contains(naga::StorageAccess::STORE),
+        ) {
+            (true, false) => StorageTextureAccess::ReadOnly,
+            (false, true) => StorageTextureAccess::WriteOnly,
+            _ => StorageTextureAccess::ReadWrite,
+        }
+    }
+    // whether a binding in group 0 is declared as sampled texture like texture_2d<f32>, instead of a storage texture
+    pub fn is_sampled(&self, binding: u32) -> bool {
+        self.global_binding(binding).map_or(false, |var| {
+            matches!(
+                self.module.types[var.ty].inner,
+                naga::TypeInner::Image {
+                    class: naga::ImageClass::Sampled { .. },
+                    ..
+                }
+            )
+        })
+    }
+    pub(crate) fn global_binding(&self, binding: u32) -> Option<&naga::GlobalVariable> {
+        self.module
+            .global_variables
+            .iter()
+            .map(|(_, var)| var)
+            .find(|var| {
+                var.binding
+                    .as_ref()
+                    .map_or(false, |b| b.group == 0 && b.binding == binding)
+            })
+    }
+    // bytes of one element of a buffer binding in group 0 as the shader lays it out. this is the stride for arrays
+    // and structs ending in a runtime sized array, the size of the whole type otherwise
+    pub fn binding_element_size(&self, binding: u32) -> Option<u32> {
+        let var = self.global_binding(binding)?;
+        let inner = &self.module.types[var.ty].inner;
+        Some(match inner {
+            naga::TypeInner::Array { stride, .. } => *stride,
+            naga::TypeInner::Struct { members, .. } => match members
+                .last()
+                .map(|member| &self.module.types[member.ty].inner)
+            {
+                Some(naga::TypeInner::Array {
+                    stride,
+                    size: naga::ArraySize::Dynamic,
+                    ..
+                }) => *stride,
+                _ => inner.size(self.module.to_ctx()),
+            },
+            _ => inner.size(self.module.to_ctx()),
+        })
+    }
+    // bindings in group 0 declared as var<uniform>, all other buffers are bound as storage buffers
+    pub fn uniform_bindings(&self) -> Vec<u32> {
+        self.module
+            .global_variables
+            .iter()
+            .filter(|(_, var)| var.space == naga::AddressSpace::Uniform)
+            .filter_map(|(_, var)| var.binding.as_ref())
+            .filter(|binding| binding.group == 0)
+            .map(|binding| binding.binding)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reflect(code: &str) -> ReflectionInfo {
+        let mut source = SourceMap::default();
+        source.push("test.wgsl", 1, code);
+        ReflectionInfo::parse("test.wgsl", &source).unwrap()
+    }
+
+    #[test]
+    fn workgroup_memory_counts_what_the_entry_point_uses() {
+        let info = reflect(
+            "var<workgroup> a: f32;
+            var<workgroup> b: vec4<f32>;
+            var<workgroup> c: array<u32, 64>;
+            fn helper() { c[0] = 1u; }
+            @compute @workgroup_size(1) fn first() { a = 1.0; b = vec4<f32>(a); }
+            @compute @workgroup_size(1) fn second() { helper(); }",
+        );
+        // a at 0, b aligned to 16
+        assert_eq!(info.workgroup_memory_size("first").unwrap(), 32);
+        assert_eq!(info.workgroup_memory_size("second").unwrap(), 256);
+        assert!(info.workgroup_memory_size("third").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::SystemTime;
+
+use egui::Color32;
+
+use crate::PipelineInfo;
+
+// edits the source of one loaded shader, saving writes the file so hot reload picks it up
+#[derive(Debug, Default)]
+pub(crate) struct ShaderEditor {
+    shader_files: Vec<String>,
+    selected: Option<String>,
+    source: String,
+    error: Option<String>,
+}
+
+impl ShaderEditor {
+    pub(crate) fn add_shader_file(&mut self, shader_file: &str) {
+        if !self.shader_files.iter().any(|file| file == shader_file) {
+            self.shader_files.push(shader_file.to_string());
+        }
+    }
+
+    // returns whether reloading all pipelines was requested
+    pub(crate) fn draw(&mut self, egui_ctx: &egui::Context, pipelines: &[PipelineInfo]) -> bool {
+        puffin::profile_function!();
+        let mut reload_all = false;
+        egui::Window::new("shader_editor").show(egui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                let mut selected = None;
+                egui::ComboBox::from_id_source("shader_editor_file")
+                    .selected_text(self.selected.as_deref().unwrap_or("select a shader"))
+                    .show_ui(ui, |ui| {
+                        for file in &self.shader_files {
+                            let is_selected = self.selected.as_ref() == Some(file);
+                            if ui.selectable_label(is_selected, file).clicked() {
+                                selected = Some(file.clone());
+                            }
+                        }
+                    });
+                if let Some(file) = selected {
+                    self.select(file);
+                }
+                if ui
+                    .add_enabled(self.selected.is_some(), egui::Button::new("save & reload"))
+                    .clicked()
+                {
+                    self.save();
+                }
+                reload_all = ui.button("reload all").clicked();
+            });
+            egui::CollapsingHeader::new("pipelines").show(ui, |ui| {
+                for pipeline in pipelines {
+                    let seconds = SystemTime::now()
+                        .duration_since(pipeline.last_reload)
+                        .unwrap_or_default()
+                        .as_secs();
+                    ui.label(format!("{} reloaded {}s ago", pipeline.name, seconds));
+                    if let Some(error) = &pipeline.last_error {
+                        ui.colored_label(Color32::RED, error);
+                    }
+                }
+            });
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+            // the pipelines recompile with their includes and prelude on their next dispatch after saving, so
+            // their errors are the ones that count
+            for pipeline in pipelines
+                .iter()
+                .filter(|pipeline| self.selected.as_ref() == Some(&pipeline.source))
+            {
+                if let Some(error) = &pipeline.last_error {
+                    ui.colored_label(Color32::RED, error);
+                }
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.source)
+                        .code_editor()
+                        .desired_width(f32::INFINITY),
+                );
+            });
+        });
+        reload_all
+    }
+
+    fn select(&mut self, shader_file: String) {
+        match std::fs::read_to_string(&shader_file) {
+            Ok(source) => {
+                self.source = source;
+                self.error = None;
+            }
+            Err(err) => {
+                self.source.clear();
+                self.error = Some(format!("can't read {}: {}", shader_file, err));
+            }
+        }
+        self.selected = Some(shader_file);
+    }
+
+    fn save(&mut self) {
+        if let Some(shader_file) = &self.selected {
+            self.error = std::fs::write(shader_file, &self.source)
+                .err()
+                .map(|err| format!("can't write {}: {}", shader_file, err));
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{bail, Result};
+
+// the contents of a buffer or texture at one point in time, taken with CoGr::snapshot
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub name: String,
+    // CoGr::frame when the snapshot was taken
+    pub frame: u64,
+    // bytes per buffer element or texture texel
+    pub element_size: usize,
+    // texture size in texels, buffers are (elements, 1, 1)
+    pub size: (u32, u32, u32),
+    pub data: Vec<u8>,
+}
+
+impl Snapshot {
+    // number of elements that differ between the snapshots, both have to be of the same resource layout
+    pub fn diff(&self, other: &Snapshot) -> Result<usize> {
+        Ok(self.changed_elements(other)?.len())
+    }
+    // indices of the elements that differ, for textures index = x + (y + z * height) * width
+    pub fn changed_elements(&self, other: &Snapshot) -> Result<Vec<usize>> {
+        if self.element_size != other.element_size || self.data.len() != other.data.len() {
+            bail!(
+                "can't diff {} of {} bytes in elements of {} with {} of {} bytes in elements of {}",
+                self.name,
+                self.data.len(),
+                self.element_size,
+                other.name,
+                other.data.len(),
+                other.element_size
+            );
+        }
+        Ok(self
+            .data
+            .chunks_exact(self.element_size)
+            .zip(other.data.chunks_exact(other.element_size))
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(index, _)| index)
+            .collect())
+    }
+    // smallest box containing all changed elements as (min, max) inclusive, None when nothing changed. checks
+    // whether a pass only touched the region it should have
+    pub fn changed_bounds(
+        &self,
+        other: &Snapshot,
+    ) -> Result<Option<((u32, u32, u32), (u32, u32, u32))>> {
+        let (width, height, _) = self.size;
+        Ok(self
+            .changed_elements(other)?
+            .into_iter()
+            .map(|index| {
+                let index = index as u32;
+                (
+                    index % width,
+                    index / width % height,
+                    index / (width * height),
+                )
+            })
+            .fold(None, |bounds, (x, y, z)| match bounds {
+                None => Some(((x, y, z), (x, y, z))),
+                Some((min, max)) => Some((
+                    (min.0.min(x), min.1.min(y), min.2.min(z)),
+                    (max.0.max(x), max.1.max(y), max.2.max(z)),
+                )),
+            }))
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+// the path of an include relative to the including file, without . and .. so a file included from different
+// directories has the same path every time
+fn include_path(file: &str, include: &str) -> String {
+    let joined = Path::new(file)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(include);
+    let mut path = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(path.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                path.pop();
+            }
+            component => path.push(component),
+        }
+    }
+    path.to_string_lossy().into_owned()
+}
+
+// the code of a shader assembled from parts of several files, with where every part came from. errors in the
+// combined code are reported at the line of the file the user wrote
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    code: String,
+    parts: Vec<SourcePart>,
+}
+
+#[derive(Debug, Clone)]
+struct SourcePart {
+    file: String,
+    // byte range of the part in the combined code
+    range: Range<usize>,
+    // line in the file the part starts at, 1 based
+    first_line: usize,
+}
+
+impl SourceMap {
+    // a map of a single file
+    pub fn from_file(file: &str, code: &str) -> Self {
+        let mut map = Self::default();
+        map.push(file, 1, code);
+        map
+    }
+    // appends code that starts at first_line of file, every part ends up on its own lines
+    pub fn push(&mut self, file: &str, first_line: usize, code: &str) {
+        let start = self.code.len();
+        self.code.push_str(code);
+        if !code.ends_with('\n') {
+            self.code.push('\n');
+        }
+        self.parts.push(SourcePart {
+            file: file.to_string(),
+            range: start..self.code.len(),
+            first_line,
+        });
+    }
+    // appends a file with every `#include "path"` line replaced by the code of that file, paths are relative to the
+    // including file. every file is included once, later includes of the same file are left out. read_file gets the
+    // code of a file, so shaders can come from memory as well as from disk
+    pub fn push_file(
+        &mut self,
+        file: &str,
+        read_file: &dyn Fn(&str) -> Result<String>,
+    ) -> Result<()> {
+        self.push_included(file, read_file, &mut Vec::new(), &mut HashSet::new())
+    }
+    fn push_included(
+        &mut self,
+        file: &str,
+        read_file: &dyn Fn(&str) -> Result<String>,
+        including: &mut Vec<String>,
+        included: &mut HashSet<String>,
+    ) -> Result<()> {
+        if including.iter().any(|parent| parent == file) {
+            bail!(
+                "{} includes itself through {}",
+                file,
+                including.join(" -> ")
+            );
+        }
+        if !included.insert(file.to_string()) {
+            return Ok(());
+        }
+        let code = read_file(file).with_context(|| format!("can't read shader {}", file))?;
+        including.push(file.to_string());
+        let mut part = String::new();
+        let mut first_line = 1;
+        for (index, line) in code.lines().enumerate() {
+            let Some(include) = line
+                .trim()
+                .strip_prefix("#include")
+                .map(|path| path.trim().trim_matches('"'))
+            else {
+                part.push_str(line);
+                part.push('\n');
+                continue;
+            };
+            if !part.is_empty() {
+                self.push(file, first_line, &part);
+                part.clear();
+            }
+            self.push_included(&include_path(file, include), read_file, including, included)?;
+            first_line = index + 2;
+        }
+        if !part.is_empty() {
+            self.push(file, first_line, &part);
+        }
+        including.pop();
+        Ok(())
+    }
+    // every file with code in the map, without duplicates
+    pub fn files(&self) -> Vec<&str> {
+        let mut files = Vec::<&str>::new();
+        for part in &self.parts {
+            if !files.contains(&part.file.as_str()) {
+                files.push(&part.file);
+            }
+        }
+        files
+    }
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+    // file, line and column of a byte offset in the combined code, lines and columns are 1 based
+    pub fn locate(&self, offset: usize) -> Option<(&str, usize, usize)> {
+        let part = self
+            .parts
+            .iter()
+            .find(|part| part.range.contains(&offset))?;
+        let before = &self.code[part.range.start..offset];
+        let line = part.first_line + before.matches('\n').count();
+        let column = before.len() - before.rfind('\n').map_or(0, |newline| newline + 1) + 1;
+        Some((&part.file, line, column))
+    }
+    pub(crate) fn format_parse_error(&self, err: &naga::front::wgsl::ParseError) -> String {
+        self.format_error(err.message(), err.labels())
+    }
+    pub(crate) fn format_validation_error(
+        &self,
+        err: &naga::WithSpan<naga::valid::ValidationError>,
+    ) -> String {
+        self.format_error(
+            &err.to_string(),
+            err.spans().map(|(span, label)| (*span, label.as_str())),
+        )
+    }
+    // the message followed by every labeled span with the line it points at, like
+    //   --> shader.wgsl:12:5
+    //    |
+    // 12 |     let x = y;
+    //    |             ^ unknown identifier
+    fn format_error<'a>(
+        &self,
+        message: &str,
+        labels: impl Iterator<Item = (naga::Span, &'a str)>,
+    ) -> String {
+        let mut out = format!("error: {}\n", message);
+        for (span, label) in labels {
+            let Some(range) = span.to_range() else {
+                continue;
+            };
+            let Some((file, line, column)) = self.locate(range.start) else {
+                continue;
+            };
+            let line_start = self.code[..range.start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = self.code[range.start..]
+                .find('\n')
+                .map_or(self.code.len(), |i| range.start + i);
+            let text = &self.code[line_start..line_end];
+            let marker_len = range.end.min(line_end).saturating_sub(range.start).max(1);
+            let gutter = " ".repeat(line.to_string().len());
+            let _ = writeln!(out, "{}--> {}:{}:{}", gutter, file, line, column);
+            let _ = writeln!(out, "{} |", gutter);
+            let _ = writeln!(out, "{} | {}", line, text);
+            let _ = writeln!(
+                out,
+                "{} | {}{} {}",
+                gutter,
+                " ".repeat(column - 1),
+                "^".repeat(marker_len),
+                label
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn assemble(files: &[(&str, &str)], file: &str) -> Result<SourceMap> {
+        let files = files
+            .iter()
+            .map(|(name, code)| (name.to_string(), code.to_string()))
+            .collect::<HashMap<_, _>>();
+        let mut map = SourceMap::default();
+        map.push_file(file, &|name: &str| {
+            files.get(name).cloned().context("no such file")
+        })?;
+        Ok(map)
+    }
+
+    #[test]
+    fn diamond_includes_are_included_once() -> Result<()> {
+        let map = assemble(
+            &[
+                (
+                    "main.wgsl",
+                    "#include \"a/a.wgsl\"\n#include \"b.wgsl\"\nmain\n",
+                ),
+                ("a/a.wgsl", "#include \"../common.wgsl\"\na\n"),
+                ("b.wgsl", "#include \"./common.wgsl\"\nb\n"),
+                ("common.wgsl", "common\n"),
+            ],
+            "main.wgsl",
+        )?;
+        assert_eq!(map.code(), "common\na\nb\nmain\n");
+        assert_eq!(
+            map.locate(map.code().find("main").unwrap()),
+            Some(("main.wgsl", 3, 1))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn include_cycles_are_rejected() {
+        let result = assemble(
+            &[
+                ("a.wgsl", "#include \"b.wgsl\"\n"),
+                ("b.wgsl", "#include \"a.wgsl\"\n"),
+            ],
+            "a.wgsl",
+        );
+        assert!(result.is_err());
+    }
+}
@@ -1,21 +1,77 @@
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) vert_pos: vec3<f32>,
+    @location(0) uv: vec2<f32>,
 }
 
+// STORAGE_FORMAT is replaced with the format of the texture that gets drawn
+@group(0) @binding(0)
+var to_screen_texture: texture_storage_2d<STORAGE_FORMAT, read>;
+// only bound when drawing with to_screen_auto_exposure
+@group(0) @binding(1)
+var<storage, read> exposure: f32;
+// only bound when drawing with to_screen_with_lut, either the 3d lut or the strip
+@group(0) @binding(2)
+var lut_3d: texture_3d<f32>;
+@group(0) @binding(3)
+var lut_strip: texture_2d<f32>;
+@group(0) @binding(4)
+var lut_sampler: sampler;
+
 @vertex
 fn vs_main(
     @builtin(vertex_index) in_vertex_index: u32,
 ) -> VertexOutput {
+    // one triangle covering the whole screen
     var out: VertexOutput;
-    let x = f32(1 - i32(in_vertex_index)) * 0.5;
-    let y = f32(i32(in_vertex_index & 1u) * 2 - 1) * 0.5;
-    out.clip_position = vec4<f32>(x, y, 0.0, 1.0);
-    out.vert_pos = out.clip_position.xyz;
+    let uv = vec2<f32>(f32((in_vertex_index << 1u) & 2u), f32(in_vertex_index & 2u));
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
     return out;
 }
 
+// texel reads outside of the texture are clamped to the closest edge texel
+fn load_clamped(pos: vec2<i32>) -> vec4<f32> {
+    let dims = vec2<i32>(textureDimensions(to_screen_texture));
+    return textureLoad(to_screen_texture, clamp(pos, vec2<i32>(0), dims - vec2<i32>(1)));
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(0.3, 0.2, 0.1, 1.0);
-}
\ No newline at end of file
+    let dims = vec2<f32>(textureDimensions(to_screen_texture));
+    return load_clamped(vec2<i32>(floor(in.uv * dims)));
+}
+
+// scales the texture by the auto exposure and compresses it to 0..1 with the reinhard operator
+@fragment
+fn fs_tonemapped(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dims = vec2<f32>(textureDimensions(to_screen_texture));
+    let color = load_clamped(vec2<i32>(floor(in.uv * dims))).rgb * exposure;
+    return vec4<f32>(color / (vec3<f32>(1.0) + color), 1.0);
+}
+
+// the lut maps a color in 0..1 to its graded color, samples are moved to the texel centers so the edges of the lut
+// are exactly the colors 0 and 1
+@fragment
+fn fs_lut_3d(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dims = vec2<f32>(textureDimensions(to_screen_texture));
+    let color = clamp(load_clamped(vec2<i32>(floor(in.uv * dims))).rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    let size = f32(textureDimensions(lut_3d).x);
+    let uvw = color * ((size - 1.0) / size) + 0.5 / size;
+    return vec4<f32>(textureSample(lut_3d, lut_sampler, uvw).rgb, 1.0);
+}
+
+// a strip of size * size by size texels, blue picks the slice and red and green the texel in it. the two closest
+// slices are blended since the sampler only filters within one
+@fragment
+fn fs_lut_strip(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dims = vec2<f32>(textureDimensions(to_screen_texture));
+    let color = clamp(load_clamped(vec2<i32>(floor(in.uv * dims))).rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    let size = f32(textureDimensions(lut_strip).y);
+    let slice = color.b * (size - 1.0);
+    let slice_low = floor(slice);
+    let slice_high = min(slice_low + 1.0, size - 1.0);
+    let texel = color.rg * (size - 1.0) + 0.5;
+    let low = textureSample(lut_strip, lut_sampler, vec2<f32>((slice_low * size + texel.x) / (size * size), texel.y / size));
+    let high = textureSample(lut_strip, lut_sampler, vec2<f32>((slice_high * size + texel.x) / (size * size), texel.y / size));
+    return vec4<f32>(mix(low.rgb, high.rgb, slice - slice_low), 1.0);
+}
@@ -1,11 +1,12 @@
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
-    BindGroupLayoutEntry, BindingResource, BindingType, BlendState, Buffer, BufferUsages,
-    ColorTargetState, ColorWrites, Device, FragmentState, FrontFace, MultisampleState,
-    PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipeline,
-    RenderPipelineDescriptor, ShaderStages, StorageTextureAccess, TextureFormat, TextureView,
-    TextureViewDimension, VertexState,
+    BindGroupLayoutEntry, BindingResource, BindingType, BlendState, Buffer, BufferBindingType,
+    BufferUsages, ColorTargetState, ColorWrites, Device, FilterMode, FragmentState, FrontFace,
+    MultisampleState, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology,
+    RenderPipeline, RenderPipelineDescriptor, SamplerBindingType, SamplerDescriptor,
+    ShaderModuleDescriptor, ShaderSource, ShaderStages, StorageTextureAccess, TextureFormat,
+    TextureSampleType, TextureView, TextureViewDimension, VertexState,
 };
 
 #[derive(Debug)]
@@ -21,8 +22,12 @@ impl ToScreenPipeline {
         device: &Device,
         screen_texture: &TextureView,
         texture_format: TextureFormat,
+        exposure: Option<&Buffer>,
+        lut: Option<(&TextureView, TextureViewDimension)>,
+        target_format: TextureFormat,
     ) -> Self {
         // init primitives
+        // a single triangle covers the screen, see to_screen.wgsl
         let indices = vec![0, 1, 2];
 
         let indices: &[u16] = indices.as_slice();
@@ -35,32 +40,94 @@ impl ToScreenPipeline {
         let num_indices = indices.len() as u32;
 
         // init bind group
-        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("texture_bind_group_layout_to_screen"),
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
+        // the exposure is only bound when tonemapping with auto exposure
+        let mut layout_entries = vec![BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::StorageTexture {
+                access: StorageTextureAccess::ReadOnly,
+                view_dimension: TextureViewDimension::D2,
+                format: texture_format,
+            },
+            count: None,
+        }];
+        let mut entries = vec![BindGroupEntry {
+            binding: 0,
+            resource: BindingResource::TextureView(screen_texture),
+        }];
+        if let Some(exposure) = exposure {
+            layout_entries.push(BindGroupLayoutEntry {
+                binding: 1,
                 visibility: ShaderStages::FRAGMENT,
-                ty: BindingType::StorageTexture {
-                    access: StorageTextureAccess::ReadOnly,
-                    view_dimension: TextureViewDimension::D2,
-                    format: texture_format,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
                 },
                 count: None,
-            }],
+            });
+            entries.push(BindGroupEntry {
+                binding: 1,
+                resource: exposure.as_entire_binding(),
+            });
+        }
+        // a color grading lut is either a 3d texture or a strip of its blue slices next to each other, the 3d one is
+        // bound at 2 and the strip at 3 so both can be declared in the shader
+        let lut_sampler = lut.map(|_| {
+            device.create_sampler(&SamplerDescriptor {
+                label: Some("lut_sampler"),
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                ..Default::default()
+            })
+        });
+        if let (Some((lut_view, view_dimension)), Some(lut_sampler)) = (lut, &lut_sampler) {
+            let binding = match view_dimension {
+                TextureViewDimension::D3 => 2,
+                _ => 3,
+            };
+            layout_entries.push(BindGroupLayoutEntry {
+                binding,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension,
+                    multisampled: false,
+                },
+                count: None,
+            });
+            layout_entries.push(BindGroupLayoutEntry {
+                binding: 4,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            });
+            entries.push(BindGroupEntry {
+                binding,
+                resource: BindingResource::TextureView(lut_view),
+            });
+            entries.push(BindGroupEntry {
+                binding: 4,
+                resource: BindingResource::Sampler(lut_sampler),
+            });
+        }
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("texture_bind_group_layout_to_screen"),
+            entries: &layout_entries,
         });
         let bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: Some("bind_group_to_screen"),
             layout: &bind_group_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: BindingResource::TextureView(screen_texture),
-            }],
+            entries: &entries,
         });
 
-        // init compute pass
-        let f_shader = device.create_shader_module(wgpu::include_wgsl!("to_screen.wgsl"));
-
-        let v_shader = device.create_shader_module(wgpu::include_wgsl!("to_screen.wgsl"));
+        // the to screen shader is embedded at compile time, so users of the crate don't need it on disk
+        let source = include_str!("to_screen.wgsl")
+            .replace("STORAGE_FORMAT", wgsl_storage_format(texture_format));
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("to_screen.wgsl"),
+            source: ShaderSource::Wgsl(source.into()),
+        });
 
         let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
@@ -72,17 +139,22 @@ impl ToScreenPipeline {
             label: Some("Render Pipeline"),
             layout: Some(&render_pipeline_layout),
             vertex: VertexState {
-                module: &v_shader,
+                module: &shader,
                 entry_point: "vs_main", // 1.
                 buffers: &[],           // 2.
             },
             fragment: Some(FragmentState {
                 // 3.
-                module: &f_shader,
-                entry_point: "fs_main",
+                module: &shader,
+                entry_point: match (exposure, lut) {
+                    (Some(_), _) => "fs_tonemapped",
+                    (None, Some((_, TextureViewDimension::D3))) => "fs_lut_3d",
+                    (None, Some(_)) => "fs_lut_strip",
+                    (None, None) => "fs_main",
+                },
                 targets: &[Some(ColorTargetState {
                     // 4.
-                    format: TextureFormat::Bgra8UnormSrgb,
+                    format: target_format,
                     blend: Some(BlendState::REPLACE),
                     write_mask: ColorWrites::ALL,
                 })],
@@ -116,3 +188,15 @@ impl ToScreenPipeline {
         }
     }
 }
+
+pub(crate) fn wgsl_storage_format(format: TextureFormat) -> &'static str {
+    match format {
+        TextureFormat::Rgba8Unorm => "rgba8unorm",
+        TextureFormat::Rgba8Snorm => "rgba8snorm",
+        TextureFormat::Rgba16Float => "rgba16float",
+        TextureFormat::Rgba32Float => "rgba32float",
+        TextureFormat::R32Float => "r32float",
+        TextureFormat::Rg32Float => "rg32float",
+        _ => panic!("{:?} can't be drawn to the screen", format),
+    }
+}
@@ -1,12 +1,18 @@
+#[cfg(feature = "audio")]
+pub mod audio;
+mod background;
+pub mod camera;
 mod gpu;
 mod window;
 pub use anyhow;
+pub use background::*;
 pub use bytemuck;
 pub use egui;
 pub use glam;
 pub use gpu::*;
 pub use puffin;
 pub use tracing;
-pub use wgpu::TextureFormat;
+pub use wgpu::{PrimitiveTopology, TextureFormat, VertexFormat};
 pub use window::*;
-pub use winit::event::{VirtualKeyCode, MouseButton};
\ No newline at end of file
+pub use winit::event::{VirtualKeyCode, MouseButton};
+pub use winit::monitor::VideoMode;
\ No newline at end of file
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use super::Input;
+
+// a physical key or mouse button an action can be bound to
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+
+impl From<VirtualKeyCode> for InputBinding {
+    fn from(key: VirtualKeyCode) -> Self {
+        InputBinding::Key(key)
+    }
+}
+
+impl From<MouseButton> for InputBinding {
+    fn from(button: MouseButton) -> Self {
+        InputBinding::Mouse(button)
+    }
+}
+
+// maps the actions of a game to keys and mouse buttons, so game logic asks for actions and the controls can be
+// rebound at runtime. an action can have several bindings and is pressed when any of them is. it serializes, so
+// the bindings can be saved next to the other settings of a game
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputMap<Action> {
+    bindings: Vec<(Action, InputBinding)>,
+}
+
+impl<Action> Default for InputMap<Action> {
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+}
+
+impl<Action: Copy + PartialEq> InputMap<Action> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // adds a binding, for building a map like InputMap::new().with(Jump, VirtualKeyCode::Space)
+    pub fn with(mut self, action: Action, binding: impl Into<InputBinding>) -> Self {
+        self.bind(action, binding);
+        self
+    }
+    pub fn bind(&mut self, action: Action, binding: impl Into<InputBinding>) {
+        let binding = binding.into();
+        if !self.bindings.contains(&(action, binding)) {
+            self.bindings.push((action, binding));
+        }
+    }
+    // replaces all bindings of the action with a single one
+    pub fn rebind(&mut self, action: Action, binding: impl Into<InputBinding>) {
+        self.unbind(action);
+        self.bind(action, binding);
+    }
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.retain(|(bound, _)| *bound != action);
+    }
+    pub fn bindings(&self, action: Action) -> impl Iterator<Item = InputBinding> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(bound, _)| *bound == action)
+            .map(|(_, binding)| *binding)
+    }
+    pub fn pressed(&self, input: &Input, action: Action) -> bool {
+        self.bindings(action).any(|binding| match binding {
+            InputBinding::Key(key) => input.key_pressed(key),
+            InputBinding::Mouse(button) => input.mouse_pressed(button).into(),
+        })
+    }
+    // 1 when only the positive action is pressed, -1 for only the negative one and 0 otherwise
+    pub fn axis(&self, input: &Input, positive: Action, negative: Action) -> f32 {
+        self.pressed(input, positive) as i32 as f32 - self.pressed(input, negative) as i32 as f32
+    }
+}
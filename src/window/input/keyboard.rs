@@ -1,11 +1,13 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use winit::event::VirtualKeyCode;
+use winit::event::{ModifiersState, VirtualKeyCode};
 
-#[derive(Default)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct KeyboardState {
     going_down: HashSet<VirtualKeyCode>,
     down: HashSet<VirtualKeyCode>,
     released: HashSet<VirtualKeyCode>,
+    modifiers: ModifiersState,
 }
 
 impl KeyboardState {
@@ -14,6 +16,7 @@ impl KeyboardState {
             going_down: HashSet::new(),
             down: HashSet::new(),
             released: HashSet::new(),
+            modifiers: ModifiersState::empty(),
         }
     }
     pub fn update(&mut self) {
@@ -21,7 +24,10 @@ impl KeyboardState {
         self.released.clear();
     }
     pub fn pressed(&mut self, key: VirtualKeyCode) {
-        self.going_down.insert(key);
+        // key repeats shouldn't make a held key count as just pressed again
+        if !self.down.contains(&key) {
+            self.going_down.insert(key);
+        }
     }
     pub fn released(&mut self, key: VirtualKeyCode) {
         self.down.remove(&key);
@@ -33,6 +39,12 @@ impl KeyboardState {
     pub fn down(&self, key: VirtualKeyCode) -> bool {
         self.going_down.contains(&key) || self.down.contains(&key)
     }
+    pub fn modifiers_changed(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
     pub fn any_down(&self) -> bool {
         !self.down.is_empty() || !self.going_down.is_empty()
     }
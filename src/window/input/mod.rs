@@ -1,21 +1,29 @@
 mod button;
+mod input_map;
 mod keyboard;
 mod mouse;
+mod recording;
 
 pub use button::*;
+pub use input_map::*;
 pub use keyboard::*;
 pub use mouse::*;
+pub use recording::*;
 
 use crate::window::input::button::ButtonState;
 use crate::window::input::keyboard::KeyboardState;
 use crate::window::input::mouse::MouseState;
+use serde::{Deserialize, Serialize};
 use winit::dpi::PhysicalPosition;
-use winit::event::{ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode};
+use winit::event::{
+    ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode,
+};
 use winit::event_loop::ControlFlow;
 
-#[derive(Default)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Input {
     pub sensitivity_modifier: f32,
+    pub invert_y: bool,
     pub mouse_state: MouseState,
     pub cursor_in_screen: bool,
     pub keyboard_state: KeyboardState,
@@ -25,22 +33,51 @@ impl Input {
     pub fn new() -> Input {
         Input {
             sensitivity_modifier: 0.8,
+            invert_y: false,
             mouse_state: MouseState::new(),
             keyboard_state: KeyboardState::new(),
             cursor_in_screen: true,
         }
     }
+    // scales all mouse deltas, 0.8 by default
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity_modifier = sensitivity;
+    }
+    // flips the vertical mouse deltas, the cursor location itself isn't affected
+    pub fn set_invert_y(&mut self, invert_y: bool) {
+        self.invert_y = invert_y;
+    }
+    // multiplier applied to a [x, y] mouse delta
+    fn delta_scale(&self) -> [f32; 2] {
+        match self.invert_y {
+            true => [self.sensitivity_modifier, -self.sensitivity_modifier],
+            false => [self.sensitivity_modifier, self.sensitivity_modifier],
+        }
+    }
     pub fn update(&mut self) {
         self.keyboard_state.update();
+        self.mouse_state.update();
         self.mouse_state.mouse_delta = [0.0, 0.0];
+        self.mouse_state.raw_mouse_delta = [0.0, 0.0];
         self.mouse_state.scroll_delta = 0.0;
     }
     pub fn update_cursor_moved(&mut self, pos: &PhysicalPosition<f32>) {
-        self.mouse_state.mouse_delta = [
-            (pos.x - self.mouse_state.mouse_location[0]) * self.sensitivity_modifier,
-            (pos.y - self.mouse_state.mouse_location[1]) * self.sensitivity_modifier,
+        // several events can arrive in one frame, the delta is summed so none of the movement is lost
+        let scale = self.delta_scale();
+        let delta = [
+            pos.x - self.mouse_state.mouse_location[0],
+            pos.y - self.mouse_state.mouse_location[1],
         ];
+        self.mouse_state.mouse_delta[0] += delta[0] * scale[0];
+        self.mouse_state.mouse_delta[1] += delta[1] * scale[1];
         self.mouse_state.mouse_location = [pos.x, pos.y];
+        // the drag distance is in pixels, sensitivity and invert y only apply to the deltas
+        self.mouse_state.moved(delta);
+    }
+    pub fn update_mouse_motion(&mut self, delta: &(f64, f64)) {
+        let scale = self.delta_scale();
+        self.mouse_state.raw_mouse_delta[0] += delta.0 as f32 * scale[0];
+        self.mouse_state.raw_mouse_delta[1] += delta.1 as f32 * scale[1];
     }
     pub fn update_cursor_entered(&mut self) {
         self.cursor_in_screen = true;
@@ -87,6 +124,9 @@ impl Input {
             *control_flow = ControlFlow::Exit
         }
     }
+    pub fn update_modifiers(&mut self, modifiers: &ModifiersState) {
+        self.keyboard_state.modifiers_changed(*modifiers);
+    }
     pub fn mouse_pressed(&self, button: MouseButton) -> ButtonState {
         if button == MouseButton::Left {
             return self.mouse_state.get_left_button();
@@ -95,15 +135,50 @@ impl Input {
         }
         ButtonState::Up
     }
+    pub fn double_clicked(&self, button: MouseButton) -> bool {
+        match button {
+            MouseButton::Left => self.mouse_state.left_double_clicked(),
+            MouseButton::Right => self.mouse_state.right_double_clicked(),
+            _ => false,
+        }
+    }
+    pub fn dragging(&self) -> bool {
+        self.mouse_state.dragging()
+    }
     pub fn key_pressed(&self, key: VirtualKeyCode) -> bool {
         self.keyboard_state.down(key)
     }
+    // whether any key or mouse button bound to the action is down
+    pub fn action_pressed<Action: Copy + PartialEq>(
+        &self,
+        map: &InputMap<Action>,
+        action: Action,
+    ) -> bool {
+        map.pressed(self, action)
+    }
+    pub fn shift_held(&self) -> bool {
+        self.keyboard_state.modifiers().shift()
+    }
+    pub fn ctrl_held(&self) -> bool {
+        self.keyboard_state.modifiers().ctrl()
+    }
+    pub fn alt_held(&self) -> bool {
+        self.keyboard_state.modifiers().alt()
+    }
+    // cursor movement since the previous on_render, scaled by the sensitivity and flipped by invert_y
     pub fn mouse_change(&self) -> [f32; 2] {
         self.mouse_state.mouse_delta
     }
+    // all raw mouse motion since the previous on_render, scaled and flipped like mouse_change. no motion is lost at low frame
+    // rates and it keeps working when the cursor hits the window edge, which makes it the better fit for cameras
+    pub fn raw_mouse_change(&self) -> [f32; 2] {
+        self.mouse_state.raw_mouse_delta
+    }
     pub fn any_change(&self) -> bool {
         self.keyboard_state.any_down()
             || self.mouse_state.mouse_delta[0] != 0.0
             || self.mouse_state.mouse_delta[1] != 0.0
+            || self.mouse_state.raw_mouse_delta[0] != 0.0
+            || self.mouse_state.raw_mouse_delta[1] != 0.0
     }
 }
@@ -1,23 +1,47 @@
 use crate::window::input::ButtonState;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
-#[derive(Default)]
+// two presses of the same button within this time count as a double click
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(300);
+// the mouse has to move this many pixels while a button is down before it counts as dragging
+const DRAG_THRESHOLD: f32 = 3.0;
+
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct MouseState {
     pub mouse_location: [f32; 2],
+    // summed cursor movement of all CursorMoved events since the last frame
     pub mouse_delta: [f32; 2],
+    // summed raw device motion since the last frame, unaffected by cursor clamping and os acceleration
+    pub raw_mouse_delta: [f32; 2],
     pub scroll_location: f32,
     pub scroll_delta: f32,
+    pub drag_delta: [f32; 2],
     left: ButtonState,
     right: ButtonState,
+    // only used to detect double clicks, which are stored in the flags below
+    #[serde(skip)]
+    last_left_press: Option<Instant>,
+    #[serde(skip)]
+    last_right_press: Option<Instant>,
+    left_double_click: bool,
+    right_double_click: bool,
 }
 impl MouseState {
     pub fn new() -> MouseState {
         MouseState {
             mouse_location: [0.0, 0.0],
             mouse_delta: [0.0, 0.0],
+            raw_mouse_delta: [0.0, 0.0],
             scroll_location: 0.0,
             scroll_delta: 0.0,
+            drag_delta: [0.0, 0.0],
             left: ButtonState::Up,
             right: ButtonState::Up,
+            last_left_press: None,
+            last_right_press: None,
+            left_double_click: false,
+            right_double_click: false,
         }
     }
     pub fn update(&mut self) {
@@ -33,15 +57,27 @@ impl MouseState {
         if self.right == ButtonState::Released {
             self.right = ButtonState::Up;
         }
+        self.left_double_click = false;
+        self.right_double_click = false;
+    }
+    pub fn moved(&mut self, delta: [f32; 2]) {
+        if bool::from(self.left) || bool::from(self.right) {
+            self.drag_delta[0] += delta[0];
+            self.drag_delta[1] += delta[1];
+        }
     }
     pub fn left_button_pressed(&mut self) {
         self.left = ButtonState::Pressed;
+        self.drag_delta = [0.0, 0.0];
+        self.left_double_click = is_double_click(&mut self.last_left_press);
     }
     pub fn left_button_released(&mut self) {
         self.left = ButtonState::Released
     }
     pub fn right_button_pressed(&mut self) {
         self.right = ButtonState::Pressed;
+        self.drag_delta = [0.0, 0.0];
+        self.right_double_click = is_double_click(&mut self.last_right_press);
     }
     pub fn right_button_released(&mut self) {
         self.right = ButtonState::Released
@@ -52,4 +88,26 @@ impl MouseState {
     pub fn get_right_button(&self) -> ButtonState {
         self.right
     }
+    pub fn left_double_clicked(&self) -> bool {
+        self.left_double_click
+    }
+    pub fn right_double_clicked(&self) -> bool {
+        self.right_double_click
+    }
+    // a button is held and the mouse moved far enough since it was pressed
+    pub fn dragging(&self) -> bool {
+        (bool::from(self.left) || bool::from(self.right))
+            && self.drag_delta[0].hypot(self.drag_delta[1]) > DRAG_THRESHOLD
+    }
+}
+
+fn is_double_click(last_press: &mut Option<Instant>) -> bool {
+    let now = Instant::now();
+    match last_press.take() {
+        Some(last) if now.duration_since(last) < DOUBLE_CLICK_TIME => true,
+        _ => {
+            *last_press = Some(now);
+            false
+        }
+    }
 }
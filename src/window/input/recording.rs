@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::Input;
+
+// the input on_render saw in one frame, together with the dt it got
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub dt: f32,
+    pub input: Input,
+}
+
+// writes one json line per frame, flushed on drop
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    pub fn new(path: &str) -> Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("can't create input recording {}", path))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+    pub fn record(&mut self, dt: f32, input: &Input) -> Result<()> {
+        let frame = InputFrame {
+            dt,
+            input: input.clone(),
+        };
+        serde_json::to_writer(&mut self.writer, &frame)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+// feeds the frames of a recording back one by one
+pub struct InputReplay {
+    frames: VecDeque<InputFrame>,
+}
+
+impl InputReplay {
+    pub fn new(path: &str) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("can't open input recording {}", path))?;
+        let frames = BufReader::new(file)
+            .lines()
+            .enumerate()
+            .map(|(line_number, line)| {
+                serde_json::from_str(&line?).with_context(|| {
+                    format!("invalid frame on line {} of {}", line_number + 1, path)
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { frames })
+    }
+    pub fn next_frame(&mut self) -> Option<InputFrame> {
+        self.frames.pop_front()
+    }
+    pub fn frames_left(&self) -> usize {
+        self.frames.len()
+    }
+}
@@ -1,12 +1,16 @@
 use crate::CoGr;
+use crate::CoGrConfig;
 use crate::Input;
+use crate::InputRecorder;
+use crate::InputReplay;
+use crate::WindowConfig;
 use anyhow::Result;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
 use winit::dpi::PhysicalPosition;
-use winit::event::{Event, WindowEvent};
+use winit::event::{DeviceEvent, Event, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::WindowBuilder;
 
@@ -14,20 +18,62 @@ pub trait Game: Sized {
     fn on_init(gpu: &mut CoGr) -> Result<Self>;
     fn on_tick(&mut self, gpu: &mut CoGr, dt: f32) -> Result<()>;
     fn on_render(&mut self, gpu: &mut CoGr, input: &Input, dt: f32) -> Result<()>;
+    // key which pauses and resumes the game. while paused on_tick isn't called and on_render gets a dt of 0
+    fn pause_key() -> Option<VirtualKeyCode> {
+        Some(VirtualKeyCode::Pause)
+    }
+    // key which advances a paused game by exactly one on_tick and on_render
+    fn step_key() -> Option<VirtualKeyCode> {
+        Some(VirtualKeyCode::Period)
+    }
+    // dt used for a step, the real elapsed frame time is used when this is None
+    fn step_dt() -> Option<f32> {
+        None
+    }
+    // dt of every frame, so CoGr::time and the animations depending on it are the same in every run regardless of
+    // the frame rate. meant for benchmarks and recordings, the real elapsed frame time is used when this is None
+    fn fixed_dt() -> Option<f32> {
+        None
+    }
+    // file the input and dt of every rendered frame are written to, so the session can be reproduced with
+    // replay_input
+    fn record_input() -> Option<String> {
+        None
+    }
+    // recording made with record_input which replaces the live keyboard and mouse input and the frame dt, the game
+    // exits after the last frame. the regular on_tick loop keeps using the real clock, combine this with a Game that
+    // only ticks through steps for fully deterministic runs
+    fn replay_input() -> Option<String> {
+        None
+    }
+    // stop rendering while the window is minimized or covered, the loop then only wakes up to handle events and
+    // ticks. saves power and avoids the errors of an outdated surface while minimized
+    fn pause_rendering_when_occluded() -> bool {
+        false
+    }
+    // fullscreen mode of the window, borderless at native resolution by default
+    fn window_config() -> WindowConfig {
+        WindowConfig::default()
+    }
+    // wgpu validation and api tracing for debugging rendering bugs, all off by default
+    fn gpu_config() -> CoGrConfig {
+        CoGrConfig::default()
+    }
+    // max level of the stdout logger main_loop_run installs. return None when the app sets up its own subscriber,
+    // an already installed subscriber is never replaced
+    fn log_level() -> Option<Level> {
+        Some(Level::INFO)
+    }
 }
 
 pub fn main_loop_run<T>(ticks_per_s: f32) -> Result<()>
 where
     T: 'static + Game,
 {
-    let subscriber = FmtSubscriber::builder()
-        // all spans/events with a level higher than TRACE (e.g, debug, info, warn, etc.)
-        // will be written to stdout.
-        .with_max_level(Level::TRACE)
-        // completes the builder.
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+    if let Some(level) = T::log_level() {
+        // fails when a global subscriber already exists, which then keeps receiving the events
+        let _ = FmtSubscriber::builder().with_max_level(level).try_init();
+    }
     puffin::set_scopes_on(true);
     let event_loop = EventLoop::new();
     let monitor = event_loop
@@ -35,16 +81,22 @@ where
         .expect("We don't support having no monitors");
     let window_builder = WindowBuilder::new()
         .with_resizable(false)
-        .with_fullscreen(Some(winit::window::Fullscreen::Borderless(Some(monitor))));
+        .with_fullscreen(Some(T::window_config().fullscreen(monitor)?));
     let window = Arc::new(
         window_builder
             .build(&event_loop)
             .expect("unable to build window"),
     );
     let mut window_input = Input::new();
+    let mut input_recorder = T::record_input()
+        .map(|path| InputRecorder::new(&path))
+        .transpose()?;
+    let mut input_replay = T::replay_input()
+        .map(|path| InputReplay::new(&path))
+        .transpose()?;
     let mut on_tick_timer = Instant::now();
     let mut on_render_timer = Instant::now();
-    let mut gpu = CoGr::new(&window, &event_loop)?;
+    let mut gpu = CoGr::with_config(&window, &event_loop, &T::gpu_config())?;
     let mut game = T::on_init(&mut gpu)?;
 
     event_loop.run(move |event, _, control_flow| {
@@ -77,19 +129,84 @@ where
                     WindowEvent::KeyboardInput { input, .. } => {
                         window_input.update_keyboard_input(input, control_flow);
                     }
+                    WindowEvent::ModifiersChanged(modifiers) => {
+                        window_input.update_modifiers(modifiers);
+                    }
                     WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
 
                     _ => {}
                 }
             }
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } => {
+                window_input.update_mouse_motion(&delta);
+            }
             Event::RedrawRequested(_) => {
                 puffin::profile_scope!("Render");
                 puffin::GlobalProfiler::lock().new_frame();
-                let dt = on_render_timer.elapsed().as_secs_f32();
+                let replayed_dt = match input_replay.as_mut() {
+                    Some(replay) => match replay.next_frame() {
+                        Some(frame) => {
+                            window_input = frame.input;
+                            Some(frame.dt)
+                        }
+                        None => {
+                            println!("input replay finished");
+                            *control_flow = ControlFlow::Exit;
+                            return;
+                        }
+                    },
+                    None => None,
+                };
+                if let Some(pause_key) = T::pause_key() {
+                    if window_input.keyboard_state.just_pressed(pause_key) {
+                        gpu.set_paused(!gpu.is_paused());
+                    }
+                }
+                let step = gpu.is_paused()
+                    && T::step_key()
+                        .is_some_and(|key| window_input.keyboard_state.just_pressed(key));
+                let frame_dt = T::fixed_dt().unwrap_or(on_render_timer.elapsed().as_secs_f32());
+                let dt = if let Some(dt) = replayed_dt {
+                    dt
+                } else if step {
+                    T::step_dt().unwrap_or(frame_dt)
+                } else if gpu.is_paused() {
+                    0.0
+                } else {
+                    frame_dt
+                };
                 on_render_timer = Instant::now();
+                let recorded = input_recorder
+                    .as_mut()
+                    .map(|recorder| recorder.record(dt, &window_input));
+                if let Some(Err(err)) = recorded {
+                    println!("{}", err);
+                    input_recorder = None;
+                }
+                if step || !gpu.is_paused() {
+                    gpu.advance_clock(dt);
+                }
+                if step {
+                    puffin::profile_scope!("Tick");
+                    if let Err(err) = game.on_tick(&mut gpu, dt) {
+                        println!("{}", err);
+                        *control_flow = ControlFlow::Exit;
+                    }
+                }
                 match game.on_render(&mut gpu, &window_input, dt) {
                     Ok(_) => {
                         window_input.update();
+                        match gpu.capture_turntable_frame() {
+                            Ok(true) => *control_flow = ControlFlow::Exit,
+                            Ok(false) => {}
+                            Err(err) => {
+                                println!("{}", err);
+                                *control_flow = ControlFlow::Exit;
+                            }
+                        }
                     }
                     Err(err) => {
                         println!("{}", err);
@@ -100,10 +217,22 @@ where
             Event::MainEventsCleared => {
                 // RedrawRequested will only trigger once, unless we manually
                 // request it.
-                window.request_redraw();
+                let exiting = matches!(control_flow, ControlFlow::ExitWithCode(_));
+                if T::pause_rendering_when_occluded() && gpu.is_occluded() {
+                    if !exiting {
+                        control_flow.set_wait_timeout(Duration::from_millis(100));
+                    }
+                } else {
+                    if !exiting {
+                        control_flow.set_poll();
+                    }
+                    window.request_redraw();
+                }
             }
             _ => {
-                if on_tick_timer.elapsed().as_secs_f32() * ticks_per_s > 1f32 {
+                if gpu.is_paused() {
+                    on_tick_timer = Instant::now();
+                } else if on_tick_timer.elapsed().as_secs_f32() * ticks_per_s > 1f32 {
                     puffin::profile_scope!("Tick");
                     if let Err(err) = game.on_tick(&mut gpu, on_tick_timer.elapsed().as_secs_f32())
                     {
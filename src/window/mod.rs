@@ -1,4 +1,8 @@
 mod input;
 mod main_loop;
+mod sketch;
+mod window_config;
 pub use input::*;
 pub use main_loop::*;
+pub use sketch::*;
+pub use window_config::*;
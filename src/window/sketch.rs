@@ -0,0 +1,121 @@
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Result};
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    main_loop_run, CoGr, DrawEncoder, Game, Input, Pipeline, ResourceHandle, TextureFormat,
+    TextureRes,
+};
+
+// put in front of every sketch shader, so it can read sketch.time and the others without declaring them.
+// the layout matches SketchConstants
+pub const SKETCH_WGSL: &str = include_str!("sketch.wgsl");
+
+// the push constants every sketch shader gets, 48 bytes:
+// offset 0  mouse: vec4<f32>       cursor position in pixels of the output texture in xy, z and w are 1 while the
+//                                  left and right button are held down
+// offset 16 resolution: vec2<u32>  size of the output texture in pixels
+// offset 24 time: f32              seconds since the start, see CoGr::time
+// offset 28 time_delta: f32        seconds since the previous frame of the sketch
+// offset 32 frame: u32             frames drawn by the sketch so far
+#[repr(C)]
+#[derive(Pod, Zeroable, Copy, Clone, Debug)]
+pub struct SketchConstants {
+    pub mouse: [f32; 4],
+    pub resolution: [u32; 2],
+    pub time: f32,
+    pub time_delta: f32,
+    pub frame: u32,
+    _padding: [u32; 3],
+}
+
+// a shadertoy like harness: the main entry point of the shader runs once per pixel of a full resolution output
+// texture at binding 0, which is shown on screen every frame. the shader gets SKETCH_WGSL prepended
+pub struct Sketch {
+    pub output: ResourceHandle,
+    pub pipeline: Pipeline,
+    last_time: f32,
+    frame: u32,
+}
+
+impl Sketch {
+    pub fn new(gpu: &mut CoGr, shader_file: &str) -> Result<Self> {
+        let output = gpu.texture(
+            "sketch_output",
+            TextureRes::FullRes,
+            TextureFormat::Rgba8Unorm,
+        );
+        let pipeline = gpu
+            .pipeline_builder(shader_file)
+            .bindings(&[&output])
+            .prelude(SKETCH_WGSL)
+            .build()?;
+        Ok(Self {
+            output,
+            pipeline,
+            last_time: 0.0,
+            frame: 0,
+        })
+    }
+    // runs the shader and shows the output, ui can still be drawn with the encoder afterwards
+    pub fn draw(&mut self, encoder: &mut DrawEncoder, input: &Input) -> Result<()> {
+        let gpu = &encoder.gpu_context;
+        let (width, height) = gpu.render_size();
+        let render_scale = gpu.render_scale();
+        let [mouse_x, mouse_y] = input.mouse_state.mouse_location;
+        let time = gpu.time();
+        let constants = SketchConstants {
+            mouse: [
+                mouse_x * render_scale,
+                mouse_y * render_scale,
+                bool::from(input.mouse_state.get_left_button()) as u32 as f32,
+                bool::from(input.mouse_state.get_right_button()) as u32 as f32,
+            ],
+            resolution: [width, height],
+            time,
+            time_delta: time - self.last_time,
+            frame: self.frame,
+            _padding: [0; 3],
+        };
+        self.last_time = time;
+        self.frame += 1;
+        let work_groups = encoder.work_groups_for_texture(&mut self.pipeline, &self.output)?;
+        encoder.dispatch_pipeline_with_constants(
+            &mut self.pipeline,
+            work_groups,
+            &[&self.output],
+            &constants,
+        )?;
+        encoder.to_screen(&self.output)
+    }
+}
+
+// the shader run_sketch was called with, Game::on_init has no way to take it as argument
+static SKETCH_FILE: OnceLock<String> = OnceLock::new();
+
+struct SketchGame(Sketch);
+
+impl Game for SketchGame {
+    fn on_init(gpu: &mut CoGr) -> Result<Self> {
+        let shader_file = SKETCH_FILE.get().expect("run_sketch sets the shader file");
+        Ok(SketchGame(Sketch::new(gpu, shader_file)?))
+    }
+
+    fn on_render(&mut self, gpu: &mut CoGr, input: &Input, _dt: f32) -> Result<()> {
+        let mut encoder = gpu.get_encoder_for_draw()?;
+        self.0.draw(&mut encoder, input)
+    }
+
+    fn on_tick(&mut self, _gpu: &mut CoGr, _dt: f32) -> Result<()> {
+        Ok(())
+    }
+}
+
+// opens a window that runs a Sketch with the shader until it's closed, the shader hot reloads like any pipeline
+pub fn run_sketch(shader_file: &str) -> Result<()> {
+    SKETCH_FILE
+        .set(shader_file.to_string())
+        .map_err(|_| anyhow!("run_sketch can only be called once"))?;
+    main_loop_run::<SketchGame>(60.0)
+}
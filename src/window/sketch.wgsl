@@ -0,0 +1,11 @@
+// the push constants of a Sketch, has to match SketchConstants in sketch.rs
+struct Sketch {
+    // cursor in pixels in xy, z and w are 1 while the left and right button are held down
+    mouse: vec4<f32>,
+    resolution: vec2<u32>,
+    time: f32,
+    time_delta: f32,
+    frame: u32,
+}
+
+var<push_constant> sketch: Sketch;
@@ -0,0 +1,58 @@
+use anyhow::{anyhow, Result};
+use winit::monitor::{MonitorHandle, VideoMode};
+use winit::window::Fullscreen;
+
+// a video mode for exclusive fullscreen, the refresh rate is in millihertz like winit reports it.
+// without a refresh rate the highest one the monitor supports at this resolution is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoModeRequest {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate_millihertz: Option<u32>,
+}
+
+// how main_loop_run creates the window
+#[derive(Debug, Clone, Default)]
+pub struct WindowConfig {
+    // exclusive fullscreen in this video mode, borderless fullscreen at native resolution when None
+    pub video_mode: Option<VideoModeRequest>,
+}
+
+impl WindowConfig {
+    pub(crate) fn fullscreen(&self, monitor: MonitorHandle) -> Result<Fullscreen> {
+        match &self.video_mode {
+            Some(request) => Ok(Fullscreen::Exclusive(find_video_mode(&monitor, request)?)),
+            None => Ok(Fullscreen::Borderless(Some(monitor))),
+        }
+    }
+}
+
+// the video mode of the monitor matching the request, errors with the supported modes when there is none
+pub fn find_video_mode(monitor: &MonitorHandle, request: &VideoModeRequest) -> Result<VideoMode> {
+    monitor
+        .video_modes()
+        .filter(|mode| {
+            mode.size().width == request.width
+                && mode.size().height == request.height
+                && request
+                    .refresh_rate_millihertz
+                    .map_or(true, |rate| mode.refresh_rate_millihertz() == rate)
+        })
+        .max_by_key(|mode| (mode.refresh_rate_millihertz(), mode.bit_depth()))
+        .ok_or_else(|| {
+            anyhow!(
+                "{:?} isn't supported by monitor {}, it supports {:?}",
+                request,
+                monitor.name().unwrap_or_default(),
+                monitor
+                    .video_modes()
+                    .map(|mode| format!(
+                        "{}x{}@{}mHz",
+                        mode.size().width,
+                        mode.size().height,
+                        mode.refresh_rate_millihertz()
+                    ))
+                    .collect::<Vec<_>>()
+            )
+        })
+}